@@ -0,0 +1,125 @@
+//! Converts the NES 2.0 XML game database into a Rust table, included by `cartridge::database`.
+//!
+//! The bundled `data/nes20db.xml` only covers the test ROMs. Point `SABI_NES_GAME_DATABASE`
+//! at the full `nes20db.xml` to correct the headers of commercial games.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const DATABASE_PATH: &str = "data/nes20db.xml";
+const DATABASE_PATH_VARIABLE: &str = "SABI_NES_GAME_DATABASE";
+
+struct Game {
+    title: String,
+    crc32: u32,
+    sha1: [u8; 20],
+    fields: String,
+}
+
+fn main() {
+    println!("cargo::rerun-if-env-changed={DATABASE_PATH_VARIABLE}");
+    let database_path = env::var(DATABASE_PATH_VARIABLE).unwrap_or(DATABASE_PATH.to_string());
+    println!("cargo::rerun-if-changed={database_path}");
+
+    let xml = fs::read_to_string(&database_path).unwrap_or_else(|error| {
+        panic!("failed to read the game database {database_path}: {error}")
+    });
+    let document = roxmltree::Document::parse(&xml).expect("game database is not valid XML");
+
+    let mut games: Vec<_> = document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("game"))
+        .map(parse_game)
+        .collect();
+    games.sort_by_key(|game| game.crc32);
+
+    let mut table = String::from("static GAMES: &[GameEntry] = &[\n");
+    for game in games {
+        writeln!(
+            table,
+            "    GameEntry {{ title: {:?}, crc32: {:#010x}, sha1: {:?}, {} }},",
+            game.title, game.crc32, game.sha1, game.fields
+        )
+        .unwrap();
+    }
+    table.push_str("];\n");
+
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("rom_database.rs");
+    fs::write(out_path, table).expect("failed to write the game database table");
+}
+
+fn parse_game(game: roxmltree::Node) -> Game {
+    let title = game
+        .children()
+        .find(roxmltree::Node::is_comment)
+        .and_then(|comment| comment.text())
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    let attribute = |element: &str, name: &str| {
+        game.children()
+            .find(|node| node.has_tag_name(element))
+            .and_then(|node| node.attribute(name))
+    };
+    let number = |element: &str, name: &str| -> u64 {
+        attribute(element, name).map_or(0, |value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("{title}: invalid <{element} {name}=\"{value}\">"))
+        })
+    };
+
+    let rom_crc32 = attribute("rom", "crc32").unwrap_or_else(|| panic!("{title}: missing <rom>"));
+    let rom_sha1 = attribute("rom", "sha1").unwrap_or_else(|| panic!("{title}: missing <rom>"));
+    let crc32 = u32::from_str_radix(rom_crc32, 16).expect("invalid CRC32");
+    let sha1 = std::array::from_fn(|i| {
+        u8::from_str_radix(&rom_sha1[2 * i..2 * i + 2], 16).expect("invalid SHA-1")
+    });
+
+    let mirroring = match attribute("pcb", "mirroring") {
+        Some("H") => "Some(MirroringType::Horizontal)",
+        Some("V") => "Some(MirroringType::Vertical)",
+        Some("4") => "Some(MirroringType::FourScreen)",
+        // Mapper-controlled or one-screen
+        _ => "None",
+    };
+    let timing = match number("console", "region") {
+        0 => "TimingRegion::Ntsc",
+        1 => "TimingRegion::Pal",
+        2 => "TimingRegion::MultiRegion",
+        _ => "TimingRegion::Dendy",
+    };
+    let console_type = match number("console", "type") {
+        0 => "ConsoleType::Nes".to_string(),
+        1 => "ConsoleType::VsSystem".to_string(),
+        2 => "ConsoleType::Playchoice10".to_string(),
+        extended => format!("ConsoleType::Extended({extended})"),
+    };
+
+    let fields = format!(
+        "prg_rom_size: {}, chr_rom_size: {}, prg_ram_size: {}, prg_nvram_size: {}, \
+         chr_ram_size: {}, chr_nvram_size: {}, mapper_id: {}, submapper: {}, \
+         mirroring: {mirroring}, has_battery: {}, timing: {timing}, \
+         console_type: {console_type}, expansion_device: {}",
+        number("prgrom", "size"),
+        number("chrrom", "size"),
+        number("prgram", "size"),
+        number("prgnvram", "size"),
+        number("chrram", "size"),
+        number("chrnvram", "size"),
+        number("pcb", "mapper"),
+        number("pcb", "submapper"),
+        number("pcb", "battery") != 0,
+        number("expansion", "type"),
+    );
+
+    Game {
+        title,
+        crc32,
+        sha1,
+        fields,
+    }
+}
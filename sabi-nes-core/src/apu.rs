@@ -6,11 +6,14 @@ use crate::apu::channels::triangle_channel::TriangleChannel;
 use crate::apu::frame_counter::{FrameCounter, FrameSignal};
 use crate::bus::DmaOperation;
 use crate::{Address, Byte};
+
+pub use expansion_audio::ExpansionAudio;
 use once_cell::sync::Lazy;
 use std::mem;
 
 mod apu_flags;
 mod channels;
+mod expansion_audio;
 mod frame_counter;
 
 // NES CPU runs at ~1.789773 MHz. We output at 44.1 kHz.
@@ -19,6 +22,10 @@ const CPU_CLOCK: f32 = 1_789_773.0;
 const SAMPLE_RATE: f32 = 44_100.0;
 const CYCLES_PER_SAMPLE: f32 = CPU_CLOCK / SAMPLE_RATE;
 
+// Mixer output of a single square channel at full volume (15), used as the
+// reference level for expansion audio chips.
+const SQUARE_FULL_SCALE: f32 = 95.88 / (8128.0 / 15.0 + 100.0);
+
 // First-order IIR filter coefficients derived from the real NES hardware.
 // All use the matched-z formula: α = exp(-2π * fc / Fs).
 //
@@ -110,6 +117,27 @@ impl Default for Apu {
 }
 
 impl Apu {
+    /// Power-on state: every channel silenced and $4017 written with $00.
+    /// Audio samples not yet drained are kept.
+    pub fn power_on(&mut self) {
+        let samples = mem::take(&mut self.samples);
+        *self = Self {
+            samples,
+            ..Self::default()
+        };
+    }
+
+    /// Reset button: $4015 is cleared, silencing every channel, and $4017 is written
+    /// again with the last value written to it
+    pub fn reset(&mut self, dma_operation: DmaOperation) {
+        let status_open_bus = self.status_open_bus;
+        self.set_status_register(Byte::new(0x00));
+        self.status_open_bus = status_open_bus;
+        if let Some(signal) = self.frame_counter.reset(dma_operation) {
+            self.dispatch_frame_signal(signal);
+        }
+    }
+
     pub fn write_frame_counter(&mut self, value: Byte, dma_operation: DmaOperation) {
         if let Some(signal) = self.frame_counter.write(value, dma_operation) {
             self.dispatch_frame_signal(signal);
@@ -117,7 +145,7 @@ impl Apu {
     }
 
     pub fn is_irq_pending(&self) -> bool {
-        self.frame_counter.is_irq_pending() || self.dmc.irq_pending
+        self.frame_counter.is_irq_line_asserted() || self.dmc.irq_pending
     }
 
     pub fn set_status_register(&mut self, byte: Byte) {
@@ -211,7 +239,15 @@ impl Apu {
     }
 
     /// Advance the APU by exactly one CPU cycle with known parity.
-    pub fn tick_one(&mut self, dma_operation: DmaOperation) -> Option<Address> {
+    /// The cartridge sound chip (if any) is clocked and mixed alongside the internal channels.
+    pub fn tick_one(
+        &mut self,
+        dma_operation: DmaOperation,
+        mut expansion_audio: Option<&mut dyn ExpansionAudio>,
+    ) -> Option<Address> {
+        if let Some(chip) = expansion_audio.as_deref_mut() {
+            chip.tick();
+        }
         self.square_channel1.tick();
         self.square_channel2.tick();
         self.triangle_channel.tick();
@@ -226,7 +262,7 @@ impl Apu {
         self.cycle_accumulator += 1.0;
         if self.cycle_accumulator >= CYCLES_PER_SAMPLE {
             self.cycle_accumulator -= CYCLES_PER_SAMPLE;
-            let mixed_output = self.mix();
+            let mixed_output = self.mix(expansion_audio.as_deref());
             self.samples.push(self.filter.filter(mixed_output));
         }
 
@@ -237,7 +273,7 @@ impl Apu {
     /// DMA requests from the DMC are ignored here; use `tick_one` directly for DMA handling.
     pub fn tick(&mut self, cycles: usize) {
         for _ in 0..cycles {
-            self.tick_one(DmaOperation::Get);
+            self.tick_one(DmaOperation::Get, None);
         }
     }
 
@@ -249,7 +285,7 @@ impl Apu {
     /// in [NESDev wiki page][nes_dev].
     ///
     /// [nes_dev]: https://www.nesdev.org/wiki/APU_Mixer
-    fn mix(&self) -> f32 {
+    fn mix(&self, expansion_audio: Option<&dyn ExpansionAudio>) -> f32 {
         let square1_output = self.square_channel1.output().as_float();
         let square2_output = self.square_channel2.output().as_float();
         let square_sum = square1_output + square2_output;
@@ -269,7 +305,11 @@ impl Apu {
             159.79 / (1.0 / tnd_sum + 100.0)
         };
 
-        square_out + tnd_out
+        let expansion_out = expansion_audio
+            .map(|chip| chip.output() * chip.relative_volume() * SQUARE_FULL_SCALE)
+            .unwrap_or_default();
+
+        square_out + tnd_out + expansion_out
     }
 }
 
@@ -332,7 +372,7 @@ mod tests {
         // Tick until we get a DMA request (should come on first output unit clock = ~54 ticks)
         let mut dma_addr = None;
         for _ in 0..200 {
-            if let Some(addr) = apu.tick_one(DmaOperation::Get) {
+            if let Some(addr) = apu.tick_one(DmaOperation::Get, None) {
                 dma_addr = Some(addr);
                 break;
             }
@@ -346,4 +386,77 @@ mod tests {
         apu.dmc.irq_pending = true;
         assert!(apu.is_irq_pending(), "irq_status should reflect DMC IRQ");
     }
+
+    #[test]
+    fn irq_line_held_through_the_put_cycle_clearing_it() {
+        let mut apu = make_apu();
+        apu.write_frame_counter(Byte::new(0x00), DmaOperation::Get);
+        while !apu.is_irq_pending() {
+            apu.tick_one(DmaOperation::Get, None);
+        }
+        // The flag is set on three consecutive cycles
+        apu.tick(2);
+
+        apu.read_status_register();
+        apu.tick_one(DmaOperation::Put, None);
+        assert_eq!(apu.peek_status_register() & 0x40, 0x00);
+        assert!(
+            apu.is_irq_pending(),
+            "CPU samples the line before the clear"
+        );
+
+        apu.tick_one(DmaOperation::Get, None);
+        assert!(!apu.is_irq_pending());
+    }
+
+    /// Chip holding a constant output level, counting the cycles it was clocked for.
+    struct ConstantChip {
+        level: f32,
+        ticks: usize,
+    }
+
+    impl ExpansionAudio for ConstantChip {
+        fn tick(&mut self) {
+            self.ticks += 1;
+        }
+
+        fn output(&self) -> f32 {
+            self.level
+        }
+
+        fn relative_volume(&self) -> f32 {
+            2.0
+        }
+    }
+
+    #[test]
+    fn expansion_audio_mixed_relative_to_square() {
+        let apu = make_apu();
+        let chip = ConstantChip {
+            level: 0.5,
+            ticks: 0,
+        };
+
+        let silent = apu.mix(None);
+        let mixed = apu.mix(Some(&chip));
+
+        // Half of the chip's full scale, which is twice as loud as a full-volume square
+        assert!((mixed - silent - SQUARE_FULL_SCALE).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn expansion_audio_clocked_with_apu() {
+        let mut apu = make_apu();
+        let mut chip = ConstantChip {
+            level: 1.0,
+            ticks: 0,
+        };
+
+        for _ in 0..100 {
+            apu.tick_one(DmaOperation::Get, Some(&mut chip));
+        }
+
+        assert_eq!(chip.ticks, 100);
+        assert!(apu.drain_samples().iter().any(|&sample| sample != 0.0));
+    }
 }
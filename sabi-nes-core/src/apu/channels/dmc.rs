@@ -132,7 +132,8 @@ impl Dmc {
     pub fn tick(&mut self) -> Option<Address> {
         let pending = self.dma_state.pending_dma_address.take();
         if self.timer_counter == 0 {
-            self.timer_counter = RATE_TABLE[self.rate_index().as_usize()];
+            // The rate table holds the period in CPU cycles, the reload cycle counts as one of them
+            self.timer_counter = RATE_TABLE[self.rate_index().as_usize()] - 1;
             self.clock_output_unit();
         } else {
             self.timer_counter -= 1;
@@ -266,6 +267,25 @@ mod tests {
         assert_eq!(dmc.output(), 0);
     }
 
+    #[test]
+    fn timer_clocks_the_output_once_per_rate_period() {
+        let mut dmc = make_dmc();
+        dmc.write_flags_and_rate(Byte::new(0x0f)); // 54 CPU cycles
+        dmc.write_direct_load(Byte::new(0));
+        dmc.deliver_sample(Byte::new(0xFF));
+        dmc.output_unit_bits_remaining = 0;
+        dmc.timer_counter = 0;
+
+        dmc.tick();
+        assert_eq!(dmc.output(), 2);
+        for _ in 0..53 {
+            dmc.tick();
+        }
+        assert_eq!(dmc.output(), 2);
+        dmc.tick();
+        assert_eq!(dmc.output(), 4);
+    }
+
     #[test]
     fn sample_address_register() {
         let mut dmc = make_dmc();
@@ -0,0 +1,22 @@
+//! Famicom cartridges can mix their own sound chip into the console audio
+//! (VRC6, VRC7, Namco 163, Sunsoft 5B, FDS, MMC5).
+//!
+//! Each chip is clocked alongside the APU and reports its current output level.
+//! The NESDev wiki pages of the individual chips give their loudness relative to
+//! an APU square channel at full volume, which is what [`ExpansionAudio::relative_volume`]
+//! uses, so chips can be mixed without touching the APU mixer.
+//!
+//! - [Expansion audio](https://www.nesdev.org/wiki/Expansion_audio)
+
+/// Sound chip on the cartridge, exposed by [`crate::cartridge::mappers::Mapper::expansion_audio`].
+pub trait ExpansionAudio {
+    /// Advance the chip by one CPU cycle
+    fn tick(&mut self);
+
+    /// Current output level, normalised to `0.0..=1.0` (full scale of the chip)
+    fn output(&self) -> f32;
+
+    /// Full-scale output of the chip, relative to a single APU square channel
+    /// at full volume (e.g. `1.0` for the MMC5 pulse channels)
+    fn relative_volume(&self) -> f32;
+}
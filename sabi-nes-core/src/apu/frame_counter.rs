@@ -9,6 +9,7 @@ pub struct FrameCounter {
     irq: IrqState,
     pending_irq_clear: bool,
     reset_delay: Byte, // countdown to reset; 0 = no pending reset
+    last_write: Byte,  // rewritten on reset
 }
 
 #[derive(Debug, Default, PartialEq)]
@@ -22,6 +23,8 @@ enum SequencerMode {
 struct IrqState {
     is_inhibited: bool,
     is_pending: bool,
+    // The CPU samples the IRQ line before a $4015 read clear takes effect on the put cycle
+    is_clearing: bool,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -38,6 +41,7 @@ impl FrameCounter {
     /// Any write here resets the sequencer to 0. If bit 7 is set, a half-frame
     /// signal is generated immediately (clocking length counters and envelopes).
     pub fn write(&mut self, value: Byte, dma_operation: DmaOperation) -> Option<FrameSignal> {
+        self.last_write = value;
         self.mode = if value.nth_bit::<7>() {
             SequencerMode::FiveStep
         } else {
@@ -61,11 +65,22 @@ impl FrameCounter {
         (self.mode == SequencerMode::FiveStep).then_some(FrameSignal::HalfFrame)
     }
 
+    /// On reset the last value written to $4017 is written again and the IRQ flag is cleared.
+    pub fn reset(&mut self, dma_operation: DmaOperation) -> Option<FrameSignal> {
+        self.force_clear_irq();
+        self.write(self.last_write, dma_operation)
+    }
+
     /// Returns true if the frame counter IRQ is currently pending.
     pub fn is_irq_pending(&self) -> bool {
         self.irq.is_pending
     }
 
+    /// Whether the IRQ line is asserted
+    pub fn is_irq_line_asserted(&self) -> bool {
+        self.irq.is_pending || self.irq.is_clearing
+    }
+
     /// Schedule a deferred IRQ clear (called when $4015 is read).
     /// The actual clear happens on the next PUT (odd) CPU cycle.
     pub fn clear_irq(&mut self) {
@@ -80,7 +95,9 @@ impl FrameCounter {
     }
 
     pub fn tick(&mut self, dma_operation: DmaOperation) -> Option<FrameSignal> {
+        self.irq.is_clearing = false;
         if dma_operation.is_put() && self.pending_irq_clear {
+            self.irq.is_clearing = self.irq.is_pending;
             self.irq.is_pending = false;
             self.pending_irq_clear = false;
         }
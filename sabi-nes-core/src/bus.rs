@@ -1,15 +1,19 @@
 use crate::apu::Apu;
 use crate::cartridge::Rom;
-use crate::cartridge::mappers::Mapper;
+use crate::cartridge::mappers::{Mapper, MapperHooks};
+use crate::cpu::{BusAccess, BusAccessKind, Clock};
 use crate::input::joypad::Joypad;
-use crate::ppu::{NmiStatus, Ppu};
+use crate::ppu::{NmiStatus, Ppu, PpuPosition, VBLANK_SCANLINE};
 use crate::utils::MirroredAddress;
 use crate::{Address, Byte, Memory};
 use derive_more::IsVariant;
 use log::{debug, trace, warn};
-use std::mem;
 use std::ops::{Deref, DerefMut, Not};
 
+mod ram_init;
+
+pub use ram_init::RamInit;
+
 #[derive(Debug, Default, PartialEq, Copy, Clone, IsVariant)]
 pub enum DmaOperation {
     #[default]
@@ -29,13 +33,6 @@ impl Not for DmaOperation {
 }
 
 impl DmaOperation {
-    pub const fn cycles(&self) -> usize {
-        match self {
-            Self::Get => 513,
-            Self::Put => 514,
-        }
-    }
-
     pub const fn reset_delay(&self) -> Byte {
         match self {
             Self::Get => Byte::new(5),
@@ -44,14 +41,38 @@ impl DmaOperation {
     }
 }
 
+/// DMC sample fetch. After halting the CPU it waits a dummy cycle, then reads on a get cycle.
+#[derive(Debug, Copy, Clone)]
+struct DmcDma {
+    address: Address,
+    // Cycles the CPU has been halted for so far
+    halted_cycles: u8,
+}
+
+/// OAM DMA copying a page to $2004: a read on each get cycle, a write on the put cycle after
+#[derive(Debug, Copy, Clone)]
+struct OamDma {
+    page: Byte,
+    offset: u16,
+    // Byte read on the last get cycle, waiting to be written
+    latch: Option<Byte>,
+    halted: bool,
+}
+
+/// The NTSC master clock drives the CPU at 1/12 and the PPU at 1/4 of its rate
+pub const MASTER_CYCLES_PER_CPU_CYCLE: u64 = 12;
+
 const VRAM_SIZE: usize = 2048;
 const PRG_RAM_SIZE: usize = 8192;
 const RAM: u16 = 0x0000;
 const RAM_MIRRORS_END: u16 = 0x1fff;
 const PPU_REGISTERS_MIRRORS_START: u16 = 0x2008;
 const PPU_REGISTERS_MIRRORS_END: u16 = 0x3fff;
+const EXPANSION_START: u16 = 0x4020;
+const EXPANSION_END: u16 = 0x5fff;
 const PRG_RAM_START: u16 = 0x6000;
 const PRG_RAM_END: u16 = 0x7fff;
+const TRAINER_START: u16 = 0x7000;
 const ROM_START: u16 = 0x8000;
 const ROM_END: u16 = 0xffff;
 
@@ -59,36 +80,124 @@ pub struct Bus {
     cpu_vram: [Byte; VRAM_SIZE],
     prg_ram: [Byte; PRG_RAM_SIZE],
     rom: Rom,
+    // Clock notifications requested by the mapper, cached so the per-cycle
+    // path doesn't need a virtual call for mappers that don't use them.
+    mapper_hooks: MapperHooks,
     ppu: Ppu,
     apu: Apu,
     joypad: Joypad,
     cycles: u64,
     frame_ready: bool,
-    // Extra cycles to consume on the next tick, used for OAM DMA stall.
-    pending_cycles: usize,
     // The CPU data bus retains the last value driven on it. Reads from unmapped
     // addresses return this value instead of driving the bus to zero.
     cpu_open_bus: Byte,
     dma_operation: DmaOperation,
+    // DMAs halt the CPU on its next read and hold it until they're done
+    dmc_dma: Option<DmcDma>,
+    oam_dma: Option<OamDma>,
+    // Address of the CPU read repeated on the last DMA cycle. The controllers only see one
+    // read while it's held, so a repeated read of them doesn't clock them again.
+    held_read: Option<Address>,
+    // Contents of the console and cartridge RAM at power-on
+    ram_init: RamInit,
+    // Battery-backed RAM was restored from a save, which already holds the trainer area
+    battery_ram_loaded: bool,
 }
 
 impl Bus {
     pub fn new(rom: Rom) -> Bus {
-        let ppu = Ppu::new(rom.screen_mirroring);
+        let mapper_hooks = rom.mapper.hooks();
+        let mut ppu = Ppu::new(rom.screen_mirroring);
+        ppu.notify_mapper_fetches(mapper_hooks.contains(MapperHooks::PPU_ADDRESS));
 
-        Bus {
+        let mut bus = Bus {
             cpu_vram: [Byte::default(); VRAM_SIZE],
             prg_ram: [Byte::default(); PRG_RAM_SIZE],
             rom,
+            mapper_hooks,
             ppu,
             apu: Apu::default(),
             joypad: Joypad::default(),
             cycles: 0,
             frame_ready: false,
-            pending_cycles: 0,
             cpu_open_bus: Byte::default(),
             dma_operation: DmaOperation::default(),
+            dmc_dma: None,
+            oam_dma: None,
+            held_read: None,
+            ram_init: RamInit::default(),
+            battery_ram_loaded: false,
+        };
+        bus.load_trainer();
+
+        bus
+    }
+
+    /// What the RAM comes up with from the next power-on, all zeros by default
+    pub fn set_ram_init(&mut self, ram_init: RamInit) {
+        self.ram_init = ram_init;
+    }
+
+    /// Power-cycle every component on the bus. The console RAM and any PRG RAM without
+    /// a battery come up as set by [`Bus::set_ram_init`]; battery-backed RAM keeps its contents.
+    pub fn power_on(&mut self) {
+        let prg_nvram_len = self.prg_nvram_len();
+        let prg_ram = &mut self.prg_ram[prg_nvram_len..];
+        let contents = self.ram_init.contents();
+        for (byte, value) in self.cpu_vram.iter_mut().chain(prg_ram).zip(contents) {
+            *byte = value;
+        }
+        if !self.battery_ram_loaded {
+            self.load_trainer();
+        }
+
+        self.rom.mapper.power_on();
+        self.ppu.power_on();
+        self.apu.power_on();
+        self.joypad = Joypad::default();
+        self.cycles = 0;
+        self.frame_ready = false;
+        self.cpu_open_bus = Byte::default();
+        self.dma_operation = DmaOperation::default();
+        self.dmc_dma = None;
+        self.oam_dma = None;
+        self.held_read = None;
+    }
+
+    /// Press the reset button. RAM is left alone, the APU is silenced and
+    /// the PPU clears its registers and warms up again.
+    pub fn reset(&mut self) {
+        self.ppu.reset();
+        self.apu.reset(self.dma_operation);
+        self.dmc_dma = None;
+        self.oam_dma = None;
+    }
+
+    /// Copy the ROM trainer (if any) into PRG RAM at $7000-$71FF, where patched dumps expect it.
+    fn load_trainer(&mut self) {
+        if let Some(trainer) = &self.rom.trainer {
+            let start = (TRAINER_START - PRG_RAM_START) as usize;
+            for (ram, &byte) in self.prg_ram[start..].iter_mut().zip(trainer) {
+                *ram = byte;
+            }
+        }
+    }
+
+    /// On boards with bus conflicts the PRG ROM keeps driving the data bus during writes,
+    /// so the mapper sees the written value ANDed with the ROM byte.
+    fn resolve_bus_conflict(&mut self, address: Address, value: Byte) -> Byte {
+        let Some(rom_value) = self.rom.read_prg(address - ROM_START) else {
+            return value;
+        };
+        if rom_value != value {
+            debug!(
+                "Bus conflict: wrote ${value:02X} to ${address:04X}, but ROM holds ${rom_value:02X}"
+            );
         }
+        let value = value & rom_value.value();
+        self.cpu_open_bus = value;
+
+        value
     }
 
     /// Advance the emulator by exactly one CPU cycle and toggle cycle parity.
@@ -97,42 +206,116 @@ impl Bus {
         self.dma_operation = !self.dma_operation;
         self.cycles += 1;
 
-        let nmi_before = self.ppu.nmi_status;
-        let mapper = self.rom.mapper.deref();
-        let nmi_after = self.ppu.tick(3, mapper);
-        if let Some(dma_addr) = self.apu.tick_one(dma_operation) {
+        if self.mapper_hooks.contains(MapperHooks::CPU_CYCLE) {
+            self.rom.mapper.on_cpu_cycle();
+        }
+
+        let scanline_before = self.ppu.scanline;
+        let mapper = self.rom.mapper.deref_mut();
+        self.ppu.tick(3, mapper);
+        let expansion_audio = match self.mapper_hooks.contains(MapperHooks::EXPANSION_AUDIO) {
+            true => self.rom.mapper.expansion_audio(),
+            false => None,
+        };
+        if let Some(address) = self.apu.tick_one(dma_operation, expansion_audio) {
             debug_assert!(
-                dma_addr >= 0x8000,
+                address >= 0x8000,
                 "DMC sample address must be in PRG ROM range ($8000–$FFFF)"
             );
-            let saved_open_bus = self.cpu_open_bus;
-            let sample = self.read_byte(dma_addr);
-            self.cpu_open_bus = saved_open_bus; // DMC DMA is not a CPU cycle; don't pollute the open-bus latch
-            self.apu.dmc.deliver_sample(sample);
-            // 4-cycle stall approximation; real hardware uses 3–4 cycles depending
-            // on whether the CPU is in a read or write cycle.
-            self.pending_cycles += 4;
+            self.dmc_dma = Some(DmcDma {
+                address,
+                halted_cycles: 0,
+            });
         }
 
-        if NmiStatus::activated(nmi_before, nmi_after) {
+        // A frame is complete at the start of vblank, whether or not the game enabled NMI
+        if scanline_before != VBLANK_SCANLINE && self.ppu.scanline == VBLANK_SCANLINE {
             self.frame_ready = true;
         }
     }
 
     // TODO?
     pub fn tick(&mut self, cycles: usize) {
-        let total = cycles + mem::take(&mut self.pending_cycles);
-        for _ in 0..total {
+        for _ in 0..cycles {
             self.tick_one();
         }
     }
 
+    /// One cycle of the CPU halted on a read of `cpu_address`. The DMC takes get cycles before
+    /// the OAM DMA, which writes on put cycles. Cycles neither uses (halt, dummy and alignment
+    /// cycles) repeat the CPU's read.
+    fn run_dma_cycle(&mut self, cpu_address: Address) -> Option<BusAccess> {
+        if self.dmc_dma.is_none() && self.oam_dma.is_none() {
+            self.held_read = None;
+            return None;
+        }
+
+        let cycle = self.cycles;
+        let dmc_ready = self.dmc_dma.is_some_and(|dma| dma.halted_cycles >= 2);
+        let oam_latch = self.oam_dma.and_then(|dma| dma.latch);
+        let oam_ready = self
+            .oam_dma
+            .is_some_and(|dma| dma.halted && dma.latch.is_none());
+        let (address, value, kind) = match (self.dma_operation, oam_latch) {
+            (DmaOperation::Get, _) if dmc_ready => {
+                let address = self.dmc_dma.take().unwrap().address;
+                let sample = self.read_byte(address);
+                self.apu.dmc.deliver_sample(sample);
+                (address, sample, BusAccessKind::Read)
+            }
+            (DmaOperation::Get, _) if oam_ready => {
+                let dma = self.oam_dma.as_mut().unwrap();
+                let address = (dma.page.as_address() << 8) + dma.offset;
+                let value = self.read_byte(address);
+                self.oam_dma.as_mut().unwrap().latch = Some(value);
+                (address, value, BusAccessKind::Read)
+            }
+            (DmaOperation::Put, Some(value)) => {
+                let address = Address::new(0x2004);
+                self.write_byte(address, value);
+                let dma = self.oam_dma.as_mut().unwrap();
+                dma.latch = None;
+                dma.offset += 1;
+                if dma.offset == 256 {
+                    self.oam_dma = None;
+                }
+                (address, value, BusAccessKind::Write)
+            }
+            _ => {
+                let is_joypad = matches!(cpu_address.value(), 0x4016 | 0x4017);
+                let value = match is_joypad && self.held_read == Some(cpu_address) {
+                    true => self.cpu_open_bus,
+                    false => self.read_byte(cpu_address),
+                };
+                (cpu_address, value, BusAccessKind::Read)
+            }
+        };
+        self.held_read = match kind == BusAccessKind::Read && address == cpu_address {
+            true => Some(cpu_address),
+            false => None,
+        };
+
+        if let Some(dma) = &mut self.dmc_dma {
+            dma.halted_cycles = dma.halted_cycles.saturating_add(1);
+        }
+        if let Some(dma) = &mut self.oam_dma {
+            dma.halted = true;
+        }
+
+        Some(BusAccess {
+            cycle,
+            address,
+            value,
+            kind,
+        })
+    }
+
     pub fn drain_audio_samples(&mut self) -> Vec<f32> {
         self.apu.drain_samples()
     }
 
     pub fn poll_irq_status(&self) -> bool {
-        self.apu.is_irq_pending()
+        self.apu.is_irq_pending() || self.rom.mapper.is_irq_pending()
     }
 
     pub fn poll_nmi_status(&mut self) -> NmiStatus {
@@ -154,6 +337,20 @@ impl Bus {
         &self.ppu
     }
 
+    /// Master clock cycles elapsed since power-on
+    pub fn master_cycles(&self) -> u64 {
+        self.cycles * MASTER_CYCLES_PER_CPU_CYCLE
+    }
+
+    /// Frames the PPU completed since power-on
+    pub fn frame_count(&self) -> u64 {
+        self.ppu.frame()
+    }
+
+    pub fn ppu_position(&self) -> PpuPosition {
+        self.ppu.position()
+    }
+
     pub fn mapper(&self) -> &dyn Mapper {
         self.rom.mapper.deref()
     }
@@ -162,6 +359,38 @@ impl Bus {
         &mut self.joypad
     }
 
+    /// Contents of the battery-backed memory: PRG RAM (if the board has a battery)
+    /// followed by the mapper's own NVRAM. `None` if nothing survives a power cycle.
+    pub fn battery_ram(&self) -> Option<Vec<Byte>> {
+        let prg_ram = &self.prg_ram[..self.prg_nvram_len()];
+        let nvram = self.rom.mapper.nvram().unwrap_or_default();
+
+        match prg_ram.is_empty() && nvram.is_empty() {
+            true => None,
+            false => Some([prg_ram, nvram].concat()),
+        }
+    }
+
+    /// Bytes at the start of PRG RAM kept alive by the battery, as given by the header
+    fn prg_nvram_len(&self) -> usize {
+        match self.rom.header.has_battery {
+            true => self.rom.header.prg_nvram_size.min(PRG_RAM_SIZE),
+            false => 0,
+        }
+    }
+
+    /// Restore battery-backed memory saved with [`Bus::battery_ram`]
+    pub fn load_battery_ram(&mut self, data: &[Byte]) {
+        let prg_ram_len = data.len().min(self.prg_nvram_len());
+        self.prg_ram[..prg_ram_len].copy_from_slice(&data[..prg_ram_len]);
+        self.battery_ram_loaded |= prg_ram_len > 0;
+        let data = &data[prg_ram_len..];
+        if let Some(nvram) = self.rom.mapper.nvram_mut() {
+            let nvram_len = data.len().min(nvram.len());
+            nvram[..nvram_len].copy_from_slice(&data[..nvram_len]);
+        }
+    }
+
     /// Read a byte without triggering any side effects. Used by the trace/debugger
     /// This method is mostly intended for tests and in the future, for debugger.
     pub fn peek_byte(&self, address: Address) -> Byte {
@@ -191,20 +420,59 @@ impl Bus {
             0x400f => self.apu.noise_channel.len_counter_and_env_restart,
             0x4015 => self.apu.peek_status_register(),
             0x4016 | 0x4017 => Byte::new(0x00),
+            EXPANSION_START..=EXPANSION_END => self
+                .rom
+                .mapper
+                .peek_expansion(address)
+                .unwrap_or(self.cpu_open_bus),
             PRG_RAM_START..=PRG_RAM_END => {
                 let index = (address - PRG_RAM_START).as_usize();
                 self.prg_ram[index]
             }
-            ROM_START..=ROM_END => {
-                let mapped = self.rom.mapper.map_address(address - ROM_START);
-                self.rom.prg_rom[mapped]
-            }
+            ROM_START..=ROM_END => self
+                .rom
+                .read_prg(address - ROM_START)
+                .unwrap_or(self.cpu_open_bus),
             _ => self.cpu_open_bus,
         }
     }
 }
 
+impl Clock for Bus {
+    fn tick(&mut self) {
+        self.tick_one();
+    }
+
+    fn power_on(&mut self) {
+        Bus::power_on(self);
+    }
+
+    fn reset(&mut self) {
+        Bus::reset(self);
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn run_dma_cycle(&mut self, cpu_address: Address) -> Option<BusAccess> {
+        Bus::run_dma_cycle(self, cpu_address)
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        self.poll_nmi_status() == NmiStatus::Active
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.poll_irq_status()
+    }
+}
+
 impl Memory for Bus {
+    fn peek_byte(&self, address: Address) -> Byte {
+        Bus::peek_byte(self, address)
+    }
+
     fn read_byte(&mut self, address: Address) -> Byte {
         let value = match address.value() {
             RAM..=RAM_MIRRORS_END => {
@@ -229,7 +497,7 @@ impl Memory for Bus {
                 value
             }
             0x2007 => {
-                let value = self.ppu.read(self.rom.mapper.deref());
+                let value = self.ppu.read(self.rom.mapper.deref_mut());
                 self.ppu.write_to_open_bus(value);
                 value
             }
@@ -247,14 +515,19 @@ impl Memory for Bus {
             0x4016 => (self.joypad.read() & 0x1F) | (self.cpu_open_bus & 0xE0),
             // TODO: For reads, this is actually Player 2's controller, not frame counter!
             0x4017 => self.cpu_open_bus & 0xE0,
+            // Cartridge expansion area; undriven addresses leave the open-bus value intact.
+            EXPANSION_START..=EXPANSION_END => match self.rom.mapper.read_expansion(address) {
+                Some(value) => value,
+                None => return self.cpu_open_bus,
+            },
             PRG_RAM_START..=PRG_RAM_END => {
                 let index = (address - PRG_RAM_START).as_usize();
                 self.prg_ram[index]
             }
-            ROM_START..=ROM_END => {
-                let mapped_address = self.rom.mapper.map_address(address - ROM_START);
-                self.rom.prg_rom[mapped_address]
-            }
+            ROM_START..=ROM_END => match self.rom.read_prg(address - ROM_START) {
+                Some(value) => value,
+                None => return self.cpu_open_bus,
+            },
             _ => {
                 trace!("Ignored attempt to read address ${address:0X}");
                 return self.cpu_open_bus;
@@ -361,21 +634,20 @@ impl Memory for Bus {
             0x4012 => self.apu.dmc.write_sample_address(value),
             0x4013 => self.apu.dmc.write_sample_length(value),
             0x4014 => {
-                let mut buffer = [Byte::default(); 256];
-                let high = value.as_address() << 8;
-
-                for (offset, byte) in buffer.iter_mut().enumerate() {
-                    // We're iterating over 256 elements, so this conversion is lossless
-                    let offset = offset as u16;
-                    *byte = self.read_byte(high + offset);
-                }
-
-                self.ppu.write_to_oam_dma(&buffer);
-                self.pending_cycles += self.dma_operation.cycles();
+                self.oam_dma = Some(OamDma {
+                    page: value,
+                    offset: 0,
+                    latch: None,
+                    halted: false,
+                });
             }
             0x4015 => self.apu.set_status_register(value),
             0x4016 => self.joypad.write(value),
             0x4017 => self.apu.write_frame_counter(value, self.dma_operation),
+            // 0x4020-0x5fff
+            EXPANSION_START..=EXPANSION_END => {
+                self.rom.mapper.write_expansion(address, value);
+            }
             // 0x6000-0x7fff
             PRG_RAM_START..=PRG_RAM_END => {
                 let index = (address - PRG_RAM_START).as_usize();
@@ -383,6 +655,10 @@ impl Memory for Bus {
             }
             // 0x8000-0xffff
             ROM_START..=ROM_END => {
+                let value = match self.mapper_hooks.contains(MapperHooks::BUS_CONFLICTS) {
+                    true => self.resolve_bus_conflict(address, value),
+                    false => value,
+                };
                 self.rom.mapper.write(address, value);
             }
             _ => {
@@ -396,8 +672,7 @@ impl Memory for Bus {
 mod tests {
     use super::*;
     use crate::cartridge::mappers::Nrom128;
-    use crate::cartridge::{CHR_ROM_BANK_SIZE, MirroringType, PRG_ROM_BANK_SIZE};
-    use assert_matches::assert_matches;
+    use crate::cartridge::{CHR_ROM_BANK_SIZE, MirroringType, PRG_ROM_BANK_SIZE, TRAINER_SIZE};
 
     fn test_bus() -> Bus {
         Bus::new(test_rom())
@@ -441,42 +716,410 @@ mod tests {
         assert_eq!(bus.read_byte(Address::new(0x9000)), 0x10);
     }
 
+    /// NROM-like mapper exposing one register at $5000 and nothing else in the expansion area.
+    #[derive(Default)]
+    struct ExpansionMapper {
+        register: Byte,
+    }
+
+    impl Mapper for ExpansionMapper {
+        fn map_address(&self, address: Address) -> usize {
+            address.as_usize() % PRG_ROM_BANK_SIZE
+        }
+        fn write(&mut self, _: Address, _: Byte) {}
+        fn load_chr(&mut self, _: Vec<Byte>) {}
+        fn read_chr(&self, _: Address) -> Byte {
+            Byte::default()
+        }
+        fn write_chr(&mut self, _: Address, _: Byte) {}
+        fn read_expansion(&mut self, address: Address) -> Option<Byte> {
+            self.peek_expansion(address)
+        }
+        fn peek_expansion(&self, address: Address) -> Option<Byte> {
+            (address == 0x5000).then_some(self.register)
+        }
+        fn write_expansion(&mut self, address: Address, value: Byte) {
+            if address == 0x5000 {
+                self.register = value;
+            }
+        }
+    }
+
+    fn expansion_bus() -> Bus {
+        Bus::new(Rom::new(
+            vec![0x10.into(); PRG_ROM_BANK_SIZE],
+            vec![0x20.into(); CHR_ROM_BANK_SIZE],
+            Box::new(ExpansionMapper::default()),
+            MirroringType::Horizontal,
+        ))
+    }
+
+    #[test]
+    fn expansion_area_routed_to_mapper() {
+        let mut bus = expansion_bus();
+        bus.write_byte(Address::new(0x5000), Byte::new(0x5a));
+
+        assert_eq!(bus.read_byte(Address::new(0x5000)), 0x5a);
+        assert_eq!(bus.peek_byte(Address::new(0x5000)), 0x5a);
+    }
+
+    #[test]
+    fn undriven_expansion_area_reads_open_bus() {
+        let mut bus = expansion_bus();
+        // Leave a recognisable value on the data bus
+        bus.read_byte(Address::new(0x9000));
+
+        assert_eq!(bus.read_byte(Address::new(0x4020)), 0x10);
+        assert_eq!(bus.read_byte(Address::new(0x5fff)), 0x10);
+    }
+
+    /// Mapper with a CPU cycle counter raising IRQ every 100 cycles, like the VRC IRQ counters.
+    #[derive(Default)]
+    struct CycleCountingMapper {
+        cycles: usize,
+    }
+
+    impl Mapper for CycleCountingMapper {
+        fn map_address(&self, address: Address) -> usize {
+            address.as_usize() % PRG_ROM_BANK_SIZE
+        }
+        fn write(&mut self, _: Address, _: Byte) {}
+        fn load_chr(&mut self, _: Vec<Byte>) {}
+        fn read_chr(&self, _: Address) -> Byte {
+            Byte::default()
+        }
+        fn write_chr(&mut self, _: Address, _: Byte) {}
+        fn hooks(&self) -> MapperHooks {
+            MapperHooks::CPU_CYCLE
+        }
+        fn on_cpu_cycle(&mut self) {
+            self.cycles += 1;
+        }
+        fn is_irq_pending(&self) -> bool {
+            self.cycles >= 100
+        }
+    }
+
+    #[test]
+    fn trainer_loaded_at_power_on() {
+        let mut rom = test_rom();
+        rom.trainer = Some((0..TRAINER_SIZE).map(|i| Byte::new(i as u8)).collect());
+        let mut bus = Bus::new(rom);
+
+        assert_eq!(bus.read_byte(Address::new(0x6fff)), 0x00);
+        assert_eq!(bus.read_byte(Address::new(0x7000)), 0x00);
+        assert_eq!(bus.read_byte(Address::new(0x7001)), 0x01);
+        assert_eq!(bus.read_byte(Address::new(0x71ff)), 0xff);
+        assert_eq!(bus.read_byte(Address::new(0x7200)), 0x00);
+    }
+
+    #[test]
+    fn battery_ram_round_trip() {
+        let mut rom = test_rom();
+        rom.header.has_battery = true;
+        rom.header.prg_nvram_size = PRG_RAM_SIZE;
+        let mut bus = Bus::new(rom);
+        bus.write_byte(Address::new(0x6000), Byte::new(0x12));
+        bus.write_byte(Address::new(0x7fff), Byte::new(0x34));
+
+        let saved = bus.battery_ram().unwrap();
+        assert_eq!(saved.len(), PRG_RAM_SIZE);
+
+        let mut rom = test_rom();
+        rom.header.has_battery = true;
+        rom.header.prg_nvram_size = PRG_RAM_SIZE;
+        let mut bus = Bus::new(rom);
+        bus.load_battery_ram(&saved);
+
+        assert_eq!(bus.read_byte(Address::new(0x6000)), 0x12);
+        assert_eq!(bus.read_byte(Address::new(0x7fff)), 0x34);
+    }
+
+    #[test]
+    fn power_on_keeps_saved_ram_over_the_trainer() {
+        let mut rom = test_rom();
+        rom.header.has_battery = true;
+        rom.header.prg_nvram_size = PRG_RAM_SIZE;
+        rom.trainer = Some(vec![Byte::new(0xea); TRAINER_SIZE]);
+        let mut bus = Bus::new(rom);
+        assert_eq!(bus.read_byte(Address::new(0x7000)), 0xea);
+
+        let mut saved = vec![Byte::default(); PRG_RAM_SIZE];
+        saved[0x1000] = Byte::new(0x99);
+        bus.load_battery_ram(&saved);
+        bus.power_on();
+
+        assert_eq!(bus.read_byte(Address::new(0x7000)), 0x99);
+    }
+
+    #[test]
+    fn battery_ram_is_sized_from_the_header() {
+        let mut rom = test_rom();
+        rom.header.has_battery = true;
+        rom.header.prg_nvram_size = 0x800;
+        let mut bus = Bus::new(rom);
+        bus.write_byte(Address::new(0x67ff), Byte::new(0x12));
+        bus.write_byte(Address::new(0x6800), Byte::new(0x34));
+        bus.set_ram_init(RamInit::Fill(Byte::new(0xff)));
+        bus.power_on();
+
+        assert_eq!(bus.battery_ram().unwrap().len(), 0x800);
+        assert_eq!(bus.read_byte(Address::new(0x67ff)), 0x12);
+        assert_eq!(bus.read_byte(Address::new(0x6800)), 0xff);
+    }
+
+    #[test]
+    fn no_battery_ram_without_battery() {
+        assert!(test_bus().battery_ram().is_none());
+    }
+
     #[test]
-    fn dmc_dma_stalls_cpu_by_4_cycles() {
+    fn power_on_fills_ram_except_battery_backed() {
         let mut bus = test_bus();
-        // Exit APU open-bus mode and configure DMC:
-        // Rate index 15 = period 54 (fastest trigger), sample at $C000 (default, maps to PRG ROM = 0x10)
-        bus.write_byte(Address::new(0x4015), Byte::new(0x00)); // exit open-bus
-        bus.write_byte(Address::new(0x4010), Byte::new(0x0F)); // rate 15
-        bus.write_byte(Address::new(0x4012), Byte::new(0x00)); // sample addr = $C000
-        bus.write_byte(Address::new(0x4013), Byte::new(0x00)); // length = 1 byte
-        bus.write_byte(Address::new(0x4015), Byte::new(0x10)); // enable DMC
-
-        let mut got_stall = false;
-        for _ in 0..200 {
-            let pending_before = bus.pending_cycles;
+        bus.set_ram_init(RamInit::Fill(Byte::new(0xff)));
+        bus.power_on();
+        assert_eq!(bus.read_byte(Address::new(0x0000)), 0xff);
+        assert_eq!(bus.read_byte(Address::new(0x07ff)), 0xff);
+        assert_eq!(bus.read_byte(Address::new(0x6000)), 0xff);
+
+        let mut rom = test_rom();
+        rom.header.has_battery = true;
+        rom.header.prg_nvram_size = PRG_RAM_SIZE;
+        let mut bus = Bus::new(rom);
+        bus.write_byte(Address::new(0x6000), Byte::new(0x12));
+        bus.set_ram_init(RamInit::Pattern(vec![Byte::new(0x01), Byte::new(0x02)]));
+        bus.power_on();
+        assert_eq!(bus.read_byte(Address::new(0x0000)), 0x01);
+        assert_eq!(bus.read_byte(Address::new(0x0001)), 0x02);
+        assert_eq!(bus.read_byte(Address::new(0x6000)), 0x12);
+    }
+
+    #[test]
+    fn reset_keeps_ram_and_silences_apu() {
+        let mut bus = test_bus();
+        bus.power_on();
+        bus.write_byte(Address::new(0x0010), Byte::new(0x42));
+        bus.write_byte(Address::new(0x4015), Byte::new(0x01)); // enable square 1
+        bus.write_byte(Address::new(0x4003), Byte::new(0x08)); // load its length counter
+        assert_eq!(bus.peek_byte(Address::new(0x4015)) & 0x01, 0x01);
+
+        bus.reset();
+
+        assert_eq!(bus.read_byte(Address::new(0x0010)), 0x42);
+        assert_eq!(bus.peek_byte(Address::new(0x4015)) & 0x01, 0x00);
+    }
+
+    #[test]
+    fn reset_rewrites_frame_counter() {
+        let frame_irq_after_reset = |frame_counter: u8| {
+            let mut bus = test_bus();
+            bus.power_on();
+            bus.write_byte(Address::new(0x4017), Byte::new(frame_counter));
+            bus.reset();
+            bus.tick(30_000);
+            bus.poll_irq_status()
+        };
+
+        assert!(frame_irq_after_reset(0x00));
+        assert!(!frame_irq_after_reset(0x40)); // IRQ inhibit survives the reset
+    }
+
+    #[test]
+    fn timing_accessors() {
+        let mut bus = test_bus();
+        bus.power_on();
+
+        while bus.frame_count() == 0 {
             bus.tick_one();
-            if bus.pending_cycles > pending_before {
-                got_stall = true;
-                assert_eq!(
-                    bus.pending_cycles - pending_before,
-                    4,
-                    "DMC DMA should add exactly 4 pending cycles"
-                );
-                // Verify sample was actually delivered to the DMC
-                assert_matches!(
-                    bus.apu.dmc.sample_buffer(),
-                    Some(_),
-                    "sample should be in DMC buffer after DMA"
-                );
-                assert_eq!(
-                    bus.apu.dmc.sample_buffer(),
-                    Some(Byte::new(0x10)),
-                    "sample should contain PRG ROM value (0x10)"
-                );
-                break;
-            }
-        }
-        assert!(got_stall, "DMC DMA should have fired within 200 cycles");
+        }
+
+        // 262 scanlines of 341 dots, 3 dots per CPU cycle
+        assert_eq!(bus.cycles(), 29_781);
+        assert_eq!(bus.master_cycles(), 29_781 * MASTER_CYCLES_PER_CPU_CYCLE);
+        assert_eq!(
+            bus.ppu_position(),
+            PpuPosition {
+                scanline: 0,
+                dot: 1
+            }
+        );
+    }
+
+    #[test]
+    fn ppu_ignores_writes_while_warming_up() {
+        let mut bus = test_bus();
+        bus.power_on();
+        bus.write_byte(Address::new(0x2000), Byte::new(0x80));
+        assert!(!bus.ppu().registers.is_generating_nmi());
+
+        while bus.ppu().is_warming_up() {
+            bus.tick_one();
+        }
+        bus.write_byte(Address::new(0x2000), Byte::new(0x80));
+        assert!(bus.ppu().registers.is_generating_nmi());
+
+        bus.reset();
+        assert!(!bus.ppu().registers.is_generating_nmi());
+        assert!(bus.ppu().is_warming_up());
+    }
+
+    #[test]
+    fn mapper_clocked_every_cpu_cycle() {
+        let mut bus = Bus::new(Rom::new(
+            vec![0x10.into(); PRG_ROM_BANK_SIZE],
+            vec![0x20.into(); CHR_ROM_BANK_SIZE],
+            Box::new(CycleCountingMapper::default()),
+            MirroringType::Horizontal,
+        ));
+
+        bus.tick(99);
+        assert!(!bus.poll_irq_status());
+
+        bus.tick_one();
+        assert!(bus.poll_irq_status());
+    }
+
+    /// Discrete latch mapper (like CNROM), optionally wired with bus conflicts.
+    /// The latch can be read back through the expansion area.
+    struct LatchMapper {
+        latch: Byte,
+        bus_conflicts: bool,
+    }
+
+    impl Mapper for LatchMapper {
+        fn map_address(&self, address: Address) -> usize {
+            address.as_usize() % PRG_ROM_BANK_SIZE
+        }
+        fn write(&mut self, _: Address, value: Byte) {
+            self.latch = value;
+        }
+        fn load_chr(&mut self, _: Vec<Byte>) {}
+        fn read_chr(&self, _: Address) -> Byte {
+            Byte::default()
+        }
+        fn write_chr(&mut self, _: Address, _: Byte) {}
+        fn peek_expansion(&self, _: Address) -> Option<Byte> {
+            Some(self.latch)
+        }
+        fn power_on(&mut self) {
+            self.latch = Byte::default();
+        }
+        fn hooks(&self) -> MapperHooks {
+            match self.bus_conflicts {
+                true => MapperHooks::BUS_CONFLICTS,
+                false => MapperHooks::empty(),
+            }
+        }
+    }
+
+    fn latch_after_write(bus_conflicts: bool, address: u16, value: u8) -> Byte {
+        let prg_rom = (0..PRG_ROM_BANK_SIZE).map(|i| Byte::new(i as u8)).collect();
+        let mut bus = Bus::new(Rom::new(
+            prg_rom,
+            vec![],
+            Box::new(LatchMapper {
+                latch: Byte::default(),
+                bus_conflicts,
+            }),
+            MirroringType::Horizontal,
+        ));
+        bus.write_byte(Address::new(address), Byte::new(value));
+
+        bus.peek_byte(Address::new(0x5000))
+    }
+
+    #[test]
+    fn bus_conflicts_and_write_with_rom() {
+        assert_eq!(latch_after_write(true, 0x8003, 0xff), 0x03);
+        assert_eq!(latch_after_write(true, 0x80f5, 0x3c), 0x34);
+        // Writing the value already stored in ROM avoids the conflict
+        assert_eq!(latch_after_write(true, 0x8042, 0x42), 0x42);
+    }
+
+    #[test]
+    fn no_bus_conflicts_unless_enabled() {
+        assert_eq!(latch_after_write(false, 0x8003, 0xff), 0xff);
+    }
+
+    #[test]
+    fn power_on_resets_the_mapper() {
+        let mut bus = Bus::new(Rom::new(
+            vec![Byte::default(); PRG_ROM_BANK_SIZE],
+            vec![],
+            Box::new(LatchMapper {
+                latch: Byte::default(),
+                bus_conflicts: false,
+            }),
+            MirroringType::Horizontal,
+        ));
+        bus.write_byte(Address::new(0x8000), Byte::new(0x05));
+        assert_eq!(bus.peek_byte(Address::new(0x5000)), 0x05);
+
+        bus.power_on();
+        assert_eq!(bus.peek_byte(Address::new(0x5000)), 0x00);
+    }
+
+    /// Hold a CPU read of `address` until no DMA needs the bus, as the CPU does
+    fn run_dma(bus: &mut Bus, address: Address) -> Vec<BusAccess> {
+        let mut accesses = vec![];
+        while let Some(access) = bus.run_dma_cycle(address) {
+            accesses.push(access);
+            bus.tick_one();
+        }
+
+        accesses
+    }
+
+    #[test]
+    fn dmc_dma_halts_the_cpu_read() {
+        // Halting on a put cycle needs an alignment cycle before the get
+        for (delay, halted_cycles) in [(0, 3), (1, 2)] {
+            let mut bus = test_bus();
+            bus.write_byte(Address::new(0x4015), Byte::new(0x00)); // exit open-bus
+            bus.write_byte(Address::new(0x4012), Byte::new(0x00)); // sample addr = $C000
+            bus.write_byte(Address::new(0x4013), Byte::new(0x00)); // length = 1 byte
+            bus.write_byte(Address::new(0x4015), Byte::new(0x10)); // enable DMC
+            bus.tick(1 + delay); // the DMA is requested on the first cycle
+
+            let accesses = run_dma(&mut bus, Address::new(0x4000));
+
+            let (sample, halted) = accesses.split_last().unwrap();
+            assert_eq!(halted.len(), halted_cycles);
+            assert!(halted.iter().all(|access| access.address == 0x4000));
+            assert_eq!(sample.address, 0xc000);
+            assert_eq!(sample.value, 0x10);
+            assert_eq!(bus.apu.dmc.sample_buffer(), Some(Byte::new(0x10)));
+            // The sample is left on the data bus for the CPU's read of open bus
+            assert_eq!(bus.read_byte(Address::new(0x4000)), 0x10);
+        }
+    }
+
+    #[test]
+    fn oam_dma_copies_a_byte_per_get_and_put_pair() {
+        let mut bus = test_bus();
+        for offset in 0..=0xff {
+            bus.write_byte(
+                Address::new(0x0200 + offset),
+                Byte::new(offset as u8 ^ 0xff),
+            );
+        }
+        bus.write_byte(Address::new(0x4014), Byte::new(0x02));
+        bus.tick_one(); // the write was on a get cycle
+
+        let accesses = run_dma(&mut bus, Address::new(0x8000));
+
+        let (halt, copy) = accesses.split_first().unwrap();
+        assert_eq!(halt.address, 0x8000);
+        assert_eq!(copy.len(), 512);
+        for (offset, pair) in copy.chunks(2).enumerate() {
+            let [get, put] = pair else { unreachable!() };
+            assert_eq!(get.address, 0x0200 + offset as u16);
+            assert_eq!(get.kind, BusAccessKind::Read);
+            assert_eq!(put.address, 0x2004);
+            assert_eq!(put.kind, BusAccessKind::Write);
+            assert_eq!(put.value, get.value);
+        }
+        bus.write_byte(Address::new(0x2003), Byte::new(0x05));
+        assert_eq!(bus.read_byte(Address::new(0x2004)), 0xfa);
     }
 }
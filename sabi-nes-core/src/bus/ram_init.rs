@@ -0,0 +1,180 @@
+use crate::Byte;
+use anyhow::{Context, Error, Result, bail};
+use std::iter;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Contents of the console and cartridge RAM at power-on. Real RAM comes up in a
+/// chip-dependent, semi-random state; some games (and test ROMs) behave differently
+/// depending on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RamInit {
+    /// Every byte set to the same value, usually $00 or $FF
+    Fill(Byte),
+    /// Pseudo-random bytes, reproducible from the seed
+    Random { seed: u64 },
+    /// The pattern repeated across the RAM
+    Pattern(Vec<Byte>),
+}
+
+impl Default for RamInit {
+    fn default() -> Self {
+        Self::Fill(Byte::new(0x00))
+    }
+}
+
+impl RamInit {
+    /// Stream of power-on RAM contents, infinite unless the pattern is empty
+    pub fn contents(&self) -> Box<dyn Iterator<Item = Byte> + '_> {
+        match self {
+            Self::Fill(value) => Box::new(iter::repeat(*value)),
+            Self::Random { seed } => {
+                let mut rng = SplitMix64(*seed);
+                Box::new(iter::from_fn(move || Some(rng.next_byte())))
+            }
+            Self::Pattern(pattern) => Box::new(pattern.iter().copied().cycle()),
+        }
+    }
+
+    /// Overwrite `ram` with the power-on contents
+    pub fn fill(&self, ram: &mut [Byte]) {
+        for (byte, value) in ram.iter_mut().zip(self.contents()) {
+            *byte = value;
+        }
+    }
+}
+
+/// Parses `zeros`, `ones` (every byte $FF), `random`, `random:<seed>` and
+/// `pattern:<hex bytes>`, e.g. `pattern:00ff`
+impl FromStr for RamInit {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (kind, argument) = match value.split_once(':') {
+            Some((kind, argument)) => (kind, Some(argument)),
+            None => (value, None),
+        };
+
+        Ok(match (kind, argument) {
+            ("zeros", None) => Self::Fill(Byte::new(0x00)),
+            ("ones", None) => Self::Fill(Byte::new(0xff)),
+            ("random", None) => Self::Random {
+                seed: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_nanos() as u64),
+            },
+            ("random", Some(seed)) => Self::Random {
+                seed: seed
+                    .parse()
+                    .with_context(|| format!("Invalid RAM seed `{seed}`"))?,
+            },
+            ("pattern", Some(hex)) => Self::Pattern(parse_hex(hex)?),
+            _ => bail!(
+                "Unknown RAM initialisation `{value}`, \
+                 expected zeros, ones, random[:<seed>] or pattern:<hex bytes>"
+            ),
+        })
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<Byte>> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        bail!("RAM pattern `{hex}` must be a non-empty string of hex byte pairs");
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| {
+            let pair = &hex[index..index + 2];
+            u8::from_str_radix(pair, 16)
+                .map(Byte::new)
+                .with_context(|| format!("Invalid hex byte `{pair}` in RAM pattern"))
+        })
+        .collect()
+}
+
+/// Small, fast generator; power-on RAM doesn't need anything stronger
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_byte(&mut self) -> Byte {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Byte::new(z as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(ram_init: &RamInit) -> Vec<Byte> {
+        let mut ram = vec![Byte::new(0x55); 8];
+        ram_init.fill(&mut ram);
+        ram
+    }
+
+    #[test]
+    fn parses_every_kind() {
+        assert_eq!(
+            "zeros".parse::<RamInit>().unwrap(),
+            RamInit::Fill(Byte::new(0x00))
+        );
+        assert_eq!(
+            "ones".parse::<RamInit>().unwrap(),
+            RamInit::Fill(Byte::new(0xff))
+        );
+        assert_eq!(
+            "random:42".parse::<RamInit>().unwrap(),
+            RamInit::Random { seed: 42 }
+        );
+        assert_eq!(
+            "pattern:00FF".parse::<RamInit>().unwrap(),
+            RamInit::Pattern(vec![Byte::new(0x00), Byte::new(0xff)])
+        );
+        assert!(matches!(
+            "random".parse::<RamInit>().unwrap(),
+            RamInit::Random { .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_values() {
+        for value in [
+            "",
+            "twos",
+            "zeros:1",
+            "random:x",
+            "pattern",
+            "pattern:0",
+            "pattern:zz",
+        ] {
+            assert!(
+                value.parse::<RamInit>().is_err(),
+                "`{value}` should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn fills_with_pattern() {
+        let ram = filled(&RamInit::Pattern(vec![
+            Byte::new(0x00),
+            Byte::new(0xff),
+            Byte::new(0x7f),
+        ]));
+        let values: Vec<_> = ram.iter().map(|byte| byte.value()).collect();
+        assert_eq!(values, [0x00, 0xff, 0x7f, 0x00, 0xff, 0x7f, 0x00, 0xff]);
+    }
+
+    #[test]
+    fn random_is_reproducible_from_the_seed() {
+        let ram = filled(&RamInit::Random { seed: 1 });
+        assert_eq!(ram, filled(&RamInit::Random { seed: 1 }));
+        assert_ne!(ram, filled(&RamInit::Random { seed: 2 }));
+    }
+}
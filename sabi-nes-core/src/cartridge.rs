@@ -1,9 +1,14 @@
+pub mod checksum;
+pub mod database;
+mod header;
 pub mod mappers;
 mod mirroring_type;
+pub mod patch;
 mod rom;
 
-pub use mirroring_type::MirroringType;
-pub use rom::Rom;
+pub use header::{ConsoleType, HEADER_SIZE, HeaderFormat, HeaderParsing, RomHeader, TimingRegion};
+pub use mirroring_type::{MirroringType, NametableSource};
+pub use rom::{Rom, RomImage, TRAINER_SIZE};
 
 pub const PRG_ROM_BANK_SIZE: usize = 16384;
 pub const CHR_ROM_BANK_SIZE: usize = 8192;
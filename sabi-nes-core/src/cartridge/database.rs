@@ -0,0 +1,110 @@
+//! Built-in game database used to correct bad iNES headers.
+//!
+//! Games are identified by the CRC32 and SHA-1 of their PRG ROM followed by CHR ROM.
+//! The table is generated at build time from `data/nes20db.xml`, which uses the schema
+//! of the NES 2.0 XML database.
+
+use crate::cartridge::checksum::{crc32, sha1};
+use crate::cartridge::{ConsoleType, MirroringType, RomHeader, TimingRegion};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GameEntry {
+    pub title: &'static str,
+    /// CRC32 of PRG ROM + CHR ROM
+    pub crc32: u32,
+    /// SHA-1 of PRG ROM + CHR ROM
+    pub sha1: [u8; 20],
+    pub prg_rom_size: usize,
+    pub chr_rom_size: usize,
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub mapper_id: u16,
+    pub submapper: u8,
+    /// Hard-wired mirroring, `None` if it's controlled by the mapper
+    pub mirroring: Option<MirroringType>,
+    pub has_battery: bool,
+    pub timing: TimingRegion,
+    pub console_type: ConsoleType,
+    /// NES 2.0 default expansion device
+    pub expansion_device: u8,
+}
+
+include!(concat!(env!("OUT_DIR"), "/rom_database.rs"));
+
+impl GameEntry {
+    /// Replace the values in `header` with the ones from the database
+    pub fn apply(&self, header: &mut RomHeader) {
+        header.mapper_id = self.mapper_id;
+        header.submapper = self.submapper;
+        header.prg_rom_size = self.prg_rom_size;
+        header.chr_rom_size = self.chr_rom_size;
+        header.prg_ram_size = self.prg_ram_size;
+        header.prg_nvram_size = self.prg_nvram_size;
+        header.chr_ram_size = self.chr_ram_size;
+        header.chr_nvram_size = self.chr_nvram_size;
+        header.has_battery = self.has_battery;
+        header.timing = self.timing;
+        header.console_type = self.console_type;
+        header.expansion_device = self.expansion_device;
+        if let Some(mirroring) = self.mirroring {
+            header.mirroring = mirroring;
+        }
+    }
+}
+
+/// All games in the database, sorted by CRC32
+pub fn games() -> &'static [GameEntry] {
+    GAMES
+}
+
+/// Find the game with PRG ROM + CHR ROM equal to `rom_data`
+pub fn find_game(rom_data: &[u8]) -> Option<&'static GameEntry> {
+    let mut candidates = find_by_crc32(crc32(rom_data)).peekable();
+    candidates.peek()?;
+
+    // Confirm with SHA-1 in case of CRC32 collisions
+    let sha1 = sha1(rom_data);
+    candidates.find(|game| game.sha1 == sha1)
+}
+
+pub fn find_by_crc32(crc32: u32) -> impl Iterator<Item = &'static GameEntry> {
+    let start = GAMES.partition_point(|game| game.crc32 < crc32);
+
+    GAMES[start..]
+        .iter()
+        .take_while(move |game| game.crc32 == crc32)
+}
+
+pub fn find_by_sha1(sha1: &[u8; 20]) -> Option<&'static GameEntry> {
+    GAMES.iter().find(|game| game.sha1 == *sha1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn games_sorted_by_crc32() {
+        assert!(games().is_sorted_by_key(|game| game.crc32));
+    }
+
+    #[test]
+    fn find_nestest() {
+        let data = std::fs::read("tests/test_roms/nestest.nes").unwrap();
+        let game = find_game(&data[16..]).unwrap();
+
+        assert_eq!(game.title, "nestest");
+        assert_eq!(game.mapper_id, 0);
+        assert_eq!(game.timing, TimingRegion::Ntsc);
+        assert_eq!(game.expansion_device, 1);
+        assert_eq!(find_by_crc32(0x158b_0388).next(), Some(game));
+        assert_eq!(find_by_sha1(&game.sha1), Some(game));
+    }
+
+    #[test]
+    fn unknown_game() {
+        assert!(find_game(&[0xea; 0x4000]).is_none());
+    }
+}
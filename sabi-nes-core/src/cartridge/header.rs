@@ -0,0 +1,525 @@
+//! iNES and NES 2.0 file headers.
+//!
+//! NES 2.0 is a backwards compatible extension of iNES, identified by bits 2-3 of byte 7 being `10`.
+//! It adds 12-bit mapper numbers, submappers, larger ROM sizes, exact RAM sizes,
+//! the CPU/PPU timing region, the console type and the default expansion device.
+//! See <https://www.nesdev.org/wiki/NES_2.0>.
+
+use crate::Byte;
+use crate::cartridge::{CHR_ROM_BANK_SIZE, MirroringType, PRG_ROM_BANK_SIZE};
+use anyhow::{Result, anyhow, bail};
+use bitflags::bitflags;
+use log::warn;
+
+/// "NES" followed by MS-DOS end-of-file used to recognize .NES (iNES) files
+const NES_TAG: [u8; 4] = [0x4e, 0x45, 0x53, 0x1a];
+
+pub const HEADER_SIZE: usize = 16;
+const PRG_RAM_UNIT_SIZE: usize = 8192;
+
+bitflags! {
+    #[derive(Debug, Copy, Clone)]
+    struct ControlByte1: u8 {
+        const MIRRORING               = 0b0000_0001; // 1 for vertical, 0 for horizontal
+        const BATTERY_BACKED_RAM      = 0b0000_0010;
+        const HAS_TRAINER             = 0b0000_0100;
+        const FOUR_SCREEN_VRAM_LAYOUT = 0b0000_1000;
+        const MAPPER_TYPE1            = 0b0001_0000; // first bit of mapper type
+        const MAPPER_TYPE2            = 0b0010_0000; // second bit of mapper type
+        const MAPPER_TYPE3            = 0b0100_0000; // third bit of mapper type
+        const MAPPER_TYPE4            = 0b1000_0000; // fourth bit of mapper type
+    }
+}
+
+impl ControlByte1 {
+    pub fn mapper_bits_lo(&self) -> Byte {
+        Byte::new(self.bits()) >> 4
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Copy, Clone)]
+    struct ControlByte2: u8 {
+        const INES_V1_FIRST   = 0b0000_0001; // 0 for iNES v1 format
+        const INES_V1_SECOND  = 0b0000_0010; // 0 for iNES v1 format
+        const INES_FMT_FIRST  = 0b0000_0100; // if INES_FMT bits are == 10, then it's NES2.0 format,
+        const INES_FMT_SECOND = 0b0000_1000; // if they are == 00, then it's iNES v1 format
+        const MAPPER_TYPE5    = 0b0001_0000; // fifth bit of mapper type
+        const MAPPER_TYPE6    = 0b0010_0000; // sixth bit of mapper type
+        const MAPPER_TYPE7    = 0b0100_0000; // seventh bit of mapper type
+        const MAPPER_TYPE8    = 0b1000_0000; // eighth bit of mapper type
+
+        const CONSOLE_MASK    = 0b0000_0011;
+        const MAPPER_MASK     = 0b1111_0000;
+    }
+}
+
+impl ControlByte2 {
+    pub fn mapper_bits_hi(&self) -> Byte {
+        (*self & Self::MAPPER_MASK).bits().into()
+    }
+
+    pub fn console_bits(&self) -> u8 {
+        (*self & Self::CONSOLE_MASK).bits()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFormat {
+    INes,
+    /// iNES header with garbage in bytes 7-15 (e.g. "DiskDude!" ripper tags), which were ignored
+    ArchaicINes,
+    Nes2,
+    /// UNIF container, the header is derived from its chunks
+    Unif,
+}
+
+/// How to treat headers that don't follow the iNES / NES 2.0 spec
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeaderParsing {
+    /// Reject malformed headers (for tooling that wants to flag bad dumps)
+    Strict,
+    /// Detect garbage left by old rippers, ignore the affected bytes and log a warning
+    #[default]
+    Lenient,
+}
+
+/// CPU/PPU timing the game was made for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingRegion {
+    /// RP2C02 (North America, Japan)
+    Ntsc,
+    /// RP2C07 (Europe, Australia)
+    Pal,
+    /// Runs on both NTSC and PAL consoles
+    MultiRegion,
+    /// UA6538 (Dendy famiclones)
+    Dendy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleType {
+    /// Nintendo Entertainment System / Family Computer
+    Nes,
+    VsSystem,
+    Playchoice10,
+    /// Extended console type from byte 13 (famiclones, VT0x chips, ...)
+    Extended(u8),
+}
+
+/// Parsed iNES or NES 2.0 header.
+/// For iNES files, the values not present in the header are derived the way most emulators do
+/// (e.g. 8kB of PRG RAM, CHR RAM if there's no CHR ROM).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomHeader {
+    pub format: HeaderFormat,
+    /// 12-bit mapper number (8-bit for iNES)
+    pub mapper_id: u16,
+    /// NES 2.0 submapper, 0 for iNES
+    pub submapper: u8,
+    /// PRG ROM size in bytes
+    pub prg_rom_size: usize,
+    /// CHR ROM size in bytes
+    pub chr_rom_size: usize,
+    /// Volatile PRG RAM size in bytes
+    pub prg_ram_size: usize,
+    /// Battery-backed PRG RAM (or EEPROM) size in bytes
+    pub prg_nvram_size: usize,
+    /// Volatile CHR RAM size in bytes
+    pub chr_ram_size: usize,
+    /// Battery-backed CHR RAM size in bytes
+    pub chr_nvram_size: usize,
+    pub mirroring: MirroringType,
+    pub has_battery: bool,
+    pub has_trainer: bool,
+    pub timing: TimingRegion,
+    pub console_type: ConsoleType,
+    /// Default expansion device (NES 2.0 byte 15), 0 if unspecified.
+    /// See <https://www.nesdev.org/wiki/NES_2.0#Default_Expansion_Device>.
+    pub expansion_device: u8,
+}
+
+impl TryFrom<&[u8]> for RomHeader {
+    type Error = anyhow::Error;
+
+    fn try_from(data: &[u8]) -> Result<Self> {
+        Self::parse(data, HeaderParsing::default())
+    }
+}
+
+impl RomHeader {
+    pub fn parse(data: &[u8], parsing: HeaderParsing) -> Result<Self> {
+        let data: &[u8; HEADER_SIZE] = data
+            .get(0..HEADER_SIZE)
+            .and_then(|header| header.try_into().ok())
+            .ok_or_else(|| anyhow!("Failed to parse first 16 bytes for header"))?;
+
+        if data[0..4] != NES_TAG {
+            bail!("File is not an iNES format - missing 'NES' tag");
+        }
+
+        // Detection as recommended by https://www.nesdev.org/wiki/INES#Variant_comparison
+        let format_bits = (data[7] >> 2) & 0b11;
+        let is_clean_ines = format_bits == 0b00 && data[12..16].iter().all(|&byte| byte == 0);
+        match (format_bits, parsing) {
+            (0b10, _) => Self::parse_nes2(data),
+            (0b00, HeaderParsing::Strict) => Self::parse_ines(data),
+            (_, HeaderParsing::Strict) => {
+                bail!("Unknown header format (byte 7: {:#04x})", data[7])
+            }
+            (_, HeaderParsing::Lenient) if is_clean_ines => {
+                let mut data = *data;
+                // Bytes 10-11 are unused by iNES, some rippers left junk there
+                if data[10..12].iter().any(|&byte| byte != 0) {
+                    warn!(
+                        "Junk in unused iNES header bytes 10-11 ({:02x?}), ignoring them",
+                        &data[10..12]
+                    );
+                    data[10..12].fill(0);
+                }
+                Self::parse_ines(&data)
+            }
+            (_, HeaderParsing::Lenient) => Self::parse_archaic_ines(data),
+        }
+    }
+
+    /// Number of 16kB PRG ROM banks, rounded up
+    pub fn prg_rom_banks(&self) -> usize {
+        self.prg_rom_size.div_ceil(PRG_ROM_BANK_SIZE)
+    }
+
+    /// Number of 8kB CHR ROM banks, rounded up
+    pub fn chr_rom_banks(&self) -> usize {
+        self.chr_rom_size.div_ceil(CHR_ROM_BANK_SIZE)
+    }
+
+    fn parse_ines(data: &[u8; HEADER_SIZE]) -> Result<Self> {
+        if data[10..16].iter().any(|&byte| byte != 0) {
+            bail!("header bytes 10-15 are not 0 — file may not be iNES 1.0 format");
+        }
+
+        let control_byte1 = ControlByte1::from_bits_truncate(data[6]);
+        let control_byte2 = ControlByte2::from_bits_truncate(data[7]);
+        let has_battery = control_byte1.contains(ControlByte1::BATTERY_BACKED_RAM);
+        let chr_rom_size = usize::from(data[5]) * CHR_ROM_BANK_SIZE;
+        // Byte 8 is the PRG RAM size in 8kB units, 0 infers 8kB for compatibility
+        let prg_ram_size = usize::from(data[8]).max(1) * PRG_RAM_UNIT_SIZE;
+        let (prg_ram_size, prg_nvram_size) = match has_battery {
+            true => (0, prg_ram_size),
+            false => (prg_ram_size, 0),
+        };
+        let timing = match data[9] & 1 {
+            0 => TimingRegion::Ntsc,
+            _ => TimingRegion::Pal,
+        };
+        let console_type = match control_byte2.console_bits() {
+            0b01 => ConsoleType::VsSystem,
+            0b10 => ConsoleType::Playchoice10,
+            _ => ConsoleType::Nes,
+        };
+
+        Ok(Self {
+            format: HeaderFormat::INes,
+            mapper_id: (control_byte1.mapper_bits_lo() | control_byte2.mapper_bits_hi())
+                .value()
+                .into(),
+            submapper: 0,
+            prg_rom_size: usize::from(data[4]) * PRG_ROM_BANK_SIZE,
+            chr_rom_size,
+            prg_ram_size,
+            prg_nvram_size,
+            chr_ram_size: if chr_rom_size == 0 {
+                CHR_ROM_BANK_SIZE
+            } else {
+                0
+            },
+            chr_nvram_size: 0,
+            mirroring: mirroring(control_byte1),
+            has_battery,
+            has_trainer: control_byte1.contains(ControlByte1::HAS_TRAINER),
+            timing,
+            console_type,
+            expansion_device: 0,
+        })
+    }
+
+    /// Parse a header with a ripper tag or other garbage in bytes 7-15,
+    /// which also corrupts the high nibble of the mapper number. Only bytes 4-6 are trusted.
+    fn parse_archaic_ines(data: &[u8; HEADER_SIZE]) -> Result<Self> {
+        let tag: String = data[7..]
+            .iter()
+            .filter(|byte| byte.is_ascii_graphic())
+            .map(|&byte| char::from(byte))
+            .collect();
+        warn!("Archaic iNES header (\"{tag}\" in bytes 7-15), ignoring bytes 7-15");
+
+        let mut data = *data;
+        data[7..].fill(0);
+
+        Ok(Self {
+            format: HeaderFormat::ArchaicINes,
+            ..Self::parse_ines(&data)?
+        })
+    }
+
+    fn parse_nes2(data: &[u8; HEADER_SIZE]) -> Result<Self> {
+        let control_byte1 = ControlByte1::from_bits_truncate(data[6]);
+        let control_byte2 = ControlByte2::from_bits_truncate(data[7]);
+        let mapper_lo = (control_byte1.mapper_bits_lo() | control_byte2.mapper_bits_hi()).value();
+        let timing = match data[12] & 0b11 {
+            0 => TimingRegion::Ntsc,
+            1 => TimingRegion::Pal,
+            2 => TimingRegion::MultiRegion,
+            _ => TimingRegion::Dendy,
+        };
+        let console_type = match control_byte2.console_bits() {
+            0b00 => ConsoleType::Nes,
+            0b01 => ConsoleType::VsSystem,
+            0b10 => ConsoleType::Playchoice10,
+            _ => ConsoleType::Extended(data[13] & 0x0f),
+        };
+
+        Ok(Self {
+            format: HeaderFormat::Nes2,
+            mapper_id: u16::from(data[8] & 0x0f) << 8 | u16::from(mapper_lo),
+            submapper: data[8] >> 4,
+            prg_rom_size: rom_size(data[4], data[9] & 0x0f, PRG_ROM_BANK_SIZE)
+                .ok_or_else(|| anyhow!("PRG ROM size in header is too large"))?,
+            chr_rom_size: rom_size(data[5], data[9] >> 4, CHR_ROM_BANK_SIZE)
+                .ok_or_else(|| anyhow!("CHR ROM size in header is too large"))?,
+            prg_ram_size: ram_size(data[10] & 0x0f),
+            prg_nvram_size: ram_size(data[10] >> 4),
+            chr_ram_size: ram_size(data[11] & 0x0f),
+            chr_nvram_size: ram_size(data[11] >> 4),
+            mirroring: mirroring(control_byte1),
+            has_battery: control_byte1.contains(ControlByte1::BATTERY_BACKED_RAM),
+            has_trainer: control_byte1.contains(ControlByte1::HAS_TRAINER),
+            timing,
+            console_type,
+            expansion_device: data[15] & 0x3f,
+        })
+    }
+}
+
+fn mirroring(control_byte1: ControlByte1) -> MirroringType {
+    MirroringType::new(
+        control_byte1.contains(ControlByte1::FOUR_SCREEN_VRAM_LAYOUT),
+        control_byte1.contains(ControlByte1::MIRRORING),
+    )
+}
+
+/// NES 2.0 ROM size from the LSB byte and the MSB nibble.
+/// An MSB nibble of $F switches to the exponent-multiplier notation: `2^E * (MM * 2 + 1)`.
+fn rom_size(lsb: u8, msb: u8, unit_size: usize) -> Option<usize> {
+    match msb {
+        0x0f => {
+            let exponent = u32::from(lsb >> 2);
+            let multiplier = usize::from(lsb & 0b11) * 2 + 1;
+            1usize.checked_shl(exponent)?.checked_mul(multiplier)
+        }
+        _ => Some((usize::from(msb) << 8 | usize::from(lsb)) * unit_size),
+    }
+}
+
+/// NES 2.0 RAM sizes are stored as shift counts: `64 << shift`, or none if the shift is 0
+fn ram_size(shift: u8) -> usize {
+    match shift {
+        0 => 0,
+        shift => 64 << shift,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Collects the warnings logged on the calling thread, tests run on their own threads
+    struct WarningLogger;
+
+    impl log::Log for WarningLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.with_borrow_mut(|warnings| warnings.push(record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn logged_warnings<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+        static LOGGER: WarningLogger = WarningLogger;
+        // Another test may have installed it already
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Warn);
+
+        WARNINGS.with_borrow_mut(Vec::clear);
+        let result = f();
+        (result, WARNINGS.with_borrow_mut(std::mem::take))
+    }
+
+    fn header(bytes: [u8; 12]) -> [u8; HEADER_SIZE] {
+        let mut header = [0; HEADER_SIZE];
+        header[..4].copy_from_slice(&NES_TAG);
+        header[4..].copy_from_slice(&bytes);
+        header
+    }
+
+    #[test]
+    fn parse_ines_header() {
+        let data = header([0x02, 0x01, 0x13, 0x40, 0x00, 0x00, 0, 0, 0, 0, 0, 0]);
+        let header = RomHeader::try_from(data.as_slice()).unwrap();
+
+        assert_eq!(
+            header,
+            RomHeader {
+                format: HeaderFormat::INes,
+                mapper_id: 0x41,
+                submapper: 0,
+                prg_rom_size: 2 * PRG_ROM_BANK_SIZE,
+                chr_rom_size: CHR_ROM_BANK_SIZE,
+                prg_ram_size: 0,
+                prg_nvram_size: 8192,
+                chr_ram_size: 0,
+                chr_nvram_size: 0,
+                mirroring: MirroringType::Vertical,
+                has_battery: true,
+                has_trainer: false,
+                timing: TimingRegion::Ntsc,
+                console_type: ConsoleType::Nes,
+                expansion_device: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn ines_header_with_garbage_is_rejected_in_strict_mode() {
+        let data = header([
+            0x02, 0x01, 0x00, 0x00, 0, 0, 0x44, 0x69, 0x73, 0x6b, 0x44, 0x75,
+        ]);
+
+        assert!(RomHeader::parse(&data, HeaderParsing::Strict).is_err());
+    }
+
+    #[test]
+    fn disk_dude_header_is_parsed_leniently() {
+        // MMC1, vertical mirroring, "DiskDude!" from byte 7 turns the mapper into 0x41
+        let mut data = header([0x08, 0x02, 0x11, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data[7..].copy_from_slice(b"DiskDude!");
+
+        let header = RomHeader::parse(&data, HeaderParsing::Lenient).unwrap();
+
+        assert_eq!(header.format, HeaderFormat::ArchaicINes);
+        assert_eq!(header.mapper_id, 1);
+        assert_eq!(header.prg_rom_size, 8 * PRG_ROM_BANK_SIZE);
+        assert_eq!(header.chr_rom_size, 2 * CHR_ROM_BANK_SIZE);
+        assert_eq!(header.mirroring, MirroringType::Vertical);
+        assert_eq!(header.console_type, ConsoleType::Nes);
+        assert!(RomHeader::parse(&data, HeaderParsing::Strict).is_err());
+    }
+
+    #[test]
+    fn junk_in_unused_ines_bytes_is_ignored() {
+        // Bytes 10-11 aren't used by iNES, the mapper in byte 7 can be trusted
+        let data = header([0x02, 0x01, 0x10, 0x40, 0, 0, 0x12, 0x34, 0, 0, 0, 0]);
+
+        let (header, warnings) =
+            logged_warnings(|| RomHeader::parse(&data, HeaderParsing::Lenient).unwrap());
+
+        assert_eq!(header.format, HeaderFormat::INes);
+        assert_eq!(header.mapper_id, 0x41);
+        assert_eq!(
+            warnings,
+            ["Junk in unused iNES header bytes 10-11 ([12, 34]), ignoring them"]
+        );
+    }
+
+    #[test]
+    fn clean_ines_header_is_parsed_leniently_without_warning() {
+        let data = header([0x02, 0x01, 0x10, 0x40, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let (header, warnings) =
+            logged_warnings(|| RomHeader::parse(&data, HeaderParsing::Lenient).unwrap());
+
+        assert_eq!(header.format, HeaderFormat::INes);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn parse_nes2_header() {
+        let data = header([
+            0x08, 0x10, 0x52, 0x0b, 0x31, 0x00, 0x70, 0x07, 0x01, 0x00, 0x00, 0x08,
+        ]);
+        let header = RomHeader::try_from(data.as_slice()).unwrap();
+
+        assert_eq!(header.format, HeaderFormat::Nes2);
+        assert_eq!(header.mapper_id, 0x105);
+        assert_eq!(header.submapper, 3);
+        assert_eq!(header.prg_rom_size, 128 * 1024);
+        assert_eq!(header.chr_rom_size, 128 * 1024);
+        assert_eq!(header.prg_ram_size, 0);
+        assert_eq!(header.prg_nvram_size, 8192);
+        assert_eq!(header.chr_ram_size, 8192);
+        assert_eq!(header.chr_nvram_size, 0);
+        assert_eq!(header.timing, TimingRegion::Pal);
+        assert_eq!(header.console_type, ConsoleType::Extended(0));
+        assert_eq!(header.expansion_device, 0x08);
+    }
+
+    #[test]
+    fn nes2_exponent_multiplier_sizes() {
+        // PRG: 2^4 * 3 = 48 bytes, CHR: 2^13 * 1 = 8kB
+        let data = header([
+            0b0001_0001,
+            0b0011_0100,
+            0x00,
+            0x08,
+            0x00,
+            0xff,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ]);
+        let header = RomHeader::try_from(data.as_slice()).unwrap();
+
+        assert_eq!(header.prg_rom_size, 48);
+        assert_eq!(header.prg_rom_banks(), 1);
+        assert_eq!(header.chr_rom_size, 8192);
+        assert_eq!(header.chr_rom_banks(), 1);
+    }
+
+    #[test]
+    fn nes2_large_rom_sizes() {
+        // MSB nibbles extend the bank counts to 12 bits
+        let data = header([0x00, 0x00, 0x00, 0x08, 0x00, 0x21, 0, 0, 0, 0, 0, 0]);
+        let header = RomHeader::try_from(data.as_slice()).unwrap();
+
+        assert_eq!(header.prg_rom_size, 0x100 * PRG_ROM_BANK_SIZE);
+        assert_eq!(header.chr_rom_size, 0x200 * CHR_ROM_BANK_SIZE);
+    }
+
+    #[test]
+    fn reserved_header_format_is_rejected_in_strict_mode() {
+        let data = header([0x01, 0x01, 0x00, 0x04, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert!(RomHeader::parse(&data, HeaderParsing::Strict).is_err());
+        assert_eq!(
+            RomHeader::parse(&data, HeaderParsing::Lenient)
+                .unwrap()
+                .format,
+            HeaderFormat::ArchaicINes
+        );
+    }
+}
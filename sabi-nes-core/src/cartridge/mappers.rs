@@ -1,24 +1,68 @@
 mod mmc1;
 mod nrom;
+mod registry;
+mod uxrom;
 
+use crate::apu::ExpansionAudio;
+use crate::cartridge::NametableSource;
 use crate::{Address, Byte};
+use bitflags::bitflags;
 
 pub use mmc1::Mmc1;
 pub use nrom::{Nrom128, Nrom256};
+pub use registry::{
+    CartridgeInfo, MapperCapabilities, MapperDescriptor, SUPPORTED_MAPPERS, find_board,
+    find_mapper, mapper_name, unsupported_mapper,
+};
+pub use uxrom::Uxrom;
 
 pub trait MapperId {
-    const ID: u8;
+    /// iNES / NES 2.0 mapper number
+    const ID: u16;
+    const NAME: &'static str;
 
-    fn name(&self) -> &'static str;
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+}
+
+bitflags! {
+    /// Clock notifications a mapper wants to receive.
+    /// The bus reads these once when the cartridge is inserted, so mappers
+    /// which don't need them (e.g. NROM) pay nothing per cycle.
+    #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+    pub struct MapperHooks: u8 {
+        /// Call [`Mapper::on_cpu_cycle`] on every CPU cycle (VRC IRQs, FME-7, ...)
+        const CPU_CYCLE   = 0b0000_0001;
+        /// Call [`Mapper::on_ppu_address`] on every PPU memory access (MMC3 A12, MMC2 latches, ...)
+        const PPU_ADDRESS = 0b0000_0010;
+        /// Clock and mix the sound chip from [`Mapper::expansion_audio`] (VRC6, N163, ...)
+        const EXPANSION_AUDIO = 0b0000_0100;
+        /// Writes to $8000-$FFFF are ANDed with the PRG ROM byte at the same address
+        /// (discrete boards without a latch that disables the ROM on writes, like UNROM and CNROM)
+        const BUS_CONFLICTS = 0b0000_1000;
+    }
 }
 
 pub trait Mapper {
     /// Maps a CPU address to a PRG ROM offset
     fn map_address(&self, address: Address) -> usize;
 
+    /// Read a byte of PRG ROM for a CPU address offset from $8000.
+    /// Mirroring is up to [`Mapper::map_address`], which only decodes the bank bits the board has.
+    /// Returns `None` (open bus) if the mapped offset is past the end of the image, e.g. for
+    /// a truncated dump, rather than mirroring whatever happens to be there.
+    fn read_prg(&self, prg_rom: &[Byte], address: Address) -> Option<Byte> {
+        prg_rom.get(self.map_address(address)).copied()
+    }
+
     /// Write to mapper registers (for mappers with registers like MMC1)
     fn write(&mut self, address: Address, value: Byte);
 
+    /// Put the registers back in their power-on state when the console is switched on.
+    /// RAM is left to the bus.
+    fn power_on(&mut self) {}
+
     /// Load CHR ROM/RAM data into the mapper
     fn load_chr(&mut self, data: Vec<Byte>);
 
@@ -27,4 +71,67 @@ pub trait Mapper {
 
     /// Write a byte to CHR RAM (no-op for CHR ROM)
     fn write_chr(&mut self, address: Address, value: Byte);
+
+    /// Read from the cartridge expansion area ($4020-$5FFF).
+    /// Returns `None` if the cartridge doesn't drive the data bus at this address,
+    /// in which case the CPU sees open bus.
+    fn read_expansion(&mut self, _address: Address) -> Option<Byte> {
+        None
+    }
+
+    /// Same as [`Mapper::read_expansion`], but without side effects (for the debugger/trace).
+    fn peek_expansion(&self, _address: Address) -> Option<Byte> {
+        None
+    }
+
+    /// Write to the cartridge expansion area ($4020-$5FFF)
+    fn write_expansion(&mut self, _address: Address, _value: Byte) {}
+
+    /// Memory backing the logical nametable `index` (0 = $2000, 1 = $2400, 2 = $2800, 3 = $2C00).
+    /// Returns `None` to use the mirroring from the ROM header.
+    fn nametable_source(&self, _index: usize) -> Option<NametableSource> {
+        None
+    }
+
+    /// 1KiB page of nametable RAM/ROM on the cartridge (see [`NametableSource::Cartridge`])
+    fn nametable(&self, _page: usize) -> Option<&[Byte]> {
+        None
+    }
+
+    /// Writable 1KiB page of nametable memory on the cartridge, `None` for nametable ROM
+    fn nametable_mut(&mut self, _page: usize) -> Option<&mut [Byte]> {
+        None
+    }
+
+    /// Non-volatile memory kept by the mapper itself (EEPROM, battery-backed CHR RAM, ...),
+    /// saved along with the battery-backed PRG RAM
+    fn nvram(&self) -> Option<&[Byte]> {
+        None
+    }
+
+    fn nvram_mut(&mut self) -> Option<&mut [Byte]> {
+        None
+    }
+
+    /// Clock notifications this mapper needs (none by default)
+    fn hooks(&self) -> MapperHooks {
+        MapperHooks::empty()
+    }
+
+    /// Called once per CPU cycle if [`MapperHooks::CPU_CYCLE`] is requested
+    fn on_cpu_cycle(&mut self) {}
+
+    /// Called with the address the PPU puts on its bus for every fetch
+    /// (rendering and $2007 accesses) if [`MapperHooks::PPU_ADDRESS`] is requested
+    fn on_ppu_address(&mut self, _address: Address) {}
+
+    /// Sound chip on the cartridge, used if [`MapperHooks::EXPANSION_AUDIO`] is requested
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        None
+    }
+
+    /// Whether the cartridge is currently asserting the CPU IRQ line
+    fn is_irq_pending(&self) -> bool {
+        false
+    }
 }
@@ -55,11 +55,8 @@ pub struct Mmc1 {
 }
 
 impl MapperId for Mmc1 {
-    const ID: u8 = 1;
-
-    fn name(&self) -> &'static str {
-        "MMC1"
-    }
+    const ID: u16 = 1;
+    const NAME: &'static str = "MMC1";
 }
 
 impl Mmc1 {
@@ -119,13 +116,17 @@ impl Mmc1 {
 
     /// Map PRG ROM address (0-based offset 0x0000-0x7FFF from CPU $8000-$FFFF)
     fn map_prg_address(&self, address: Address) -> usize {
+        // Guard against headers with 0 or 1 PRG banks, the bank registers wrap around instead
+        let prg_rom_banks = self.prg_rom_banks.max(1);
         let bank_mode = (self.control >> 2) & 0b11;
         let prg_bank_num = (self.prg_bank & 0x0F).as_usize();
 
-        match bank_mode.value() {
+        // Address lines past the end of the ROM aren't connected, so a 16KB image in 32KB
+        // mode shows up twice
+        let offset = match bank_mode.value() {
             0 | 1 => {
                 // 32KB mode: ignore low bit of bank number
-                let bank = (prg_bank_num >> 1) % (self.prg_rom_banks / 2);
+                let bank = (prg_bank_num >> 1) % (prg_rom_banks / 2).max(1);
                 bank * 0x8000 + address.as_usize()
             }
             2 => {
@@ -133,22 +134,23 @@ impl Mmc1 {
                 if address < 0x4000 {
                     address.as_usize() // First 16KB bank
                 } else {
-                    let bank = prg_bank_num % self.prg_rom_banks;
+                    let bank = prg_bank_num % prg_rom_banks;
                     bank * 0x4000 + (address.as_usize() & 0x3FFF)
                 }
             }
             3 => {
                 // Switch $8000, fix last bank at $C000
                 if address < 0x4000 {
-                    let bank = prg_bank_num % self.prg_rom_banks;
+                    let bank = prg_bank_num % prg_rom_banks;
                     bank * 0x4000 + address.as_usize()
                 } else {
-                    let last_bank = self.prg_rom_banks - 1;
+                    let last_bank = prg_rom_banks - 1;
                     last_bank * 0x4000 + (address.as_usize() & 0x3FFF)
                 }
             }
             _ => unreachable!(),
-        }
+        };
+        offset % (prg_rom_banks * 0x4000)
     }
 
     fn map_chr_address(&self, address: Address) -> Address {
@@ -191,6 +193,16 @@ impl Mapper for Mmc1 {
         }
     }
 
+    fn power_on(&mut self) {
+        // Registers as in `new`, the CHR memory is left alone
+        self.shift_register = Byte::new(0x10);
+        self.shift_count = 0;
+        self.control = Byte::new(0x0C);
+        self.chr_bank_0 = Byte::default();
+        self.chr_bank_1 = Byte::default();
+        self.prg_bank = Byte::default();
+    }
+
     fn load_chr(&mut self, data: Vec<Byte>) {
         if data.is_empty() {
             self.chr = vec![Byte::default(); CHR_RAM_SIZE];
@@ -217,3 +229,31 @@ impl Mapper for Mmc1 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Five serial writes of `bank` to the PRG bank register
+    fn select_prg_bank(mmc1: &mut Mmc1, bank: u8) {
+        for bit in 0..5 {
+            mmc1.write(Address::new(0xe000), Byte::new((bank >> bit) & 1));
+        }
+    }
+
+    #[test]
+    fn power_on_resets_registers() {
+        let mut mmc1 = Mmc1::new(4);
+        select_prg_bank(&mut mmc1, 2);
+        assert_eq!(mmc1.map_address(Address::new(0x0000)), 2 * 0x4000);
+        // Two bits of another register write are left in the shift register
+        mmc1.write(Address::new(0xe000), Byte::new(0x01));
+        mmc1.write(Address::new(0xe000), Byte::new(0x01));
+
+        mmc1.power_on();
+        assert_eq!(mmc1.map_address(Address::new(0x0000)), 0x0000);
+
+        select_prg_bank(&mut mmc1, 3);
+        assert_eq!(mmc1.map_address(Address::new(0x0000)), 3 * 0x4000);
+    }
+}
@@ -2,7 +2,7 @@
 //! The iNES format assigns mapper 0 to NROM.
 //! The suffixes 128 and 256 refer to kilobits by Nintendo's own designation;
 
-use crate::cartridge::mappers::{Mapper, MapperId};
+use crate::cartridge::mappers::{Mapper, MapperHooks, MapperId};
 use crate::{Address, Byte};
 
 const CHR_RAM_SIZE: usize = 8192;
@@ -11,12 +11,21 @@ const CHR_RAM_SIZE: usize = 8192;
 pub struct Nrom<const PRG_ROM_BANKS: usize> {
     chr: Vec<Byte>,
     is_chr_ram: bool,
+    /// The ROM also drives the data bus during writes (NES 2.0 submapper 2)
+    bus_conflicts: bool,
 }
 
 pub type Nrom128 = Nrom<1>;
 pub type Nrom256 = Nrom<2>;
 
 impl<const N: usize> Nrom<N> {
+    pub fn with_bus_conflicts(bus_conflicts: bool) -> Self {
+        Self {
+            bus_conflicts,
+            ..Self::default()
+        }
+    }
+
     fn load_chr_data(&mut self, data: Vec<Byte>) {
         if data.is_empty() {
             self.chr = vec![Byte::default(); CHR_RAM_SIZE];
@@ -45,7 +54,8 @@ impl<const N: usize> Nrom<N> {
 
 impl<const N: usize> Mapper for Nrom<N> {
     fn map_address(&self, address: Address) -> usize {
-        address.value() as usize % (N * 0x4000)
+        // NROM-128 leaves A14 unconnected, mirroring its 16KB at $C000
+        address.as_usize() & (N * 0x4000 - 1)
     }
 
     fn write(&mut self, _: Address, _: Byte) {}
@@ -61,12 +71,16 @@ impl<const N: usize> Mapper for Nrom<N> {
     fn write_chr(&mut self, address: Address, value: Byte) {
         self.write_char_data(address, value);
     }
+
+    fn hooks(&self) -> MapperHooks {
+        match self.bus_conflicts {
+            true => MapperHooks::BUS_CONFLICTS,
+            false => MapperHooks::empty(),
+        }
+    }
 }
 
 impl<const PRG_ROM_BANKS: usize> MapperId for Nrom<PRG_ROM_BANKS> {
-    const ID: u8 = 0;
-
-    fn name(&self) -> &'static str {
-        "NROM"
-    }
+    const ID: u16 = 0;
+    const NAME: &'static str = "NROM";
 }
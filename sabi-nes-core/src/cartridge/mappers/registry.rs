@@ -0,0 +1,308 @@
+//! Registry of the mappers supported by sabi-nes.
+//!
+//! Maps iNES / NES 2.0 mapper numbers (and submappers) to descriptors with the
+//! mapper name, the boards it covers, what the boards can do and how to construct it.
+//! Also knows the names of common mappers which aren't supported yet,
+//! so loading such a ROM gives a readable error.
+
+use crate::cartridge::mappers::{Mapper, MapperId, Mmc1, Nrom128, Nrom256, Uxrom};
+use anyhow::{Error, anyhow};
+use bitflags::bitflags;
+use std::fmt::{Display, Formatter};
+
+bitflags! {
+    /// Features of the boards covered by a mapper
+    #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+    pub struct MapperCapabilities: u8 {
+        /// Boards may carry battery-backed PRG RAM
+        const BATTERY         = 0b0000_0001;
+        /// Mapper can raise CPU IRQs
+        const IRQ             = 0b0000_0010;
+        /// Boards carry an expansion sound chip
+        const EXPANSION_AUDIO = 0b0000_0100;
+    }
+}
+
+/// Cartridge properties needed to construct a mapper
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CartridgeInfo {
+    /// Number of 16kB PRG ROM banks
+    pub prg_rom_banks: usize,
+    /// NES 2.0 submapper number (0 for iNES 1.0)
+    pub submapper: u8,
+}
+
+impl CartridgeInfo {
+    /// Whether a discrete board has bus conflicts. Only for UxROM, CNROM and AxROM (mappers 2, 3
+    /// and 7), where NES 2.0 submappers 1 and 2 select boards without and with bus conflicts;
+    /// any other submapper uses the mapper default.
+    pub fn bus_conflicts(&self, mapper_default: bool) -> bool {
+        match self.submapper {
+            1 => false,
+            2 => true,
+            _ => mapper_default,
+        }
+    }
+}
+
+pub struct MapperDescriptor {
+    /// iNES / NES 2.0 mapper number
+    pub id: u16,
+    /// Submapper this descriptor is specific to, `None` if it covers all of them
+    pub submapper: Option<u8>,
+    pub name: &'static str,
+    /// Names of the cartridge boards using this mapper
+    pub boards: &'static [&'static str],
+    pub capabilities: MapperCapabilities,
+    constructor: fn(&CartridgeInfo) -> Box<dyn Mapper>,
+}
+
+impl MapperDescriptor {
+    pub fn create(&self, info: &CartridgeInfo) -> Box<dyn Mapper> {
+        (self.constructor)(info)
+    }
+}
+
+impl Display for MapperDescriptor {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:03}", self.id)?;
+        if let Some(submapper) = self.submapper {
+            write!(f, ".{submapper}")?;
+        }
+        write!(f, " {} ({})", self.name, self.boards.join(", "))
+    }
+}
+
+pub static SUPPORTED_MAPPERS: &[MapperDescriptor] = &[
+    MapperDescriptor {
+        id: Nrom128::ID,
+        submapper: None,
+        name: Nrom128::NAME,
+        boards: &["NES-NROM-128", "NES-NROM-256", "HVC-NROM"],
+        capabilities: MapperCapabilities::empty(),
+        constructor: nrom,
+    },
+    MapperDescriptor {
+        id: Mmc1::ID,
+        submapper: None,
+        name: Mmc1::NAME,
+        boards: &[
+            "SAROM", "SBROM", "SCROM", "SEROM", "SGROM", "SKROM", "SLROM", "SNROM", "SUROM",
+        ],
+        capabilities: MapperCapabilities::BATTERY,
+        constructor: mmc1,
+    },
+    MapperDescriptor {
+        id: Uxrom::ID,
+        submapper: None,
+        name: Uxrom::NAME,
+        boards: &["NES-UNROM", "NES-UOROM", "HVC-UNROM", "HVC-UOROM"],
+        capabilities: MapperCapabilities::empty(),
+        constructor: uxrom,
+    },
+];
+
+// Common mappers which are not supported (yet), used for error messages.
+const KNOWN_MAPPERS: &[(u16, &str)] = &[
+    (3, "CNROM"),
+    (4, "MMC3"),
+    (5, "MMC5"),
+    (7, "AxROM"),
+    (9, "MMC2"),
+    (10, "MMC4"),
+    (11, "Color Dreams"),
+    (13, "CPROM"),
+    (16, "Bandai FCG"),
+    (19, "Namco 163"),
+    (21, "VRC4a/VRC4c"),
+    (22, "VRC2a"),
+    (23, "VRC2b/VRC4e"),
+    (24, "VRC6a"),
+    (25, "VRC4b/VRC4d"),
+    (26, "VRC6b"),
+    (34, "BNROM/NINA-001"),
+    (66, "GxROM"),
+    (69, "Sunsoft FME-7"),
+    (71, "Camerica"),
+    (85, "VRC7"),
+    (206, "Namco 118"),
+];
+
+// Manufacturer prefixes of board names, which dumps use inconsistently
+const BOARD_PREFIXES: &[&str] = &["NES-", "HVC-", "UNL-", "BTL-", "BMC-"];
+
+fn nrom(info: &CartridgeInfo) -> Box<dyn Mapper> {
+    match info.prg_rom_banks {
+        1 => Box::new(Nrom128::default()),
+        _ => Box::new(Nrom256::default()),
+    }
+}
+
+fn mmc1(info: &CartridgeInfo) -> Box<dyn Mapper> {
+    Box::new(Mmc1::new(info.prg_rom_banks))
+}
+
+fn uxrom(info: &CartridgeInfo) -> Box<dyn Mapper> {
+    // UNROM and UOROM have bus conflicts, only some clone boards avoid them
+    Box::new(Uxrom::new(info.prg_rom_banks, info.bus_conflicts(true)))
+}
+
+/// Find the descriptor for a mapper number and submapper.
+/// A descriptor specific to the submapper wins over a generic one.
+pub fn find_mapper(id: u16, submapper: u8) -> Option<&'static MapperDescriptor> {
+    let mut candidates = SUPPORTED_MAPPERS.iter().filter(|mapper| mapper.id == id);
+
+    candidates
+        .clone()
+        .find(|mapper| mapper.submapper == Some(submapper))
+        .or_else(|| candidates.find(|mapper| mapper.submapper.is_none()))
+}
+
+/// Find the mapper for a board name such as "NES-SNROM", as used by UNIF files
+pub fn find_board(board: &str) -> Option<&'static MapperDescriptor> {
+    let board = board.trim().to_ascii_uppercase();
+    let board = strip_board_prefix(&board);
+
+    SUPPORTED_MAPPERS.iter().find(|mapper| {
+        mapper
+            .boards
+            .iter()
+            .any(|known| strip_board_prefix(known) == board)
+    })
+}
+
+fn strip_board_prefix(board: &str) -> &str {
+    BOARD_PREFIXES
+        .iter()
+        .find_map(|prefix| board.strip_prefix(prefix))
+        .unwrap_or(board)
+}
+
+/// Name of a mapper, whether it's supported or not
+pub fn mapper_name(id: u16) -> Option<&'static str> {
+    SUPPORTED_MAPPERS
+        .iter()
+        .find(|mapper| mapper.id == id)
+        .map(|mapper| mapper.name)
+        .or_else(|| {
+            KNOWN_MAPPERS
+                .iter()
+                .find(|(known_id, _)| *known_id == id)
+                .map(|(_, name)| *name)
+        })
+}
+
+/// Error describing an unsupported mapper, e.g. "mapper 5 (MMC5) not supported"
+pub fn unsupported_mapper(id: u16, submapper: u8) -> Error {
+    let submapper = match submapper {
+        0 => String::new(),
+        _ => format!(", submapper {submapper}"),
+    };
+
+    match mapper_name(id) {
+        Some(name) => anyhow!("mapper {id} ({name}{submapper}) not supported"),
+        None => anyhow!("mapper {id}{submapper} not supported"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mappers::MapperHooks;
+    use crate::cartridge::{CHR_ROM_BANK_SIZE, PRG_ROM_BANK_SIZE, Rom};
+
+    #[test]
+    fn find_supported_mappers() {
+        assert_eq!(find_mapper(0, 0).map(|m| m.name), Some("NROM"));
+        assert_eq!(find_mapper(1, 0).map(|m| m.name), Some("MMC1"));
+        // Generic descriptors cover all submappers
+        assert_eq!(find_mapper(1, 5).map(|m| m.name), Some("MMC1"));
+        assert_eq!(find_mapper(2, 0).map(|m| m.name), Some("UxROM"));
+        assert!(find_mapper(4, 0).is_none());
+    }
+
+    #[test]
+    fn find_boards() {
+        assert_eq!(find_board("NES-NROM-256").map(|m| m.id), Some(0));
+        assert_eq!(find_board("NROM").map(|m| m.id), Some(0));
+        assert_eq!(find_board("HVC-SNROM").map(|m| m.id), Some(1));
+        assert_eq!(find_board("nes-slrom").map(|m| m.id), Some(1));
+        assert_eq!(find_board("UNROM").map(|m| m.id), Some(2));
+        assert!(find_board("NES-TLROM").is_none());
+    }
+
+    #[test]
+    fn unsupported_mapper_errors_are_named() {
+        assert_eq!(
+            unsupported_mapper(5, 0).to_string(),
+            "mapper 5 (MMC5) not supported"
+        );
+        assert_eq!(
+            unsupported_mapper(4, 1).to_string(),
+            "mapper 4 (MMC3, submapper 1) not supported"
+        );
+        assert_eq!(
+            unsupported_mapper(255, 0).to_string(),
+            "mapper 255 not supported"
+        );
+    }
+
+    #[test]
+    fn bus_conflicts_from_submapper() {
+        let info = |submapper| CartridgeInfo {
+            prg_rom_banks: 2,
+            submapper,
+        };
+
+        assert!(info(0).bus_conflicts(true));
+        assert!(!info(0).bus_conflicts(false));
+        assert!(!info(1).bus_conflicts(true));
+        assert!(info(2).bus_conflicts(false));
+    }
+
+    #[test]
+    fn submapper_switches_on_bus_conflicts() {
+        let mapper_hooks = |submapper| {
+            let info = CartridgeInfo {
+                prg_rom_banks: 2,
+                submapper,
+            };
+            find_mapper(2, submapper).unwrap().create(&info).hooks()
+        };
+
+        assert!(mapper_hooks(0).contains(MapperHooks::BUS_CONFLICTS));
+        assert!(!mapper_hooks(1).contains(MapperHooks::BUS_CONFLICTS));
+        assert!(mapper_hooks(2).contains(MapperHooks::BUS_CONFLICTS));
+    }
+
+    #[test]
+    fn nrom_has_no_bus_conflict_submappers() {
+        let info = CartridgeInfo {
+            prg_rom_banks: 2,
+            submapper: 2,
+        };
+        let nrom = find_mapper(0, 2).unwrap().create(&info);
+
+        assert!(!nrom.hooks().contains(MapperHooks::BUS_CONFLICTS));
+    }
+
+    #[test]
+    fn loading_unsupported_mapper_fails() {
+        let mut data = vec![0x4e, 0x45, 0x53, 0x1a, 0x01, 0x01, 0x50, 0x00];
+        data.resize(16 + PRG_ROM_BANK_SIZE + CHR_ROM_BANK_SIZE, 0x00);
+
+        let error = Rom::from_bytes(&data).err().expect("MMC5 is not supported");
+
+        assert_eq!(error.to_string(), "mapper 5 (MMC5) not supported");
+    }
+
+    #[test]
+    fn supported_mapper_ids_are_unique() {
+        for (index, mapper) in SUPPORTED_MAPPERS.iter().enumerate() {
+            let duplicate = SUPPORTED_MAPPERS[index + 1..]
+                .iter()
+                .any(|other| other.id == mapper.id && other.submapper == mapper.submapper);
+            assert!(!duplicate, "duplicate descriptor for {mapper}");
+        }
+    }
+}
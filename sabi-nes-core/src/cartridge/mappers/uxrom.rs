@@ -0,0 +1,130 @@
+//! UxROM (Mapper 2) - Nintendo's UNROM and UOROM boards and their clones
+//!
+//! A discrete latch selects the PRG ROM bank at $8000, the last bank is fixed at $C000.
+//! Most boards don't disable the ROM during writes, so they have bus conflicts.
+//!
+//! Memory Map:
+//! - CPU $8000-$BFFF: 16KB PRG ROM bank (switchable)
+//! - CPU $C000-$FFFF: 16KB PRG ROM bank (fixed to the last bank)
+//! - PPU $0000-$1FFF: 8KB CHR RAM (CHR ROM on some clones)
+
+use crate::cartridge::mappers::{Mapper, MapperHooks, MapperId};
+use crate::{Address, Byte};
+
+const CHR_RAM_SIZE: usize = 8192;
+
+#[derive(Debug)]
+pub struct Uxrom {
+    /// Bank latch ($8000-$FFFF), UNROM decodes 3 bits and UOROM 4, clones up to 8
+    prg_bank: Byte,
+
+    /// Number of PRG ROM banks (16KB each)
+    prg_rom_banks: usize,
+
+    /// CHR ROM or RAM data
+    chr: Vec<Byte>,
+    is_chr_ram: bool,
+
+    /// The ROM also drives the data bus during writes to the latch
+    bus_conflicts: bool,
+}
+
+impl MapperId for Uxrom {
+    const ID: u16 = 2;
+    const NAME: &'static str = "UxROM";
+}
+
+impl Uxrom {
+    pub fn new(prg_rom_banks: usize, bus_conflicts: bool) -> Self {
+        Self {
+            prg_bank: Byte::default(),
+            prg_rom_banks,
+            chr: Vec::new(),
+            is_chr_ram: false,
+            bus_conflicts,
+        }
+    }
+}
+
+impl Mapper for Uxrom {
+    fn map_address(&self, address: Address) -> usize {
+        // Guard against headers with 0 PRG banks, the latch wraps around instead
+        let prg_rom_banks = self.prg_rom_banks.max(1);
+        let bank = match address < 0x4000 {
+            true => self.prg_bank.as_usize() % prg_rom_banks,
+            false => prg_rom_banks - 1,
+        };
+        bank * 0x4000 + (address.as_usize() & 0x3FFF)
+    }
+
+    fn write(&mut self, address: Address, value: Byte) {
+        if address >= 0x8000 {
+            self.prg_bank = value;
+        }
+    }
+
+    fn power_on(&mut self) {
+        self.prg_bank = Byte::default();
+    }
+
+    fn load_chr(&mut self, data: Vec<Byte>) {
+        if data.is_empty() {
+            self.chr = vec![Byte::default(); CHR_RAM_SIZE];
+            self.is_chr_ram = true;
+        } else {
+            self.chr = data;
+            self.is_chr_ram = false;
+        }
+    }
+
+    fn read_chr(&self, address: Address) -> Byte {
+        self.chr
+            .get(address.as_usize())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn write_chr(&mut self, address: Address, value: Byte) {
+        if self.is_chr_ram
+            && let Some(b) = self.chr.get_mut(address.as_usize())
+        {
+            *b = value;
+        }
+    }
+
+    fn hooks(&self) -> MapperHooks {
+        match self.bus_conflicts {
+            true => MapperHooks::BUS_CONFLICTS,
+            false => MapperHooks::empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_low_bank_and_fixes_last_bank() {
+        let mut uxrom = Uxrom::new(8, true);
+        assert_eq!(uxrom.map_address(Address::new(0x0123)), 0x0123);
+        assert_eq!(uxrom.map_address(Address::new(0x4123)), 7 * 0x4000 + 0x0123);
+
+        uxrom.write(Address::new(0xc000), Byte::new(0x03));
+        assert_eq!(uxrom.map_address(Address::new(0x0123)), 3 * 0x4000 + 0x0123);
+        assert_eq!(uxrom.map_address(Address::new(0x4123)), 7 * 0x4000 + 0x0123);
+
+        // Bank bits past the end of the ROM aren't connected
+        uxrom.write(Address::new(0x8000), Byte::new(0x0a));
+        assert_eq!(uxrom.map_address(Address::new(0x0000)), 2 * 0x4000);
+    }
+
+    #[test]
+    fn power_on_resets_the_latch() {
+        let mut uxrom = Uxrom::new(4, true);
+        uxrom.write(Address::new(0x8000), Byte::new(0x02));
+
+        uxrom.power_on();
+        assert_eq!(uxrom.map_address(Address::new(0x0000)), 0x0000);
+    }
+}
@@ -8,6 +8,16 @@ pub enum MirroringType {
     FourScreen,
 }
 
+/// Memory backing one of the four logical 1KiB nametables ($2000, $2400, $2800, $2C00)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NametableSource {
+    /// 1KiB page of the PPU VRAM (pages 0-1 are the console's internal VRAM,
+    /// pages 2-3 are the extra RAM of four-screen boards)
+    Vram(usize),
+    /// 1KiB page of nametable RAM or ROM provided by the mapper
+    Cartridge(usize),
+}
+
 impl MirroringType {
     pub fn new(is_four_screen: bool, is_vertical: bool) -> Self {
         match (is_four_screen, is_vertical) {
@@ -16,4 +26,17 @@ impl MirroringType {
             (false, false) => Self::Horizontal,
         }
     }
+
+    /// Memory backing the logical nametable `index` (0-3) with this arrangement
+    pub const fn nametable_source(self, index: usize) -> NametableSource {
+        let page = match self {
+            // $2000=$2400, $2800=$2C00
+            Self::Horizontal => (index >> 1) & 1,
+            // $2000=$2800, $2400=$2C00
+            Self::Vertical => index & 1,
+            Self::FourScreen => index & 3,
+        };
+
+        NametableSource::Vram(page)
+    }
 }
@@ -0,0 +1,487 @@
+//! Soft-patching of ROM images with IPS, UPS and BPS patches.
+//!
+//! Patches are applied to the raw file (header included), before it's parsed.
+//! UPS and BPS patches carry CRC32 checksums of the source, the target and the patch itself,
+//! which are all verified.
+
+use crate::cartridge::checksum::crc32;
+use anyhow::{Result, anyhow, bail};
+use std::path::{Path, PathBuf};
+
+const IPS_TAG: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+const UPS_TAG: &[u8] = b"UPS1";
+const BPS_TAG: &[u8] = b"BPS1";
+/// Largest patched image accepted, to reject corrupted sizes before allocating
+const MAX_IMAGE_SIZE: usize = 64 * 1024 * 1024;
+/// Source, target and patch CRC32 at the end of UPS and BPS patches
+const FOOTER_SIZE: usize = 12;
+
+/// Extensions of patches applied automatically when found next to a ROM
+const PATCH_EXTENSIONS: [&str; 3] = ["ips", "ups", "bps"];
+
+/// Apply `patches` in order to the ROM image `data`
+pub fn apply_patches<P: AsRef<[u8]>>(data: &[u8], patches: &[P]) -> Result<Vec<u8>> {
+    patches.iter().try_fold(data.to_vec(), |data, patch| {
+        apply_patch(&data, patch.as_ref())
+    })
+}
+
+/// Apply a single patch, detecting its format from the magic bytes
+pub fn apply_patch(data: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    if patch.starts_with(IPS_TAG) {
+        apply_ips(data, patch)
+    } else if patch.starts_with(UPS_TAG) {
+        apply_ups(data, patch)
+    } else if patch.starts_with(BPS_TAG) {
+        apply_bps(data, patch)
+    } else {
+        bail!("Unknown patch format")
+    }
+}
+
+/// Patches with the same name as the ROM (`game.ips`, `game.bps`, ... for `game.nes`)
+pub fn sibling_patches(rom_path: impl AsRef<Path>) -> Vec<PathBuf> {
+    PATCH_EXTENSIONS
+        .iter()
+        .map(|extension| rom_path.as_ref().with_extension(extension))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// The sibling patches of the ROM followed by `extra`, each file only once even if it's
+/// found next to the ROM and also given explicitly
+pub fn rom_patches(rom_path: impl AsRef<Path>, extra: &[PathBuf]) -> Vec<PathBuf> {
+    let mut patches = sibling_patches(rom_path);
+    for patch in extra {
+        if !patches.iter().any(|known| same_file(known, patch)) {
+            patches.push(patch.clone());
+        }
+    }
+
+    patches
+}
+
+fn same_file(first: &Path, second: &Path) -> bool {
+    match (first.canonicalize(), second.canonicalize()) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => first == second,
+    }
+}
+
+/// Reads big-endian numbers and UPS/BPS variable-length numbers from a patch
+struct PatchReader<'a> {
+    patch: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn new(patch: &'a [u8], position: usize) -> Self {
+        Self { patch, position }
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .position
+            .checked_add(length)
+            .and_then(|end| self.patch.get(self.position..end))
+            .ok_or_else(|| anyhow!("Patch is truncated"))?;
+        self.position += length;
+
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn big_endian(&mut self, length: usize) -> Result<usize> {
+        Ok(self
+            .bytes(length)?
+            .iter()
+            .fold(0, |value, &byte| value << 8 | usize::from(byte)))
+    }
+
+    /// Variable-length number where each byte holds 7 bits and bit 7 marks the last byte
+    fn number(&mut self) -> Result<usize> {
+        let mut value = 0usize;
+        let mut shift = 1usize;
+        loop {
+            let byte = self.byte()?;
+            value = usize::from(byte & 0x7f)
+                .checked_mul(shift)
+                .and_then(|bits| value.checked_add(bits))
+                .ok_or_else(|| anyhow!("Patch number overflows"))?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            // `checked_shl` only fails for shift amounts past the width, not on lost bits
+            if shift > usize::MAX >> 7 {
+                bail!("Patch number overflows");
+            }
+            shift <<= 7;
+            value = value
+                .checked_add(shift)
+                .ok_or_else(|| anyhow!("Patch number overflows"))?;
+        }
+    }
+}
+
+fn apply_ips(data: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    let mut output = data.to_vec();
+    let mut reader = PatchReader::new(patch, IPS_TAG.len());
+
+    loop {
+        let offset_bytes = reader.bytes(3)?;
+        if offset_bytes == IPS_EOF {
+            break;
+        }
+        let offset = PatchReader::new(offset_bytes, 0).big_endian(3)?;
+        let (length, record) = match reader.big_endian(2)? {
+            // RLE record: a run of a single byte
+            0 => {
+                let length = reader.big_endian(2)?;
+                (length, vec![reader.byte()?; length])
+            }
+            length => (length, reader.bytes(length)?.to_vec()),
+        };
+
+        if output.len() < offset + length {
+            output.resize(offset + length, 0);
+        }
+        output[offset..offset + length].copy_from_slice(&record);
+    }
+
+    // Optional extension: size to truncate the output to
+    if let Ok(truncate) = reader.big_endian(3) {
+        output.truncate(truncate);
+    }
+
+    Ok(output)
+}
+
+fn apply_ups(data: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    let checksums = verify_checksums(patch)?;
+    let mut reader = PatchReader::new(patch, UPS_TAG.len());
+    let source_size = reader.number()?;
+    let target_size = reader.number()?;
+
+    // UPS patches can be applied in both directions
+    let data_crc32 = crc32(data);
+    let (target_crc32, target_size) = match data_crc32 {
+        crc if crc == checksums.source && data.len() == source_size => {
+            (checksums.target, target_size)
+        }
+        crc if crc == checksums.target && data.len() == target_size => {
+            (checksums.source, source_size)
+        }
+        _ => bail!("UPS patch doesn't match the ROM (CRC32 {data_crc32:08X})"),
+    };
+
+    if target_size > MAX_IMAGE_SIZE {
+        bail!("UPS patch target is too large ({target_size} bytes)");
+    }
+
+    let mut output = data.to_vec();
+    output.resize(target_size, 0);
+    let mut position = 0usize;
+    while reader.position < patch.len() - FOOTER_SIZE {
+        position = position.saturating_add(reader.number()?);
+        loop {
+            let xor = reader.byte()?;
+            if xor == 0 {
+                position = position.saturating_add(1);
+                break;
+            }
+            if let Some(byte) = output.get_mut(position) {
+                *byte ^= xor;
+            }
+            position = position.saturating_add(1);
+        }
+    }
+
+    if crc32(&output) != target_crc32 {
+        bail!("UPS patched ROM doesn't match the expected CRC32");
+    }
+
+    Ok(output)
+}
+
+fn apply_bps(data: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    let checksums = verify_checksums(patch)?;
+    let data_crc32 = crc32(data);
+    if data_crc32 != checksums.source {
+        bail!("BPS patch doesn't match the ROM (CRC32 {data_crc32:08X})");
+    }
+
+    let mut reader = PatchReader::new(patch, BPS_TAG.len());
+    let source_size = reader.number()?;
+    let target_size = reader.number()?;
+    let metadata_size = reader.number()?;
+    reader.bytes(metadata_size)?;
+    if source_size != data.len() {
+        bail!("BPS patch expects a {source_size} byte ROM");
+    }
+    if target_size > MAX_IMAGE_SIZE {
+        bail!("BPS patch target is too large ({target_size} bytes)");
+    }
+
+    let mut output = Vec::with_capacity(target_size);
+    let mut source_offset = 0usize;
+    let mut target_offset = 0usize;
+    while reader.position < patch.len() - FOOTER_SIZE {
+        let action = reader.number()?;
+        let length = (action >> 2) + 1;
+        if output.len() + length > target_size {
+            bail!("BPS patch writes past the end of the target");
+        }
+        match action & 0b11 {
+            // SourceRead: copy from the same position of the source
+            0 => {
+                let start = output.len();
+                let bytes = data
+                    .get(start..start + length)
+                    .ok_or_else(|| anyhow!("BPS source read out of range"))?;
+                output.extend_from_slice(bytes);
+            }
+            // TargetRead: copy from the patch
+            1 => output.extend_from_slice(reader.bytes(length)?),
+            // SourceCopy: copy from anywhere in the source
+            2 => {
+                source_offset = relative_offset(source_offset, reader.number()?)?;
+                let bytes = data
+                    .get(source_offset..source_offset.saturating_add(length))
+                    .ok_or_else(|| anyhow!("BPS source copy out of range"))?;
+                output.extend_from_slice(bytes);
+                source_offset += length;
+            }
+            // TargetCopy: copy from the already written output, byte by byte as the ranges may overlap
+            _ => {
+                target_offset = relative_offset(target_offset, reader.number()?)?;
+                for _ in 0..length {
+                    let byte = *output
+                        .get(target_offset)
+                        .ok_or_else(|| anyhow!("BPS target copy out of range"))?;
+                    output.push(byte);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+
+    if output.len() != target_size || crc32(&output) != checksums.target {
+        bail!("BPS patched ROM doesn't match the expected CRC32");
+    }
+
+    Ok(output)
+}
+
+/// BPS offsets are stored as a magnitude with the sign in bit 0
+fn relative_offset(offset: usize, encoded: usize) -> Result<usize> {
+    let magnitude = encoded >> 1;
+    match encoded & 1 {
+        0 => offset.checked_add(magnitude),
+        _ => offset.checked_sub(magnitude),
+    }
+    .ok_or_else(|| anyhow!("BPS copy offset out of range"))
+}
+
+struct Checksums {
+    source: u32,
+    target: u32,
+}
+
+/// Check the CRC32 of the patch itself and return the source and target checksums
+fn verify_checksums(patch: &[u8]) -> Result<Checksums> {
+    if patch.len() < 4 + FOOTER_SIZE {
+        bail!("Patch is truncated");
+    }
+    let footer = &patch[patch.len() - FOOTER_SIZE..];
+    let checksum = |index: usize| {
+        u32::from_le_bytes([
+            footer[index * 4],
+            footer[index * 4 + 1],
+            footer[index * 4 + 2],
+            footer[index * 4 + 3],
+        ])
+    };
+
+    if crc32(&patch[..patch.len() - 4]) != checksum(2) {
+        bail!("Patch is corrupted (CRC32 mismatch)");
+    }
+
+    Ok(Checksums {
+        source: checksum(0),
+        target: checksum(1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn encode_number(mut value: usize) -> Vec<u8> {
+        let mut bytes = vec![];
+        loop {
+            let bits = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(0x80 | bits);
+                return bytes;
+            }
+            bytes.push(bits);
+            value -= 1;
+        }
+    }
+
+    fn with_footer(mut patch: Vec<u8>, source: &[u8], target: &[u8]) -> Vec<u8> {
+        patch.extend(crc32(source).to_le_bytes());
+        patch.extend(crc32(target).to_le_bytes());
+        patch.extend(crc32(&patch).to_le_bytes());
+        patch
+    }
+
+    #[test]
+    fn numbers() {
+        for value in [0, 1, 127, 128, 255, 16_511, 16_512, 1 << 20] {
+            let bytes = encode_number(value);
+            assert_eq!(PatchReader::new(&bytes, 0).number().unwrap(), value);
+        }
+
+        let overflowing = [[0x7f; 9].as_slice(), &[0x80]].concat();
+        assert!(PatchReader::new(&overflowing, 0).number().is_err());
+    }
+
+    #[test]
+    fn overflowing_ups_size_is_rejected() {
+        let mut patch = [b"UPS1".as_slice(), &[0x7f; 9], &[0x80], &[0; 8]].concat();
+        patch.extend(crc32(&patch).to_le_bytes());
+
+        assert!(apply_patch(&[0; 4], &patch).is_err());
+    }
+
+    #[test]
+    fn ips_records() {
+        let patch = [
+            b"PATCH".as_slice(),
+            &[0x00, 0x00, 0x01, 0x00, 0x02, 0xaa, 0xbb], // 2 bytes at 1
+            &[0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x03, 0xcc], // RLE: 3 x $CC at 5
+            b"EOF",
+        ]
+        .concat();
+
+        let output = apply_patch(&[0; 4], &patch).unwrap();
+
+        assert_eq!(output, [0x00, 0xaa, 0xbb, 0x00, 0x00, 0xcc, 0xcc, 0xcc]);
+    }
+
+    #[test]
+    fn ips_truncation() {
+        let patch = [b"PATCH".as_slice(), b"EOF", &[0x00, 0x00, 0x02]].concat();
+
+        assert_eq!(apply_patch(&[1, 2, 3, 4], &patch).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn ups_patch() {
+        let source = [1, 2, 3, 4];
+        let target = [1, 0x12, 3, 4, 5];
+        // Skip 1 byte, XOR the next one, then XOR the appended byte
+        let patch = [
+            b"UPS1".as_slice(),
+            &encode_number(4),
+            &encode_number(5),
+            &encode_number(1),
+            &[0x10, 0x00],
+            &encode_number(1),
+            &[0x05, 0x00],
+        ]
+        .concat();
+        let patch = with_footer(patch, &source, &target);
+
+        assert_eq!(apply_patch(&source, &patch).unwrap(), target);
+        // UPS patches work both ways
+        assert_eq!(apply_patch(&target, &patch).unwrap(), source);
+    }
+
+    #[test]
+    fn ups_patch_for_another_rom() {
+        let source = [1, 2, 3, 4];
+        let patch = [b"UPS1".as_slice(), &encode_number(4), &encode_number(4)].concat();
+        let patch = with_footer(patch, &source, &source);
+
+        assert!(apply_patch(&[9, 9, 9, 9], &patch).is_err());
+    }
+
+    #[test]
+    fn bps_patch() {
+        let source = [1, 2, 3, 4];
+        let target = [1, 2, 9, 9, 9, 3, 4, 1, 2];
+        let action = |command: usize, length: usize| encode_number((length - 1) << 2 | command);
+        let patch = [
+            b"BPS1".as_slice(),
+            &encode_number(source.len()),
+            &encode_number(target.len()),
+            &encode_number(0),
+            &action(0, 2), // SourceRead [1, 2]
+            &action(1, 1), // TargetRead [9]
+            &[9],
+            &action(3, 2), // TargetCopy [9, 9] from offset 2
+            &encode_number(2 << 1),
+            &action(2, 2), // SourceCopy [3, 4] from offset 2
+            &encode_number(2 << 1),
+            &action(2, 2), // SourceCopy [1, 2] from offset 0
+            &encode_number(4 << 1 | 1),
+        ]
+        .concat();
+        let patch = with_footer(patch, &source, &target);
+
+        assert_eq!(apply_patch(&source, &patch).unwrap(), target);
+    }
+
+    #[test]
+    fn corrupted_patch() {
+        let source = [1, 2, 3, 4];
+        let patch = [
+            b"BPS1".as_slice(),
+            &encode_number(4),
+            &encode_number(4),
+            &encode_number(0),
+        ]
+        .concat();
+        let mut patch = with_footer(patch, &source, &source);
+        patch[5] ^= 0xff;
+
+        assert_eq!(
+            apply_patch(&source, &patch).err().unwrap().to_string(),
+            "Patch is corrupted (CRC32 mismatch)"
+        );
+    }
+
+    #[test]
+    fn patches_applied_in_order() {
+        let first = [b"PATCH".as_slice(), &[0, 0, 0, 0, 1, 0xaa], b"EOF"].concat();
+        let second = [b"PATCH".as_slice(), &[0, 0, 0, 0, 1, 0xbb], b"EOF"].concat();
+
+        assert_eq!(apply_patches(&[0, 0], &[first, second]).unwrap(), [0xbb, 0]);
+    }
+
+    #[test]
+    fn patch_next_to_rom_and_given_explicitly_is_listed_once() {
+        let directory =
+            std::env::temp_dir().join(format!("sabi-nes-patches-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let rom_path = directory.join("game.nes");
+        let sibling = directory.join("game.ips");
+        let other = directory.join("fix.ips");
+        std::fs::write(&sibling, b"PATCHEOF").unwrap();
+        std::fs::write(&other, b"PATCHEOF").unwrap();
+
+        let explicit = [directory.join(".").join("game.ips"), other.clone()];
+        let patches = rom_patches(&rom_path, &explicit);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(patches, [sibling, other]);
+    }
+}
@@ -1,133 +1,55 @@
-use crate::Byte;
-use crate::cartridge::mappers::{Mapper, Mmc1, Nrom128, Nrom256};
-use crate::cartridge::{CHR_ROM_BANK_SIZE, MirroringType, PRG_ROM_BANK_SIZE};
-use anyhow::{Result, anyhow, bail};
-use bitflags::bitflags;
+use crate::cartridge::database::{GameEntry, find_game};
+use crate::cartridge::header::{HEADER_SIZE, HeaderFormat, HeaderParsing, RomHeader, TimingRegion};
+use crate::cartridge::mappers::{CartridgeInfo, Mapper, find_mapper, unsupported_mapper};
+use crate::cartridge::patch::apply_patches;
+use crate::cartridge::{CHR_ROM_BANK_SIZE, ConsoleType, MirroringType};
+use crate::{Address, Byte};
+use anyhow::{Context, Result, anyhow};
 use log::debug;
-use std::path::Path;
-
-/// "NES" followed by MS-DOS end-of-file used to recognize .NES (iNES) files
-const NES_TAG: [u8; 4] = [0x4e, 0x45, 0x53, 0x1a];
-
-bitflags! {
-    #[derive(Debug, Copy, Clone)]
-    struct ControlByte1: u8 {
-        const MIRRORING               = 0b0000_0001; // 1 for vertical, 0 for horizontal
-        const BATTERY_BACKED_RAM      = 0b0000_0010;
-        const HAS_TRAINER             = 0b0000_0100;
-        const FOUR_SCREEN_VRAM_LAYOUT = 0b0000_1000;
-        const MAPPER_TYPE1            = 0b0001_0000; // first bit of mapper type
-        const MAPPER_TYPE2            = 0b0010_0000; // second bit of mapper type
-        const MAPPER_TYPE3            = 0b0100_0000; // third bit of mapper type
-        const MAPPER_TYPE4            = 0b1000_0000; // fourth bit of mapper type
-    }
-}
+use std::path::{Path, PathBuf};
 
-impl ControlByte1 {
-    pub fn mapper_bits_lo(&self) -> Byte {
-        Byte::new(self.bits()) >> 4
-    }
-}
+mod unif;
 
-bitflags! {
-    #[derive(Debug, Copy, Clone)]
-    struct ControlByte2: u8 {
-        const INES_V1_FIRST   = 0b0000_0001; // 0 for iNES v1 format
-        const INES_V1_SECOND  = 0b0000_0010; // 0 for iNES v1 format
-        const INES_FMT_FIRST  = 0b0000_0100; // if INES_FMT bits are == 10, then it's NES2.0 format,
-        const INES_FMT_SECOND = 0b0000_1000; // if they are == 00, then it's iNES v1 format
-        const MAPPER_TYPE5    = 0b0001_0000; // fifth bit of mapper type
-        const MAPPER_TYPE6    = 0b0010_0000; // sixth bit of mapper type
-        const MAPPER_TYPE7    = 0b0100_0000; // seventh bit of mapper type
-        const MAPPER_TYPE8    = 0b1000_0000; // eighth bit of mapper type
+pub const TRAINER_SIZE: usize = 512;
 
-        const MAPPER_MASK     = 0b1111_0000;
-    }
-}
+/// Look up the PRG + CHR data in the game database. The sizes from the header are tried first,
+/// then everything after the header in case they're wrong.
+fn find_game_data(
+    data: &[u8],
+    prg_rom_start: usize,
+    header: &RomHeader,
+) -> Option<&'static GameEntry> {
+    let rom_data = data.get(prg_rom_start..)?;
+    let declared_size = header.prg_rom_size + header.chr_rom_size;
 
-impl ControlByte2 {
-    pub fn mapper_bits_hi(&self) -> Byte {
-        (*self & Self::MAPPER_MASK).bits().into()
+    match rom_data.get(..declared_size) {
+        Some(declared) if declared.len() < rom_data.len() => find_game(declared),
+        _ => None,
     }
+    .or_else(|| find_game(rom_data))
 }
 
-#[derive(Debug)]
-struct RomHeader {
-    /// Number of 16kB ROM banks (PRG ROM)
-    pub prg_rom_banks: usize,
-    /// Number o 8kB VROM banks (CHR ROM)
-    pub chr_rom_banks: usize,
-    pub control_byte1: ControlByte1,
-    pub control_byte2: ControlByte2,
-    /// Size of PRG RAM in 8kB units
-    #[allow(unused)]
-    pub prg_ram_units: usize,
-}
-
-impl TryFrom<&[u8]> for RomHeader {
-    type Error = anyhow::Error;
-
-    fn try_from(data: &[u8]) -> Result<Self> {
-        Self::validate(data)?;
+fn create_mapper(header: &RomHeader) -> Result<Box<dyn Mapper>> {
+    let (mapper_id, submapper) = (header.mapper_id, header.submapper);
+    let descriptor = find_mapper(mapper_id, submapper)
+        .ok_or_else(|| unsupported_mapper(mapper_id, submapper))?;
+    debug!("{} (id={mapper_id:03}) mapper detected", descriptor.name);
 
-        Ok(Self {
-            prg_rom_banks: data[4].into(),
-            chr_rom_banks: data[5].into(),
-            control_byte1: ControlByte1::from_bits_truncate(data[6]),
-            control_byte2: ControlByte2::from_bits_truncate(data[7]),
-            prg_ram_units: data[8].into(),
-        })
-    }
-}
-
-impl RomHeader {
-    fn validate(data: &[u8]) -> Result<()> {
-        if data[0..4] != NES_TAG {
-            bail!("File is not an iNES format - missing 'NES' tag");
-        }
-
-        let is_ines1 = ((data[7] >> 2) & 0b11) == 0;
-
-        if !is_ines1 {
-            bail!("Only iNes 1.0 format is currently supported");
-        }
-
-        if data[10..16].iter().any(|&byte| byte != 0) {
-            bail!("header bytes 10-15 are not 0 — file may not be iNES 1.0 format");
-        }
-
-        Ok(())
-    }
-
-    fn mapper(&self) -> Result<Box<dyn Mapper>> {
-        let mapper_id = self.mapper_id();
-        Ok(match mapper_id.value() {
-            0 => {
-                if self.prg_rom_banks == 1 {
-                    debug!("NROM128 (id=000) mapper detected");
-                    Box::new(Nrom128::default())
-                } else {
-                    debug!("NROM256 (id=000) mapper detected");
-                    Box::new(Nrom256::default())
-                }
-            }
-            1 => {
-                debug!("MMC1 (id=001) mapper detected");
-                Box::new(Mmc1::new(self.prg_rom_banks))
-            }
-            _ => bail!("Unsupported mapper type (ID: {mapper_id:03})"),
-        })
-    }
-
-    fn mapper_id(&self) -> Byte {
-        self.control_byte1.mapper_bits_lo() | self.control_byte2.mapper_bits_hi()
-    }
+    Ok(descriptor.create(&CartridgeInfo {
+        prg_rom_banks: header.prg_rom_banks(),
+        submapper,
+    }))
 }
 
 pub struct Rom {
     pub prg_rom: Vec<Byte>,
     pub mapper: Box<dyn Mapper>,
     pub screen_mirroring: MirroringType,
+    pub header: RomHeader,
+    /// 512 bytes copied into PRG RAM at $7000-$71FF before the game starts (mostly patched dumps)
+    pub trainer: Option<Vec<Byte>>,
+    /// Game database entry the header was corrected with
+    pub game: Option<&'static GameEntry>,
 }
 
 impl Rom {
@@ -137,63 +59,287 @@ impl Rom {
         mut mapper: Box<dyn Mapper>,
         screen_mirroring: MirroringType,
     ) -> Self {
+        let header = RomHeader {
+            format: HeaderFormat::INes,
+            mapper_id: 0,
+            submapper: 0,
+            prg_rom_size: prg_rom.len(),
+            chr_rom_size: chr_rom.len(),
+            prg_ram_size: 0,
+            prg_nvram_size: 0,
+            chr_ram_size: if chr_rom.is_empty() {
+                CHR_ROM_BANK_SIZE
+            } else {
+                0
+            },
+            chr_nvram_size: 0,
+            mirroring: screen_mirroring,
+            has_battery: false,
+            has_trainer: false,
+            timing: TimingRegion::Ntsc,
+            console_type: ConsoleType::Nes,
+            expansion_device: 0,
+        };
         mapper.load_chr(chr_rom);
         Self {
             prg_rom,
             mapper,
             screen_mirroring,
+            header,
+            trainer: None,
+            game: None,
         }
     }
+
+    /// Read PRG ROM at the given offset from $8000, `None` if nothing drives the bus
+    pub fn read_prg(&self, address: Address) -> Option<Byte> {
+        self.mapper.read_prg(&self.prg_rom, address)
+    }
 }
 
 impl Rom {
+    /// Path of the `.sav` file for the ROM at `rom_path`: next to the ROM,
+    /// or in `save_dir` if given
+    pub fn save_path(rom_path: impl AsRef<Path>, save_dir: Option<&Path>) -> PathBuf {
+        let rom_path = rom_path.as_ref();
+        let save_path = rom_path.with_extension("sav");
+
+        match (save_dir, save_path.file_name()) {
+            (Some(save_dir), Some(file_name)) => save_dir.join(file_name),
+            _ => save_path,
+        }
+    }
+
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
         let game_bytes = std::fs::read(path)?;
 
         Self::from_bytes(&game_bytes)
     }
 
+    /// Load a ROM with IPS/UPS/BPS patches applied in order
+    pub fn from_file_with_patches<P: AsRef<Path>>(
+        path: impl AsRef<Path>,
+        patches: &[P],
+    ) -> Result<Self> {
+        let game_bytes = std::fs::read(path)?;
+        let patches = patches
+            .iter()
+            .map(|patch| {
+                std::fs::read(patch)
+                    .with_context(|| format!("Failed to read patch `{}`", patch.as_ref().display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::from_bytes(&apply_patches(&game_bytes, &patches)?)
+    }
+
     pub fn from_bytes(data: &[u8]) -> Result<Self> {
-        let header: RomHeader = data
-            .get(0..16)
-            .ok_or_else(|| anyhow!("Failed to parse first 16 bytes for header"))?
-            .try_into()?;
-
-        let four_screen = header
-            .control_byte1
-            .contains(ControlByte1::FOUR_SCREEN_VRAM_LAYOUT);
-        let vertical_mirroring = header.control_byte1.contains(ControlByte1::MIRRORING);
-        let screen_mirroring = MirroringType::new(four_screen, vertical_mirroring);
-        let mut mapper = header.mapper()?;
-
-        let skip_trainer = header.control_byte1.contains(ControlByte1::HAS_TRAINER);
-        let prg_rom_size = header.prg_rom_banks * PRG_ROM_BANK_SIZE;
-        let chr_rom_size = header.chr_rom_banks * CHR_ROM_BANK_SIZE;
-        let prg_rom_start = 16 + usize::from(skip_trainer) * 512;
-        let chr_rom_start = prg_rom_start + prg_rom_size;
+        Self::from_bytes_with(data, HeaderParsing::default())
+    }
+
+    /// Same as [`Rom::from_bytes`], with control over how malformed headers are handled
+    pub fn from_bytes_with(data: &[u8], parsing: HeaderParsing) -> Result<Self> {
+        Self::from_image(RomImage::parse(data, parsing)?)
+    }
+
+    /// Create the mapper for an unpacked ROM image
+    pub fn from_image(image: RomImage) -> Result<Self> {
+        let RomImage {
+            header,
+            prg_rom,
+            chr_rom,
+            trainer,
+            game,
+        } = image;
+        let to_bytes = |data: Vec<u8>| data.into_iter().map(Byte::new).collect::<Vec<_>>();
+
+        let screen_mirroring = header.mirroring;
+        let mut mapper = create_mapper(&header)?;
+        mapper.load_chr(to_bytes(chr_rom));
+
+        log::info!(
+            "ROM loaded: format={:?}, mapper={}.{}, mirroring={screen_mirroring:?}",
+            header.format,
+            header.mapper_id,
+            header.submapper
+        );
+
+        Ok(Self {
+            prg_rom: to_bytes(prg_rom),
+            mapper,
+            screen_mirroring,
+            header,
+            trainer: trainer.map(to_bytes),
+            game,
+        })
+    }
+}
+
+/// Contents of a ROM file split into its parts, before a mapper is created for it.
+/// Unlike [`Rom`], this can be built for ROMs with unsupported mappers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomImage {
+    pub header: RomHeader,
+    pub prg_rom: Vec<u8>,
+    pub chr_rom: Vec<u8>,
+    pub trainer: Option<Vec<u8>>,
+    /// Game database entry the header was corrected with
+    pub game: Option<&'static GameEntry>,
+}
+
+impl RomImage {
+    /// Split an iNES, NES 2.0 or UNIF file into its parts
+    pub fn parse(data: &[u8], parsing: HeaderParsing) -> Result<Self> {
+        if data.starts_with(unif::UNIF_TAG) {
+            return unif::parse(data);
+        }
+
+        let mut header = RomHeader::parse(data, parsing)?;
+        let prg_rom_start = HEADER_SIZE + usize::from(header.has_trainer) * TRAINER_SIZE;
+
+        // Strict parsing takes the header as it is, and so does lenient parsing for NES 2.0
+        // headers, which describe the cartridge fully
+        let game = match (parsing, header.format) {
+            (HeaderParsing::Lenient, HeaderFormat::INes | HeaderFormat::ArchaicINes) => {
+                find_game_data(data, prg_rom_start, &header)
+            }
+            _ => None,
+        };
+        if let Some(game) = game {
+            log::info!("Found `{}` in the game database", game.title);
+            game.apply(&mut header);
+        }
+
+        let trainer = match header.has_trainer {
+            true => Some(
+                data.get(HEADER_SIZE..(HEADER_SIZE + TRAINER_SIZE))
+                    .ok_or_else(|| anyhow!("Failed to retrieve trainer data - not enough bytes"))?
+                    .to_vec(),
+            ),
+            false => None,
+        };
+        let chr_rom_start = prg_rom_start + header.prg_rom_size;
 
         let prg_rom = data
-            .get(prg_rom_start..(prg_rom_start + prg_rom_size))
+            .get(prg_rom_start..chr_rom_start)
             .ok_or_else(|| anyhow!("Failed to retrieve PRG ROM data - not enough bytes"))?
-            .iter()
-            .map(|&byte| Byte::new(byte))
-            .collect();
+            .to_vec();
         let chr_rom = data
-            .get(chr_rom_start..(chr_rom_start + chr_rom_size))
+            .get(chr_rom_start..(chr_rom_start + header.chr_rom_size))
             .ok_or_else(|| anyhow!("Failed to retrieve CHR ROM data - not enough bytes"))?
-            .iter()
-            .map(|&byte| Byte::new(byte))
-            .collect();
-
-        mapper.load_chr(chr_rom);
-
-        let mapper_id = header.mapper_id();
-        log::info!("ROM loaded: mapper={mapper_id}, mirroring={screen_mirroring:?}");
+            .to_vec();
 
         Ok(Self {
+            header,
             prg_rom,
-            mapper,
-            screen_mirroring,
+            chr_rom,
+            trainer,
+            game,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::PRG_ROM_BANK_SIZE;
+
+    #[test]
+    fn load_nes2_rom() {
+        // MMC1 (submapper 5), 32kB PRG ROM, 8kB CHR RAM, 8kB battery-backed PRG RAM, multi-region
+        let mut data = vec![
+            0x4e, 0x45, 0x53, 0x1a, 0x02, 0x00, 0x13, 0x08, 0x50, 0x00, 0x70, 0x07, 0x02, 0x00,
+            0x00, 0x01,
+        ];
+        data.resize(HEADER_SIZE + 2 * PRG_ROM_BANK_SIZE, 0xea);
+
+        let rom = Rom::from_bytes(&data).unwrap();
+
+        assert_eq!(rom.header.format, HeaderFormat::Nes2);
+        assert_eq!(rom.header.mapper_id, 1);
+        assert_eq!(rom.header.submapper, 5);
+        assert_eq!(rom.header.prg_nvram_size, 8192);
+        assert_eq!(rom.header.chr_ram_size, 8192);
+        assert_eq!(rom.header.timing, TimingRegion::MultiRegion);
+        assert_eq!(rom.header.expansion_device, 1);
+        assert_eq!(rom.screen_mirroring, MirroringType::Vertical);
+        assert_eq!(rom.prg_rom.len(), 2 * PRG_ROM_BANK_SIZE);
+        assert!(rom.trainer.is_none());
+        assert!(rom.game.is_none());
+    }
+
+    #[test]
+    fn header_corrected_from_game_database() {
+        let mut data = std::fs::read("tests/test_roms/nestest.nes").unwrap();
+        // Claim MMC5 with battery-backed RAM and vertical mirroring
+        data[6] = 0x53;
+
+        assert!(Rom::from_bytes_with(&data, HeaderParsing::Strict).is_err());
+        let rom = Rom::from_bytes(&data).unwrap();
+
+        assert_eq!(rom.game.map(|game| game.title), Some("nestest"));
+        assert_eq!(rom.header.mapper_id, 0);
+        assert!(!rom.header.has_battery);
+        assert_eq!(rom.screen_mirroring, MirroringType::Horizontal);
+    }
+
+    #[test]
+    fn nes2_header_not_corrected_from_game_database() {
+        let mut data = std::fs::read("tests/test_roms/nestest.nes").unwrap();
+        // Claim MMC5 with battery-backed RAM and vertical mirroring in a NES 2.0 header
+        data[6] = 0x53;
+        data[7] = 0x08;
+
+        let image = RomImage::parse(&data, HeaderParsing::Lenient).unwrap();
+
+        assert_eq!(image.header.format, HeaderFormat::Nes2);
+        assert!(image.game.is_none());
+        assert_eq!(image.header.mapper_id, 5);
+        assert!(image.header.has_battery);
+    }
+
+    #[test]
+    fn save_path_next_to_rom_or_in_save_dir() {
+        assert_eq!(
+            Rom::save_path("roms/zelda.nes", None),
+            PathBuf::from("roms/zelda.sav")
+        );
+        assert_eq!(
+            Rom::save_path("roms/zelda.nes", Some(Path::new("saves"))),
+            PathBuf::from("saves/zelda.sav")
+        );
+    }
+
+    #[test]
+    fn load_with_patches() {
+        let patch_path = std::env::temp_dir().join(format!("sabi-nes-{}.ips", std::process::id()));
+        // Overwrite the first PRG ROM byte
+        let patch = [
+            b"PATCH".as_slice(),
+            &[0x00, 0x00, 0x10, 0x00, 0x01, 0x42],
+            b"EOF",
+        ]
+        .concat();
+        std::fs::write(&patch_path, patch).unwrap();
+
+        let rom = Rom::from_file_with_patches("tests/test_roms/nestest.nes", &[&patch_path]);
+        std::fs::remove_file(&patch_path).unwrap();
+
+        assert_eq!(rom.unwrap().prg_rom[0], 0x42);
+    }
+
+    #[test]
+    fn trainer_is_kept() {
+        let mut data = vec![
+            0x4e, 0x45, 0x53, 0x1a, 0x01, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+        data.resize(HEADER_SIZE + TRAINER_SIZE, 0x77);
+        data.resize(HEADER_SIZE + TRAINER_SIZE + PRG_ROM_BANK_SIZE, 0xea);
+
+        let rom = Rom::from_bytes(&data).unwrap();
+
+        assert_eq!(rom.trainer, Some(vec![Byte::new(0x77); TRAINER_SIZE]));
+        assert!(rom.prg_rom.iter().all(|&byte| byte == 0xea));
+    }
+}
@@ -0,0 +1,195 @@
+//! UNIF (Universal NES Image Format) container.
+//!
+//! A 32-byte header ("UNIF", revision, padding) followed by chunks of a 4-byte ID,
+//! a little-endian 32-bit length and the chunk data. Instead of a mapper number,
+//! the `MAPR` chunk names the cartridge board. See <https://www.nesdev.org/wiki/UNIF>.
+
+use crate::cartridge::header::{HeaderFormat, RomHeader, TimingRegion};
+use crate::cartridge::mappers::find_board;
+use crate::cartridge::rom::RomImage;
+use crate::cartridge::{CHR_ROM_BANK_SIZE, ConsoleType, MirroringType};
+use anyhow::{Result, anyhow, bail};
+use log::info;
+use std::collections::BTreeMap;
+
+pub const UNIF_TAG: &[u8; 4] = b"UNIF";
+
+const UNIF_HEADER_SIZE: usize = 32;
+const PRG_RAM_SIZE: usize = 8192;
+
+pub fn parse(data: &[u8]) -> Result<RomImage> {
+    let mut chunks = data
+        .get(UNIF_HEADER_SIZE..)
+        .ok_or_else(|| anyhow!("UNIF header is truncated"))?;
+
+    let mut board = None;
+    let mut mirroring = MirroringType::Horizontal;
+    let mut has_battery = false;
+    let mut timing = TimingRegion::Ntsc;
+    // PRG0-PRGF and CHR0-CHRF, concatenated in order of the hex digit
+    let mut prg_chunks = BTreeMap::new();
+    let mut chr_chunks = BTreeMap::new();
+
+    while !chunks.is_empty() {
+        let (id, length) = match chunks.get(..8) {
+            Some(chunk_header) => (
+                &chunk_header[..4],
+                u32::from_le_bytes(chunk_header[4..8].try_into()?) as usize,
+            ),
+            None => bail!("UNIF chunk header is truncated"),
+        };
+        let chunk = chunks
+            .get(8..8 + length)
+            .ok_or_else(|| anyhow!("UNIF chunk `{}` is truncated", id.escape_ascii()))?;
+        chunks = &chunks[8 + length..];
+
+        match (id, chunk.first()) {
+            (b"MAPR", _) => board = Some(c_string(chunk)),
+            (b"NAME", _) => info!("UNIF game name: {}", c_string(chunk)),
+            (b"MIRR", Some(&value)) => {
+                mirroring = match value {
+                    1 => MirroringType::Vertical,
+                    4 => MirroringType::FourScreen,
+                    // One-screen and mapper-controlled mirroring are up to the mapper
+                    _ => MirroringType::Horizontal,
+                }
+            }
+            (b"BATR", Some(&value)) => has_battery = value != 0,
+            (b"TVCI", Some(&value)) => {
+                timing = match value {
+                    1 => TimingRegion::Pal,
+                    2 => TimingRegion::MultiRegion,
+                    _ => TimingRegion::Ntsc,
+                }
+            }
+            ([b'P', b'R', b'G', index], _) => {
+                prg_chunks.insert(chunk_index(*index)?, chunk);
+            }
+            ([b'C', b'H', b'R', index], _) => {
+                chr_chunks.insert(chunk_index(*index)?, chunk);
+            }
+            _ => {}
+        }
+    }
+
+    let board = board.ok_or_else(|| anyhow!("UNIF file has no board name (MAPR chunk)"))?;
+    let mapper = find_board(&board).ok_or_else(|| anyhow!("board `{board}` not supported"))?;
+    let prg_rom = prg_chunks
+        .into_values()
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+    let chr_rom = chr_chunks
+        .into_values()
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+
+    let header = RomHeader {
+        format: HeaderFormat::Unif,
+        mapper_id: mapper.id,
+        submapper: 0,
+        prg_rom_size: prg_rom.len(),
+        chr_rom_size: chr_rom.len(),
+        prg_ram_size: if has_battery { 0 } else { PRG_RAM_SIZE },
+        prg_nvram_size: if has_battery { PRG_RAM_SIZE } else { 0 },
+        chr_ram_size: if chr_rom.is_empty() {
+            CHR_ROM_BANK_SIZE
+        } else {
+            0
+        },
+        chr_nvram_size: 0,
+        mirroring,
+        has_battery,
+        has_trainer: false,
+        timing,
+        console_type: ConsoleType::Nes,
+        expansion_device: 0,
+    };
+
+    Ok(RomImage {
+        header,
+        prg_rom,
+        chr_rom,
+        trainer: None,
+        game: None,
+    })
+}
+
+/// Null-terminated UTF-8 string
+fn c_string(chunk: &[u8]) -> String {
+    let end = chunk
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(chunk.len());
+    String::from_utf8_lossy(&chunk[..end]).into_owned()
+}
+
+fn chunk_index(hex_digit: u8) -> Result<u32> {
+    char::from(hex_digit)
+        .to_digit(16)
+        .ok_or_else(|| anyhow!("invalid UNIF ROM chunk index `{}`", char::from(hex_digit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::{PRG_ROM_BANK_SIZE, Rom};
+    use crate::{Address, Byte};
+
+    fn unif(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut data = UNIF_TAG.to_vec();
+        data.extend(7u32.to_le_bytes());
+        data.resize(UNIF_HEADER_SIZE, 0);
+        for (id, chunk) in chunks {
+            data.extend(*id);
+            data.extend((chunk.len() as u32).to_le_bytes());
+            data.extend(*chunk);
+        }
+        data
+    }
+
+    #[test]
+    fn load_unif_rom() {
+        let prg0 = vec![0x00; PRG_ROM_BANK_SIZE];
+        let prg1 = vec![0x01; PRG_ROM_BANK_SIZE];
+        let chr0 = vec![0x02; CHR_ROM_BANK_SIZE];
+        // PRG chunks out of order, they're sorted by index
+        let data = unif(&[
+            (b"MAPR", b"NES-NROM-256\0"),
+            (b"NAME", b"Test\0"),
+            (b"PRG1", &prg1),
+            (b"PRG0", &prg0),
+            (b"CHR0", &chr0),
+            (b"MIRR", &[1]),
+            (b"BATR", &[1]),
+        ]);
+
+        let rom = Rom::from_bytes(&data).unwrap();
+
+        assert_eq!(rom.header.format, HeaderFormat::Unif);
+        assert_eq!(rom.header.mapper_id, 0);
+        assert!(rom.header.has_battery);
+        assert_eq!(rom.screen_mirroring, MirroringType::Vertical);
+        assert_eq!(rom.read_prg(Address::new(0x0000)), Some(Byte::new(0x00)));
+        assert_eq!(rom.read_prg(Address::new(0x4000)), Some(Byte::new(0x01)));
+        assert_eq!(rom.mapper.read_chr(Address::new(0x0000)), 0x02);
+    }
+
+    #[test]
+    fn unsupported_board() {
+        let data = unif(&[(b"MAPR", b"NES-TLROM\0"), (b"PRG0", &[0; 16])]);
+
+        let error = Rom::from_bytes(&data).err().unwrap();
+
+        assert_eq!(error.to_string(), "board `NES-TLROM` not supported");
+    }
+
+    #[test]
+    fn truncated_chunk() {
+        let mut data = unif(&[(b"MAPR", b"NES-NROM-128\0"), (b"PRG0", &[0; 16])]);
+        data.truncate(data.len() - 1);
+
+        assert!(Rom::from_bytes(&data).is_err());
+    }
+}
@@ -1,4 +1,7 @@
 mod addressing_mode;
+mod bus_access;
+mod clock;
+mod flat_memory;
 mod interrupts;
 mod memory;
 pub mod opcodes;
@@ -6,20 +9,22 @@ mod stack_pointer;
 mod status_register;
 
 pub use crate::cpu::addressing_mode::AddressingMode;
+pub use crate::cpu::bus_access::{BusAccess, BusAccessKind};
+pub use crate::cpu::clock::Clock;
+pub use crate::cpu::flat_memory::FlatMemory;
+pub use crate::cpu::interrupts::InterruptKind;
 pub use crate::cpu::memory::Memory;
 
 use crate::bus::Bus;
 use crate::cpu::interrupts::Interrupt;
-use crate::cpu::opcodes::{OPCODES_MAPPING, Opcode};
+use crate::cpu::opcodes::{Instruction, Opcode, decode};
 use crate::cpu::stack_pointer::StackPointer;
 use crate::cpu::status_register::StatusRegister;
-use crate::ppu::NmiStatus;
 use crate::utils::NthBit;
 use crate::{Address, Byte, Word};
-use anyhow::{Context, Result, anyhow, bail};
-use log::debug;
+use anyhow::{Context, Result};
+use log::{debug, warn};
 
-const PROGRAM_ROM_BEGIN_ADDR: Address = Address::new(0x0600);
 const RESET_VECTOR_BEGIN_ADDR: Address = Address::new(0xfffc);
 
 struct ByteUpdate {
@@ -27,37 +32,98 @@ struct ByteUpdate {
     new: Byte,
 }
 
-pub struct Cpu {
+/// A 6502 core, by default the NES's 2A03 wired to its [`Bus`].
+/// Any [`Memory`] with a [`Clock`] can host it, e.g. a [`FlatMemory`] for plain 6502 programs.
+pub struct Cpu<M = Bus> {
     pub accumulator: Byte,
     pub register_x: Byte,
     pub register_y: Byte,
     pub status_register: StatusRegister,
     pub program_counter: Address,
     stack_pointer: StackPointer,
-    bus: Bus,
+    bus: M,
+    bus_accesses: Option<Vec<BusAccess>>,
+    /// Address of the JAM opcode that halted the CPU, until the next reset
+    halted_at: Option<Address>,
+    /// The 2A03 ignores the decimal flag, other 6502s do BCD arithmetic with it
+    decimal_mode: bool,
+    interrupt_lines: InterruptLines,
+    /// A DMA held the CPU before the last read went through
+    halted_by_dma: bool,
+    /// What the step in progress did so far
+    current_step: StepResult,
 }
 
-impl Memory for Cpu {
-    fn read_byte(&mut self, addr: Address) -> Byte {
-        self.bus.read_byte(addr)
-    }
+/// What a call to [`Cpu::step`] did
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct StepResult {
+    /// CPU cycles that passed, including any interrupt sequence and DMA stalls
+    pub cycles: u64,
+    /// Interrupt serviced before the instruction. An NMI hijacking BRK counts too.
+    pub interrupt: Option<InterruptKind>,
+    /// Cycles out of `cycles` the CPU was halted by OAM or DMC DMA
+    pub dma_cycles: u64,
+}
 
-    fn write_byte(&mut self, addr: Address, value: Byte) {
-        self.bus.write_byte(addr, value);
+/// The interrupt lines as sampled at the end of the last two cycles. An instruction is followed
+/// by an interrupt if one was pending at the end of its second-to-last cycle.
+#[derive(Debug, Default, Copy, Clone)]
+struct InterruptLines {
+    /// NMI edge seen and not yet serviced
+    nmi: bool,
+    previous_nmi: bool,
+    /// IRQ asserted while the I flag was clear
+    irq: bool,
+    previous_irq: bool,
+}
+
+impl<M: Memory + Clock> Memory for Cpu<M> {
+    fn peek_byte(&self, addr: Address) -> Byte {
+        self.bus.peek_byte(addr)
     }
 
-    fn read_word(&mut self, addr: Address) -> Word {
-        self.bus.read_word(addr)
+    fn read_byte(&mut self, addr: Address) -> Byte {
+        self.halted_by_dma = false;
+        while let Some(access) = self.bus.run_dma_cycle(addr) {
+            self.halted_by_dma = true;
+            if let Some(accesses) = &mut self.bus_accesses {
+                accesses.push(access);
+            }
+            // The halted CPU doesn't sample its interrupt lines, an interrupt raised by the DMA
+            // is first seen at the end of the read it held
+            self.bus.tick();
+            self.current_step.dma_cycles += 1;
+        }
+
+        let value = self.bus.read_byte(addr);
+        if let Some(accesses) = &mut self.bus_accesses {
+            accesses.push(BusAccess {
+                cycle: self.bus.cycles(),
+                address: addr,
+                value,
+                kind: BusAccessKind::Read,
+            });
+        }
+
+        value
     }
 
-    fn write_word(&mut self, addr: Address, word: Word) {
-        self.bus.write_word(addr, word);
+    fn write_byte(&mut self, addr: Address, value: Byte) {
+        if let Some(accesses) = &mut self.bus_accesses {
+            accesses.push(BusAccess {
+                cycle: self.bus.cycles(),
+                address: addr,
+                value,
+                kind: BusAccessKind::Write,
+            });
+        }
+        self.bus.write_byte(addr, value);
     }
 }
 
-impl Cpu {
-    pub fn new(bus: Bus) -> Cpu {
-        Cpu {
+impl<M: Memory + Clock> Cpu<M> {
+    pub fn new(bus: M) -> Self {
+        Self {
             accumulator: Byte::default(),
             register_x: Byte::default(),
             register_y: Byte::default(),
@@ -65,10 +131,16 @@ impl Cpu {
             program_counter: Address::default(),
             stack_pointer: StackPointer::default(),
             bus,
+            bus_accesses: None,
+            halted_at: None,
+            decimal_mode: false,
+            interrupt_lines: InterruptLines::default(),
+            halted_by_dma: false,
+            current_step: StepResult::default(),
         }
     }
 
-    pub fn bus(&self) -> &Bus {
+    pub fn bus(&self) -> &M {
         &self.bus
     }
 
@@ -77,7 +149,7 @@ impl Cpu {
         self.bus.peek_byte(address)
     }
 
-    pub fn bus_mut(&mut self) -> &mut Bus {
+    pub fn bus_mut(&mut self) -> &mut M {
         &mut self.bus
     }
 
@@ -85,175 +157,219 @@ impl Cpu {
         self.stack_pointer
     }
 
-    pub fn load(&mut self, data: &[Byte]) {
-        for (index, &value) in data.iter().enumerate() {
-            let index = u16::try_from(index).unwrap();
-            let addr = Address::from(index);
-            self.write_byte(addr + PROGRAM_ROM_BEGIN_ADDR, value);
+    /// Do BCD arithmetic in ADC and SBC while the decimal flag is set, as a stock 6502 does.
+    /// Off by default, the NES's 2A03 has decimal mode disconnected.
+    pub fn set_decimal_mode(&mut self, enabled: bool) {
+        self.decimal_mode = enabled;
+    }
+
+    /// Address of the JAM opcode the CPU is halted on, `None` while it is running
+    pub fn halted_at(&self) -> Option<Address> {
+        self.halted_at
+    }
+
+    /// Start or stop recording every bus access of the CPU, one per cycle
+    pub fn record_bus_accesses(&mut self, enabled: bool) {
+        self.bus_accesses = match enabled {
+            true => Some(self.bus_accesses.take().unwrap_or_default()),
+            false => None,
+        };
+    }
+
+    /// Bus accesses recorded since the last call
+    pub fn take_bus_accesses(&mut self) -> Vec<BusAccess> {
+        self.bus_accesses
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Copy `data` into memory starting at `address`, without spending any cycles
+    pub fn load(&mut self, address: Address, data: &[Byte]) {
+        for (offset, &value) in (0..=u16::MAX).zip(data) {
+            self.bus.write_byte(address.wrapping_add(offset), value);
         }
     }
 
-    /// Execute a single CPU instruction.
-    pub fn step(&mut self) -> Result<()> {
-        // Handle NMI interrupt if pending
-        if self.bus.poll_nmi_status() == NmiStatus::Active {
-            self.interrupt(&interrupts::NMI);
+    /// Execute a single CPU instruction, preceded by the interrupt sequence if one is pending.
+    /// A halted CPU only spends a cycle, so the rest of the system keeps running.
+    pub fn step(&mut self) -> Result<StepResult> {
+        let start = self.bus.cycles();
+        self.current_step = StepResult::default();
+        self.execute()?;
+
+        Ok(StepResult {
+            cycles: self.bus.cycles() - start,
+            ..self.current_step
+        })
+    }
+
+    fn execute(&mut self) -> Result<()> {
+        if self.halted_at.is_some() {
+            // The address bus is stuck at $FFFF and interrupts are no longer serviced
+            self.dummy_read(Address::new(0xffff));
+            return Ok(());
         }
 
-        // Handle IRQ if pending and interrupt flag is clear
-        if self.bus.poll_irq_status()
-            && !self
-                .status_register
-                .contains(StatusRegister::INTERRUPT_DISABLE)
-        {
-            self.interrupt(&interrupts::IRQ);
+        let lines = self.interrupt_lines;
+        if lines.previous_nmi || lines.previous_irq {
+            self.interrupt();
         }
 
         let code = self.read_byte(self.program_counter);
         let instruction_pc = self.program_counter;
         self.program_counter = self.program_counter.wrapping_add(1u16);
-        self.bus.tick_one(); // opcode fetch cycle
+        self.tick(); // opcode fetch cycle
 
         let current_program_counter = self.program_counter;
-        let opcode = OPCODES_MAPPING
-            .get(&code)
-            .ok_or_else(|| anyhow!("Unknown opcode: {code:02X} at PC ${instruction_pc:04X}"))?;
+        let opcode = decode(code);
         let address = self
             .pc_operand_address(opcode)
             .with_context(|| format!("Failed to fetch address for {}", opcode.name))?;
-        let opcode_name = opcode.name;
-
-        match opcode_name {
-            "ADC" => self.adc(address),
-            "AND" => self.and(address),
-            "ASL" => self.asl(address, opcode.addressing_mode),
-            "BIT" => self.bit(address),
-            "BCC" => self.branch(!self.status_register.contains(StatusRegister::CARRY)),
-            "BCS" => self.branch(self.status_register.contains(StatusRegister::CARRY)),
-            "BEQ" => self.branch(self.status_register.contains(StatusRegister::ZERO)),
-            "BMI" => self.branch(self.status_register.contains(StatusRegister::NEGATIVE)),
-            "BNE" => self.branch(!self.status_register.contains(StatusRegister::ZERO)),
-            "BPL" => self.branch(!self.status_register.contains(StatusRegister::NEGATIVE)),
-            "BVC" => self.branch(!self.status_register.contains(StatusRegister::OVERFLOW)),
-            "BVS" => self.branch(self.status_register.contains(StatusRegister::OVERFLOW)),
-            "BRK" => {
-                self.program_counter = self.program_counter.wrapping_add(1u16); // skip the padding byte (BRK is a 2-byte instruction)
-                self.interrupt(&interrupts::BRK);
-                self.bus.tick(0); // drain any pending OAM DMA cycles
+
+        let status = self.status_register;
+        match opcode.instruction {
+            Instruction::Adc => self.adc(address),
+            Instruction::And => self.and(address),
+            Instruction::Asl => self.asl(address, opcode.addressing_mode),
+            Instruction::Bit => self.bit(address),
+            Instruction::Bcc => self.branch(!status.contains(StatusRegister::CARRY)),
+            Instruction::Bcs => self.branch(status.contains(StatusRegister::CARRY)),
+            Instruction::Beq => self.branch(status.contains(StatusRegister::ZERO)),
+            Instruction::Bmi => self.branch(status.contains(StatusRegister::NEGATIVE)),
+            Instruction::Bne => self.branch(!status.contains(StatusRegister::ZERO)),
+            Instruction::Bpl => self.branch(!status.contains(StatusRegister::NEGATIVE)),
+            Instruction::Bvc => self.branch(!status.contains(StatusRegister::OVERFLOW)),
+            Instruction::Bvs => self.branch(status.contains(StatusRegister::OVERFLOW)),
+            Instruction::Brk => {
+                // BRK is a 2-byte instruction, the padding byte is read and skipped
+                self.dummy_read(self.program_counter);
+                self.program_counter = self.program_counter.wrapping_add(1u16);
+                self.enter_interrupt(&interrupts::BRK);
                 return Ok(());
             }
-            "CLC" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Clc => {
+                self.dummy_read(self.program_counter);
                 self.status_register.set_carry_flag(false);
             }
-            "CLD" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Cld => {
+                self.dummy_read(self.program_counter);
                 self.status_register.set_decimal_flag(false);
             }
-            "CLI" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Cli => {
+                self.dummy_read(self.program_counter);
                 self.status_register.set_interrupt_flag(false);
             }
-            "CLV" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Clv => {
+                self.dummy_read(self.program_counter);
                 self.status_register.set_overflow_flag(false);
             }
-            "CMP" => self.compare(address, self.accumulator),
-            "CPX" => self.compare(address, self.register_x),
-            "CPY" => self.compare(address, self.register_y),
-            "DEC" => self.dec(address),
-            "DEX" => self.dex(),
-            "DEY" => self.dey(),
-            "EOR" => self.eor(address),
-            "INC" => self.inc(address),
-            "INX" => self.inx(),
-            "INY" => self.iny(),
-            "JMP" => self.program_counter = address,
-            "JSR" => self.jsr(),
-            "LDA" => self.lda(address),
-            "LDX" => self.ldx(address),
-            "LDY" => self.ldy(address),
-            "LSR" => self.lsr(address, opcode.addressing_mode),
-            "NOP" | "*NOP" => {
-                self.bus.tick_one(); // internal cycle
-            }
-            "ORA" => self.ora(address),
-            "PHA" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Cmp => self.compare(address, self.accumulator),
+            Instruction::Cpx => self.compare(address, self.register_x),
+            Instruction::Cpy => self.compare(address, self.register_y),
+            Instruction::Dec => self.dec(address),
+            Instruction::Dex => self.dex(),
+            Instruction::Dey => self.dey(),
+            Instruction::Eor => self.eor(address),
+            Instruction::Inc => self.inc(address),
+            Instruction::Inx => self.inx(),
+            Instruction::Iny => self.iny(),
+            Instruction::Jmp => self.program_counter = address,
+            Instruction::Jsr => self.jsr(),
+            Instruction::Lda => self.lda(address),
+            Instruction::Ldx => self.ldx(address),
+            Instruction::Ldy => self.ldy(address),
+            Instruction::Lsr => self.lsr(address, opcode.addressing_mode),
+            Instruction::Nop => match opcode.addressing_mode {
+                AddressingMode::Implied => self.dummy_read(self.program_counter),
+                // Unofficial NOPs with an operand read it like any other instruction
+                _ => self.dummy_read(address),
+            },
+            Instruction::Ora => self.ora(address),
+            Instruction::Pha => {
+                self.dummy_read(self.program_counter);
                 self.write_byte(self.stack_pointer.address(), self.accumulator);
                 self.stack_pointer.decrement();
-                self.bus.tick_one(); // push cycle
+                self.tick(); // push cycle
             }
-            "PHP" => self.php(),
-            "PLA" => self.pla(),
-            "PLP" => self.plp(),
-            "ROL" => self.rol(address, opcode.addressing_mode),
-            "ROR" => self.ror(address, opcode.addressing_mode),
-            "RTI" => {
+            Instruction::Php => self.php(),
+            Instruction::Pla => self.pla(),
+            Instruction::Plp => self.plp(),
+            Instruction::Rol => self.rol(address, opcode.addressing_mode),
+            Instruction::Ror => self.ror(address, opcode.addressing_mode),
+            Instruction::Rti => {
                 self.rti();
-                self.bus.tick(0); // drain any pending OAM DMA cycles
                 return Ok(());
             }
-            "RTS" => {
+            Instruction::Rts => {
                 self.rts();
-                self.bus.tick(0); // drain any pending OAM DMA cycles
                 return Ok(());
             }
-            "SBC" | "*SBC" => self.sbc(address),
-            "SEC" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Sbc => self.sbc(address),
+            Instruction::Sec => {
+                self.dummy_read(self.program_counter);
                 self.status_register.set_carry_flag(true);
             }
-            "SED" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Sed => {
+                self.dummy_read(self.program_counter);
                 self.status_register.set_decimal_flag(true);
             }
-            "SEI" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Sei => {
+                self.dummy_read(self.program_counter);
                 self.status_register.set_interrupt_flag(true);
             }
-            "STA" => {
+            Instruction::Sta => {
                 self.write_byte(address, self.accumulator);
-                self.bus.tick_one(); // data write cycle
+                self.tick(); // data write cycle
             }
-            "STX" => {
+            Instruction::Stx => {
                 self.write_byte(address, self.register_x);
-                self.bus.tick_one(); // data write cycle
+                self.tick(); // data write cycle
             }
-            "STY" => {
+            Instruction::Sty => {
                 self.write_byte(address, self.register_y);
-                self.bus.tick_one(); // data write cycle
+                self.tick(); // data write cycle
             }
-            "TAX" => self.tax(),
-            "TAY" => self.tay(),
-            "TSX" => self.tsx(),
-            "TXA" => self.txa(),
-            "TXS" => {
-                self.bus.tick_one(); // internal cycle
+            Instruction::Tax => self.tax(),
+            Instruction::Tay => self.tay(),
+            Instruction::Tsx => self.tsx(),
+            Instruction::Txa => self.txa(),
+            Instruction::Txs => {
+                self.dummy_read(self.program_counter);
                 self.stack_pointer.set(self.register_x);
             }
-            "TYA" => self.tya(),
-
-            "*LAX" => self.lax(address),
-            "*SAX" => self.sax(address),
-            "*DCP" => self.dcp(address),
-            "*ISB" => self.isb(address),
-            "*SLO" => self.slo(address),
-            "*RLA" => self.rla(address, opcode.addressing_mode),
-            "*SRE" => self.sre(address),
-            "*RRA" => self.rra(address, opcode.addressing_mode),
-            "*ANC" => self.anc(address),
-            "*ALR" => self.alr(address),
-            "*ARR" => self.arr(address),
-            "*ANE" => self.ane(address),
-            "*LXA" => self.lxa(address),
-            "*AXS" => self.axs(address),
-            "*SHA" | "*SHX" | "*SHY" | "*SHS" | "*LAS" => {} // unstable — treat as NOP
-            _ => bail!("Unsupported opcode name: {opcode_name}"),
-        }
-
-        // Drain any pending cycles accumulated during the instruction (e.g. OAM DMA stall).
-        self.bus.tick(0);
+            Instruction::Tya => self.tya(),
+
+            Instruction::Lax => self.lax(address),
+            Instruction::Sax => self.sax(address),
+            Instruction::Dcp => self.dcp(address),
+            Instruction::Isb => self.isb(address),
+            Instruction::Slo => self.slo(address),
+            Instruction::Rla => self.rla(address, opcode.addressing_mode),
+            Instruction::Sre => self.sre(address),
+            Instruction::Rra => self.rra(address, opcode.addressing_mode),
+            Instruction::Anc => self.anc(address),
+            Instruction::Alr => self.alr(address),
+            Instruction::Arr => self.arr(address),
+            Instruction::Ane => self.ane(address),
+            Instruction::Lxa => self.lxa(address),
+            Instruction::Axs => self.axs(address),
+            Instruction::Sha => {
+                self.unstable_store(address, self.register_y, self.accumulator & self.register_x);
+            }
+            Instruction::Shx => self.unstable_store(address, self.register_y, self.register_x),
+            Instruction::Shy => self.unstable_store(address, self.register_x, self.register_y),
+            Instruction::Shs => {
+                self.stack_pointer.set(self.accumulator & self.register_x);
+                self.unstable_store(address, self.register_y, self.stack_pointer.value());
+            }
+            Instruction::Las => self.las(address),
+            Instruction::Jam => {
+                warn!("CPU halted by JAM opcode {code:02X} at ${instruction_pc:04X}");
+                self.dummy_read(self.program_counter);
+                self.halted_at = Some(instruction_pc);
+            }
+        }
 
         if current_program_counter == self.program_counter {
             let len: u16 = opcode.length().try_into()?;
@@ -269,35 +385,78 @@ impl Cpu {
         }
     }
 
-    pub fn reset(&mut self) -> Result<()> {
+    /// Switch the console on: the system around the CPU powers on, then the CPU comes up with
+    /// A, X, Y and S cleared and interrupts disabled and runs the reset sequence
+    pub fn power_on(&mut self) -> Result<()> {
+        self.bus.power_on();
         self.accumulator = Byte::default();
         self.register_x = Byte::default();
         self.register_y = Byte::default();
-        self.status_register = StatusRegister::empty();
-        self.program_counter = self.read_word(RESET_VECTOR_BEGIN_ADDR).as_address();
+        self.status_register = StatusRegister::INIT;
+        self.stack_pointer.set(Byte::default());
+
+        self.run_reset_sequence()
+    }
+
+    /// Press the reset button: the system around the CPU resets, then the CPU runs the
+    /// reset sequence
+    pub fn reset(&mut self) -> Result<()> {
+        self.bus.reset();
+        self.run_reset_sequence()
+    }
+
+    /// The registers keep their values: reset runs the interrupt sequence with the stack
+    /// writes turned into reads, so S still drops by 3, then it sets I and jumps through
+    /// the reset vector.
+    fn run_reset_sequence(&mut self) -> Result<()> {
+        self.halted_at = None;
+        self.dummy_read(self.program_counter);
+        self.dummy_read(self.program_counter);
+        for _ in 0..3 {
+            self.dummy_read(self.stack_pointer.address());
+            self.stack_pointer.decrement();
+        }
+        self.status_register.set_interrupt_flag(true);
+
+        let low = self.read_byte(RESET_VECTOR_BEGIN_ADDR);
+        self.tick();
+        let high = self.read_byte(RESET_VECTOR_BEGIN_ADDR + 1u16);
+        self.tick();
+        self.program_counter = Word::from_le_bytes(low, high).as_address();
         debug!("CPU reset: PC set to ${:04X}", self.program_counter);
-        self.stack_pointer.reset();
+        self.interrupt_lines = InterruptLines::default();
 
         Ok(())
     }
 
     fn adc(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
-        self.add_to_acc(value);
+        self.tick(); // data read cycle
+        self.add_with_carry(value);
     }
 
     fn sbc(&mut self, address: Address) {
-        let negated = self
-            .read_byte(address)
-            .value()
-            .cast_signed()
-            .wrapping_neg()
-            .wrapping_sub(1)
-            .cast_unsigned();
-        self.bus.tick_one(); // data read cycle
+        let value = self.read_byte(address);
+        self.tick(); // data read cycle
+        self.subtract_with_borrow(value);
+    }
+
+    fn decimal_arithmetic(&self) -> bool {
+        self.decimal_mode && self.status_register.contains(StatusRegister::DECIMAL)
+    }
 
-        self.add_to_acc(negated.into());
+    fn add_with_carry(&mut self, value: Byte) {
+        match self.decimal_arithmetic() {
+            true => self.decimal_add(value),
+            false => self.add_to_acc(value),
+        }
+    }
+
+    fn subtract_with_borrow(&mut self, value: Byte) {
+        match self.decimal_arithmetic() {
+            true => self.decimal_subtract(value),
+            false => self.add_to_acc(Byte::new(!value.value())),
+        }
     }
 
     fn add_to_acc(&mut self, data: Byte) {
@@ -314,9 +473,56 @@ impl Cpu {
             .update_zero_and_negative_flags(self.accumulator);
     }
 
+    /// NMOS 6502 BCD addition: Z comes from the binary sum, N and V from the sum
+    /// before the high digit is adjusted
+    fn decimal_add(&mut self, value: Byte) {
+        let (accumulator, value) = (self.accumulator.value(), value.value());
+        let carry = u8::from(self.status_register.contains(StatusRegister::CARRY));
+
+        let mut low = (accumulator & 0x0f) + (value & 0x0f) + carry;
+        if low >= 0x0a {
+            low = ((low + 0x06) & 0x0f) + 0x10;
+        }
+        let signed = i16::from((accumulator & 0xf0).cast_signed())
+            + i16::from((value & 0xf0).cast_signed())
+            + i16::from(low);
+        let mut sum = u16::from(accumulator & 0xf0) + u16::from(value & 0xf0) + u16::from(low);
+        if sum >= 0xa0 {
+            sum += 0x60;
+        }
+
+        self.add_to_acc(value.into());
+        self.accumulator = Byte::new(sum.to_le_bytes()[0]);
+        self.status_register
+            .set_carry_flag(sum >= 0x100)
+            .set_negative_flag(signed & 0x80 != 0)
+            .set_overflow_flag(!(-128..=127).contains(&signed));
+    }
+
+    /// NMOS 6502 BCD subtraction, all flags are those of the binary subtraction
+    fn decimal_subtract(&mut self, value: Byte) {
+        let (accumulator, value) = (
+            i16::from(self.accumulator.value()),
+            i16::from(value.value()),
+        );
+        let borrow = i16::from(!self.status_register.contains(StatusRegister::CARRY));
+
+        let mut low = (accumulator & 0x0f) - (value & 0x0f) - borrow;
+        if low < 0 {
+            low = ((low - 0x06) & 0x0f) - 0x10;
+        }
+        let mut difference = (accumulator & 0xf0) - (value & 0xf0) + low;
+        if difference < 0 {
+            difference -= 0x60;
+        }
+
+        self.add_to_acc(Byte::new(!value.to_le_bytes()[0]));
+        self.accumulator = Byte::new(difference.to_le_bytes()[0]);
+    }
+
     fn compare(&mut self, address: Address, register: Byte) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
         let result = register.wrapping_sub(value);
 
         self.status_register
@@ -341,7 +547,7 @@ impl Cpu {
 
     fn logical_op_with_acc(&mut self, address: Address, logical_op: impl Fn(Byte, Byte) -> Byte) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
 
         self.accumulator = logical_op(self.accumulator, value);
         self.status_register
@@ -350,7 +556,7 @@ impl Cpu {
 
     fn bit(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
 
         self.status_register
             .set_overflow_flag(value.nth_bit::<6>())
@@ -405,7 +611,7 @@ impl Cpu {
     ) -> ByteUpdate {
         match mode {
             AddressingMode::Accumulator => {
-                self.bus.tick_one(); // internal cycle
+                self.dummy_read(self.program_counter);
                 let previous_accumulator = self.accumulator;
                 self.accumulator = shift_op(self.accumulator) | input_carry;
 
@@ -416,14 +622,14 @@ impl Cpu {
             }
             _ => {
                 let value = self.read_byte(address);
-                self.bus.tick_one(); // read cycle
+                self.tick(); // read cycle
                 let shifted = shift_op(value) | input_carry;
 
                 // RMW: dummy write of the original value before the real write.
                 self.write_byte(address, value);
-                self.bus.tick_one(); // dummy write cycle
+                self.tick(); // dummy write cycle
                 self.write_byte(address, shifted);
-                self.bus.tick_one(); // real write cycle
+                self.tick(); // real write cycle
 
                 ByteUpdate {
                     previous: value,
@@ -453,39 +659,39 @@ impl Cpu {
     fn sax(&mut self, address: Address) {
         let result = self.accumulator & self.register_x;
         self.write_byte(address, result);
-        self.bus.tick_one(); // data write cycle
+        self.tick(); // data write cycle
     }
 
     fn tax(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.register_x = self.accumulator;
         self.status_register
             .update_zero_and_negative_flags(self.register_x);
     }
 
     fn tay(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.register_y = self.accumulator;
         self.status_register
             .update_zero_and_negative_flags(self.register_y);
     }
 
     fn tsx(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.register_x = self.stack_pointer.value();
         self.status_register
             .update_zero_and_negative_flags(self.register_x);
     }
 
     fn txa(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.accumulator = self.register_x;
         self.status_register
             .update_zero_and_negative_flags(self.accumulator);
     }
 
     fn tya(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.accumulator = self.register_y;
         self.status_register
             .update_zero_and_negative_flags(self.accumulator);
@@ -493,26 +699,26 @@ impl Cpu {
 
     fn dec(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // read cycle
+        self.tick(); // read cycle
         let decremented = value.wrapping_sub(1);
 
         self.write_byte(address, value); // dummy write
-        self.bus.tick_one(); // dummy write cycle
+        self.tick(); // dummy write cycle
         self.write_byte(address, decremented);
-        self.bus.tick_one(); // real write cycle
+        self.tick(); // real write cycle
         self.status_register
             .update_zero_and_negative_flags(decremented);
     }
 
     fn dex(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.register_x = self.register_x.wrapping_sub(1);
         self.status_register
             .update_zero_and_negative_flags(self.register_x);
     }
 
     fn dey(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.register_y = self.register_y.wrapping_sub(1);
         self.status_register
             .update_zero_and_negative_flags(self.register_y);
@@ -520,26 +726,26 @@ impl Cpu {
 
     fn inc(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // read cycle
+        self.tick(); // read cycle
         let incremented = value.wrapping_add(1);
 
         self.write_byte(address, value); // dummy write
-        self.bus.tick_one(); // dummy write cycle
+        self.tick(); // dummy write cycle
         self.write_byte(address, incremented);
-        self.bus.tick_one(); // real write cycle
+        self.tick(); // real write cycle
         self.status_register
             .update_zero_and_negative_flags(incremented);
     }
 
     fn inx(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.register_x = self.register_x.wrapping_add(1);
         self.status_register
             .update_zero_and_negative_flags(self.register_x);
     }
 
     fn iny(&mut self) {
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         self.register_y = self.register_y.wrapping_add(1);
         self.status_register
             .update_zero_and_negative_flags(self.register_y);
@@ -561,12 +767,12 @@ impl Cpu {
         // Cycle 2: read addr_low
         let addr_low_pos = self.program_counter;
         let addr_low = self.read_byte(addr_low_pos);
-        self.bus.tick_one();
+        self.tick();
 
         // Cycle 3: internal read from stack pointer (the 6502 reads the stack here, updating
         // the data-bus latch, but discards the value).
         self.read_byte(self.stack_pointer.address());
-        self.bus.tick_one();
+        self.tick();
 
         // The return address is the address of the high-byte operand (addr_low_pos + 1).
         // RTS will pop this and increment by 1 to land on the instruction after JSR.
@@ -576,16 +782,16 @@ impl Cpu {
         // Cycle 4: push PCH (high byte of return address)
         self.write_byte(self.stack_pointer.address(), ret_high);
         self.stack_pointer.decrement();
-        self.bus.tick_one();
+        self.tick();
 
         // Cycle 5: push PCL (low byte of return address)
         self.write_byte(self.stack_pointer.address(), ret_low);
         self.stack_pointer.decrement();
-        self.bus.tick_one();
+        self.tick();
 
         // Cycle 6: read addr_high — this is the last bus access, so cpu_open_bus = addr_high
         let addr_high = self.read_byte(addr_low_pos + 1u16);
-        self.bus.tick_one();
+        self.tick();
 
         let target = Word::from_le_bytes(addr_low, addr_high).as_address();
         self.program_counter = target;
@@ -594,46 +800,48 @@ impl Cpu {
     fn rti(&mut self) {
         // RTI: opcode(1) + dummy_read(1) + SP_inc(1) + pop_P(1) + pop_PCL(1) + pop_PCH(1) = 6
         self.read_byte(self.program_counter);
-        self.bus.tick_one(); // dummy read
+        self.tick(); // dummy read
+        self.dummy_read(self.stack_pointer.address()); // SP increment
         self.stack_pointer.increment();
-        self.bus.tick_one(); // SP increment
         let status = self.read_byte(self.stack_pointer.address());
-        self.bus.tick_one(); // pop P
+        // Unlike PLP, the restored I flag already counts for the interrupt poll at the end of RTI
+        self.status_register = StatusRegister::from(status);
+        self.status_register.remove(StatusRegister::BREAK);
+        self.status_register.insert(StatusRegister::BREAK2);
+        self.tick(); // pop P
         self.stack_pointer.increment();
         let pcl = self.read_byte(self.stack_pointer.address());
-        self.bus.tick_one(); // pop PCL
+        self.tick(); // pop PCL
         self.stack_pointer.increment();
         let pch = self.read_byte(self.stack_pointer.address());
-        self.bus.tick_one(); // pop PCH
+        self.tick(); // pop PCH
 
-        self.status_register = StatusRegister::from(status);
-        self.status_register.remove(StatusRegister::BREAK);
-        self.status_register.insert(StatusRegister::BREAK2);
         self.program_counter = Word::from_le_bytes(pcl, pch).as_address();
     }
 
     fn rts(&mut self) {
         // RTS: opcode(1) + dummy_read(1) + SP_inc(1) + pop_PCL(1) + pop_PCH(1) + inc_PC(1) = 6
         self.read_byte(self.program_counter);
-        self.bus.tick_one(); // dummy read
+        self.tick(); // dummy read
+        self.dummy_read(self.stack_pointer.address()); // SP increment
         self.stack_pointer.increment();
-        self.bus.tick_one(); // SP increment
         let pcl = self.read_byte(self.stack_pointer.address());
-        self.bus.tick_one(); // pop PCL
+        self.tick(); // pop PCL
         self.stack_pointer.increment();
         let pch = self.read_byte(self.stack_pointer.address());
-        self.bus.tick_one(); // pop PCH
-        self.program_counter = (Word::from_le_bytes(pcl, pch) + 1).as_address();
-        self.bus.tick_one(); // increment PC
+        self.tick(); // pop PCH
+        let return_addr = Word::from_le_bytes(pcl, pch).as_address();
+        self.dummy_read(return_addr); // increment PC
+        self.program_counter = return_addr.wrapping_add(1u16);
     }
 
     fn pla(&mut self) {
         // PLA: opcode(1) + internal(1) + SP_inc(1) + pop(1) = 4
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
+        self.dummy_read(self.stack_pointer.address()); // SP increment
         self.stack_pointer.increment();
-        self.bus.tick_one(); // SP increment
         let value = self.read_byte(self.stack_pointer.address());
-        self.bus.tick_one(); // pop cycle
+        self.tick(); // pop cycle
 
         self.accumulator = value;
         self.status_register
@@ -642,11 +850,11 @@ impl Cpu {
 
     fn plp(&mut self) {
         // PLP: opcode(1) + internal(1) + SP_inc(1) + pop(1) = 4
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
+        self.dummy_read(self.stack_pointer.address()); // SP increment
         self.stack_pointer.increment();
-        self.bus.tick_one(); // SP increment
         let value = self.read_byte(self.stack_pointer.address());
-        self.bus.tick_one(); // pop cycle
+        self.tick(); // pop cycle
 
         self.status_register = StatusRegister::from(value);
         self.status_register.remove(StatusRegister::BREAK);
@@ -655,7 +863,7 @@ impl Cpu {
 
     fn php(&mut self) {
         // PHP: opcode(1) + internal(1) + push(1) = 3
-        self.bus.tick_one(); // internal cycle
+        self.dummy_read(self.program_counter);
         let mut status_register_with_b_flags = self.status_register;
         status_register_with_b_flags.insert(StatusRegister::BREAK | StatusRegister::BREAK2);
         self.write_byte(
@@ -663,22 +871,37 @@ impl Cpu {
             status_register_with_b_flags.bits().into(),
         );
         self.stack_pointer.decrement();
-        self.bus.tick_one(); // push cycle
+        self.tick(); // push cycle
     }
 
     fn branch(&mut self, condition: bool) {
         let jump = self.read_byte(self.program_counter).value().cast_signed();
-        self.bus.tick_one(); // offset byte read cycle
+        self.tick(); // offset byte read cycle
 
         if condition {
+            // A taken branch doesn't poll for interrupts in its last cycle without a page
+            // crossing, so an interrupt raised during the offset read waits for one more
+            // instruction. The IRQ line is sampled again, the NMI edge is held back instead.
+            let lines = &mut self.interrupt_lines;
+            if lines.irq && !lines.previous_irq {
+                lines.irq = false;
+            }
+            let nmi_raised = lines.nmi && !lines.previous_nmi;
+
             // NOTE: This is intended!
             #[allow(clippy::cast_sign_loss)]
             let jump_addr = self.program_counter.wrapping_add(1 + jump as u16);
 
-            self.bus.tick_one(); // branch-taken cycle
+            // Taken: the next opcode is fetched while the offset is added to PCL
+            let next_instruction = self.program_counter.wrapping_add(1u16);
+            self.dummy_read(next_instruction);
 
-            if is_page_crossed(self.program_counter, jump_addr) {
-                self.bus.tick_one(); // page-cross fixup cycle
+            if is_page_crossed(next_instruction, jump_addr) {
+                // Page-cross fixup: fetch from the target before PCH is corrected
+                let unfixed = (next_instruction & 0xFF00) | (jump_addr & 0x00FF);
+                self.dummy_read(unfixed);
+            } else if nmi_raised {
+                self.interrupt_lines.previous_nmi = false;
             }
 
             self.program_counter = jump_addr;
@@ -694,33 +917,33 @@ impl Cpu {
             AddressingMode::Immediate => address,
             AddressingMode::ZeroPage => {
                 let zp = self.read_byte(address);
-                self.bus.tick_one();
+                self.tick();
                 zp.into()
             }
             AddressingMode::ZeroPageX => {
                 let zp = self.read_byte(address);
-                self.bus.tick_one();
-                self.bus.tick_one(); // add X (internal)
+                self.tick();
+                self.dummy_read(zp.into()); // add X
                 zp.wrapping_add(self.register_x).into()
             }
             AddressingMode::ZeroPageY => {
                 let zp = self.read_byte(address);
-                self.bus.tick_one();
-                self.bus.tick_one(); // add Y (internal)
+                self.tick();
+                self.dummy_read(zp.into()); // add Y
                 zp.wrapping_add(self.register_y).into()
             }
             AddressingMode::Absolute => {
                 let low = self.read_byte(address);
-                self.bus.tick_one();
+                self.tick();
                 let high = self.read_byte(address.wrapping_add(1u16));
-                self.bus.tick_one();
+                self.tick();
                 Word::from_le_bytes(low, high).as_address()
             }
             AddressingMode::AbsoluteX => {
                 let low = self.read_byte(address);
-                self.bus.tick_one();
+                self.tick();
                 let high = self.read_byte(address.wrapping_add(1u16));
-                self.bus.tick_one();
+                self.tick();
                 let base = Word::from_le_bytes(low, high).as_address();
                 let incremented = base.wrapping_add(self.register_x);
 
@@ -732,71 +955,71 @@ impl Cpu {
                     let unfixed =
                         Address::new((base.value() & 0xFF00) | (incremented.value() & 0x00FF));
                     self.read_byte(unfixed);
-                    self.bus.tick_one();
+                    self.tick();
                 } else if !opcode.needs_page_cross_check {
                     // No page cross but RMW/write op: dummy read at the same address.
                     self.read_byte(incremented);
-                    self.bus.tick_one();
+                    self.tick();
                 }
 
                 incremented
             }
             AddressingMode::AbsoluteY => {
                 let low = self.read_byte(address);
-                self.bus.tick_one();
+                self.tick();
                 let high = self.read_byte(address.wrapping_add(1u16));
-                self.bus.tick_one();
+                self.tick();
                 let base = Word::from_le_bytes(low, high).as_address();
                 let incremented = base.wrapping_add(self.register_y);
 
                 if is_page_crossed(base, incremented) {
                     let unfixed = (base & 0xFF00) | (incremented & 0x00FF);
                     self.read_byte(unfixed);
-                    self.bus.tick_one();
+                    self.tick();
                 } else if !opcode.needs_page_cross_check {
                     self.read_byte(incremented);
-                    self.bus.tick_one();
+                    self.tick();
                 }
 
                 incremented
             }
             AddressingMode::IndirectX => {
                 let base = self.read_byte(address);
-                self.bus.tick_one();
-                self.bus.tick_one(); // add X (internal)
+                self.tick();
+                self.dummy_read(base.into()); // add X
                 let ptr = base.wrapping_add(self.register_x);
                 let low = self.read_byte(ptr.into());
-                self.bus.tick_one();
+                self.tick();
                 let high = self.read_byte(ptr.wrapping_add(1).into());
-                self.bus.tick_one();
+                self.tick();
                 Word::from_le_bytes(low, high).as_address()
             }
             AddressingMode::IndirectY => {
                 let base = self.read_byte(address);
-                self.bus.tick_one();
+                self.tick();
                 let low = self.read_byte(base.into());
-                self.bus.tick_one();
+                self.tick();
                 let high = self.read_byte(base.wrapping_add(1).into());
-                self.bus.tick_one();
+                self.tick();
                 let deref_base = Word::from_le_bytes(low, high).as_address();
                 let incremented = deref_base.wrapping_add(self.register_y);
 
                 if is_page_crossed(deref_base, incremented) {
                     let unfixed = (deref_base & 0xFF00) | (incremented & 0x00FF);
                     self.read_byte(unfixed);
-                    self.bus.tick_one();
+                    self.tick();
                 } else if !opcode.needs_page_cross_check {
                     self.read_byte(incremented);
-                    self.bus.tick_one();
+                    self.tick();
                 }
 
                 incremented
             }
             AddressingMode::Indirect => {
                 let low_addr = self.read_byte(address);
-                self.bus.tick_one();
+                self.tick();
                 let high_addr = self.read_byte(address.wrapping_add(1u16));
-                self.bus.tick_one();
+                self.tick();
                 let target = Word::from_le_bytes(low_addr, high_addr).as_address();
 
                 // Reproduce the CPU page-boundary bug:
@@ -804,10 +1027,10 @@ impl Cpu {
                 // when the indirect pointer crosses a page boundary.
                 // JMP ($xxFF) will fetch the address from $xxFF and $xx00."
                 let low = self.read_byte(target);
-                self.bus.tick_one();
+                self.tick();
                 let high_bug_addr = (target & 0xFF00) | ((target + 1) & 0x00FF);
                 let high = self.read_byte(high_bug_addr);
-                self.bus.tick_one();
+                self.tick();
                 Word::from_le_bytes(low, high).as_address()
             }
             _ => Address::default(),
@@ -816,7 +1039,7 @@ impl Cpu {
 
     fn load_value(&mut self, address: Address) -> Byte {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
         self.status_register.update_zero_and_negative_flags(value);
 
         value
@@ -824,39 +1047,83 @@ impl Cpu {
 
     fn push_byte_to_stack(&mut self, byte: Byte) {
         self.write_byte(self.stack_pointer.address(), byte);
-
         self.stack_pointer.decrement();
+        self.tick(); // push cycle
     }
 
-    fn push_word_to_stack(&mut self, word: Word) {
-        let [low, high] = word.to_le_bytes();
-
-        self.push_byte_to_stack(high);
-        self.push_byte_to_stack(low);
+    /// Dummy read cycle: the CPU is busy internally, but the value it reads is discarded
+    fn dummy_read(&mut self, address: Address) {
+        self.read_byte(address);
+        self.tick();
     }
 
-    fn interrupt(&mut self, interrupt: &Interrupt) {
-        self.push_word_to_stack(self.program_counter.as_word());
+    /// One CPU cycle: the rest of the system runs, then the interrupt lines are sampled
+    fn tick(&mut self) {
+        self.bus.tick();
+
+        let lines = &mut self.interrupt_lines;
+        lines.previous_nmi = lines.nmi;
+        if self.bus.poll_nmi() {
+            lines.nmi = true;
+        }
+        lines.previous_irq = lines.irq;
+        lines.irq = self.bus.irq_pending()
+            && !self
+                .status_register
+                .contains(StatusRegister::INTERRUPT_DISABLE);
+    }
+
+    /// NMI and IRQ: the opcode fetch is replaced by two reads of the next instruction
+    fn interrupt(&mut self) {
+        self.dummy_read(self.program_counter);
+        self.dummy_read(self.program_counter);
+        self.enter_interrupt(&interrupts::IRQ);
+        self.current_step
+            .interrupt
+            .get_or_insert(InterruptKind::Irq);
+    }
+
+    /// Push PC and P, then jump through the interrupt vector (5 cycles).
+    /// An NMI detected by the time P is pushed hijacks the sequence and takes the NMI vector,
+    /// whether it was entered for an IRQ or BRK.
+    fn enter_interrupt(&mut self, interrupt: &Interrupt) {
+        let [pcl, pch] = self.program_counter.as_word().to_le_bytes();
+        self.push_byte_to_stack(pch);
+        self.push_byte_to_stack(pcl);
+
+        let vector = match self.interrupt_lines.nmi {
+            true => {
+                self.interrupt_lines.nmi = false;
+                self.current_step.interrupt = Some(InterruptKind::Nmi);
+                interrupts::NMI.vector_addr
+            }
+            false => interrupt.vector_addr,
+        };
         let mut status = self.status_register;
         status.remove(StatusRegister::BREAK | StatusRegister::BREAK2);
         status |= StatusRegister::from_bits_truncate(interrupt.break_flag_mask.value());
-
         self.push_byte_to_stack(status.bits().into());
         self.status_register.set_interrupt_flag(true);
 
-        self.bus.tick(interrupt.cpu_cycles);
-        self.program_counter = self.read_word(interrupt.vector_addr).as_address();
+        let low = self.read_byte(vector);
+        self.tick();
+        let high = self.read_byte(vector + 1u16);
+        self.tick();
+        self.program_counter = Word::from_le_bytes(low, high).as_address();
+
+        // The first instruction of the handler always runs before the next interrupt
+        self.interrupt_lines.previous_nmi = false;
     }
 
     fn dcp(&mut self, address: Address) {
         // RMW: read + dummy_write + real_write, then CMP using already-read (decremented) value
         let value = self.read_byte(address);
-        self.bus.tick_one(); // read cycle
+        self.tick(); // read cycle
         let decremented = value.wrapping_sub(1);
         self.write_byte(address, value); // dummy write
-        self.bus.tick_one(); // dummy write cycle
+        self.tick(); // dummy write cycle
         self.write_byte(address, decremented);
-        self.bus.tick_one(); // real write cycle
+        self.tick(); // real write cycle
 
         // Inline CMP using the decremented value (no extra read)
         let result = self.accumulator.wrapping_sub(decremented);
@@ -868,33 +1135,27 @@ impl Cpu {
     fn isb(&mut self, address: Address) {
         // RMW: read + dummy_write + real_write, then SBC using already-read (incremented) value
         let value = self.read_byte(address);
-        self.bus.tick_one(); // read cycle
+        self.tick(); // read cycle
         let incremented = value.wrapping_add(1);
         self.write_byte(address, value); // dummy write
-        self.bus.tick_one(); // dummy write cycle
+        self.tick(); // dummy write cycle
         self.write_byte(address, incremented);
-        self.bus.tick_one(); // real write cycle
+        self.tick(); // real write cycle
 
         // Inline SBC using the incremented value (no extra read)
-        let negated = incremented
-            .value()
-            .cast_signed()
-            .wrapping_neg()
-            .wrapping_sub(1)
-            .cast_unsigned();
-        self.add_to_acc(negated.into());
+        self.subtract_with_borrow(incremented);
     }
 
     fn slo(&mut self, address: Address) {
         // RMW: read + dummy_write + real_write, then ORA acc with shifted value (no extra read)
         let value = self.read_byte(address);
-        self.bus.tick_one(); // read cycle
+        self.tick(); // read cycle
         let shifted_left = value << 1;
         self.status_register.set_carry_flag(value.nth_bit::<7>());
         self.write_byte(address, value); // dummy write
-        self.bus.tick_one(); // dummy write cycle
+        self.tick(); // dummy write cycle
         self.write_byte(address, shifted_left);
-        self.bus.tick_one(); // real write cycle
+        self.tick(); // real write cycle
 
         // Inline ORA using the shifted value (no extra read)
         self.accumulator |= shifted_left;
@@ -921,13 +1182,13 @@ impl Cpu {
     fn sre(&mut self, address: Address) {
         // RMW: read + dummy_write + real_write, then EOR acc with shifted value (no extra read)
         let value = self.read_byte(address);
-        self.bus.tick_one(); // read cycle
+        self.tick(); // read cycle
         let shifted_right = value >> 1;
         self.status_register.set_carry_flag(value.nth_bit::<0>());
         self.write_byte(address, value); // dummy write
-        self.bus.tick_one(); // dummy write cycle
+        self.tick(); // dummy write cycle
         self.write_byte(address, shifted_right);
-        self.bus.tick_one(); // real write cycle
+        self.tick(); // real write cycle
 
         // Inline EOR using the shifted value (no extra read)
         self.accumulator = self.accumulator ^ shifted_right;
@@ -947,12 +1208,43 @@ impl Cpu {
             .set_carry_flag(previous.nth_bit::<0>())
             .update_zero_and_negative_flags(rotated);
 
-        self.add_to_acc(rotated);
+        self.add_with_carry(rotated);
+    }
+
+    /// SHA, SHX, SHY and SHS (TAS) store `register & (H + 1)`, where H is the high byte
+    /// of the base address before indexing. When indexing crosses a page, the high byte of
+    /// the target address is replaced by that value too. If a DMA halts the CPU on the
+    /// dummy read before the write, the AND with H + 1 doesn't happen.
+    fn unstable_store(&mut self, address: Address, index: Byte, register: Byte) {
+        let base = address.wrapping_sub(index.as_word().value());
+        let [_, high] = base.as_word().to_le_bytes();
+        let and_high = register & high.wrapping_add(1).value();
+
+        let address = match is_page_crossed(base, address) {
+            true => Address::new((u16::from(and_high.value()) << 8) | (address & 0x00ff).value()),
+            false => address,
+        };
+        let value = match self.halted_by_dma {
+            true => register,
+            false => and_high,
+        };
+
+        self.write_byte(address, value);
+        self.tick(); // data write cycle
+    }
+
+    fn las(&mut self, address: Address) {
+        let value = self.read_byte(address) & self.stack_pointer.value().value();
+        self.tick(); // data read cycle
+        self.accumulator = value;
+        self.register_x = value;
+        self.stack_pointer.set(value);
+        self.status_register.update_zero_and_negative_flags(value);
     }
 
     fn anc(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
         self.accumulator &= value;
         self.status_register
             .update_zero_and_negative_flags(self.accumulator);
@@ -964,7 +1256,7 @@ impl Cpu {
 
     fn alr(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
         let and = self.accumulator & value;
         self.status_register
             .set(StatusRegister::CARRY, and.nth_bit::<0>());
@@ -975,7 +1267,7 @@ impl Cpu {
 
     fn arr(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
         let and = self.accumulator & value;
         let carry_in = Byte::from(self.status_register.contains(StatusRegister::CARRY));
         let result = (carry_in << 7) | (and >> 1);
@@ -990,7 +1282,7 @@ impl Cpu {
 
     fn ane(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
         self.accumulator = (self.accumulator | Byte::new(0xee)) & self.register_x & value;
         self.status_register
             .update_zero_and_negative_flags(self.accumulator);
@@ -998,7 +1290,7 @@ impl Cpu {
 
     fn lxa(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
         let result = (self.accumulator | Byte::new(0xee)) & value;
         self.accumulator = result;
         self.register_x = result;
@@ -1008,7 +1300,7 @@ impl Cpu {
 
     fn axs(&mut self, address: Address) {
         let value = self.read_byte(address);
-        self.bus.tick_one(); // data read cycle
+        self.tick(); // data read cycle
         let ax = self.accumulator & self.register_x;
         let result = ax.wrapping_sub(value);
         self.status_register.set(StatusRegister::CARRY, ax >= value);
@@ -1028,6 +1320,8 @@ mod tests {
     use crate::cartridge::Rom;
     use once_cell::sync::Lazy;
 
+    const PROGRAM_ROM_BEGIN_ADDR: Address = Address::new(0x0600);
+
     static TEST_ROM: Lazy<Vec<u8>> = Lazy::new(|| {
         let mut rom = vec![];
         let header = vec![
@@ -1071,11 +1365,12 @@ mod tests {
             self
         }
 
-        fn build_and_run(self, data: &[u8]) -> Cpu {
+        /// Powered-on CPU with the program loaded and PC pointing at it, all flags cleared
+        fn build(self, data: &[u8]) -> Cpu {
             let rom = Rom::from_bytes(&TEST_ROM).expect("Failed to parse test ROM");
             let bus = Bus::new(rom);
             let mut cpu = Cpu::new(bus);
-            cpu.status_register = StatusRegister::empty();
+            cpu.power_on().expect("Failed to power on");
 
             for write in self.writes {
                 match write {
@@ -1086,9 +1381,16 @@ mod tests {
 
             let data = data.iter().map(|&byte| Byte::new(byte)).collect::<Vec<_>>();
 
-            cpu.load(&data);
-            cpu.reset().expect("Failed to reset");
+            cpu.load(PROGRAM_ROM_BEGIN_ADDR, &data);
+            cpu.status_register = StatusRegister::empty();
             cpu.program_counter = PROGRAM_ROM_BEGIN_ADDR;
+
+            cpu
+        }
+
+        /// Run the program until it reaches a BRK
+        fn build_and_run(self, data: &[u8]) -> Cpu {
+            let mut cpu = self.build(data);
             loop {
                 let code = cpu.read_byte(cpu.program_counter);
                 if code == 0x00 {
@@ -1615,4 +1917,784 @@ mod tests {
             assert_eq!(cpu.status_register, StatusRegister::INTERRUPT_DISABLE);
         }
     }
+
+    mod decimal {
+        use super::*;
+
+        fn run_6502(program: &[u8], decimal_mode: bool) -> Cpu<FlatMemory> {
+            let mut cpu = Cpu::new(FlatMemory::new());
+            cpu.set_decimal_mode(decimal_mode);
+            let program = program
+                .iter()
+                .map(|&byte| Byte::new(byte))
+                .collect::<Vec<_>>();
+            cpu.load(PROGRAM_ROM_BEGIN_ADDR, &program);
+            cpu.program_counter = PROGRAM_ROM_BEGIN_ADDR;
+            while cpu.peek_byte(cpu.program_counter) != 0x00 {
+                cpu.step().unwrap();
+            }
+
+            cpu
+        }
+
+        #[test]
+        fn adc() {
+            // SED; CLC; LDA #$58; ADC #$46
+            let cpu = run_6502(&[0xf8, 0x18, 0xa9, 0x58, 0x69, 0x46, 0x00], true);
+
+            assert_eq!(cpu.accumulator, 0x04);
+            assert!(cpu.status_register.contains(StatusRegister::CARRY));
+        }
+
+        #[test]
+        fn adc_zero_flag_comes_from_binary_sum() {
+            // SED; SEC; LDA #$99; ADC #$00
+            let cpu = run_6502(&[0xf8, 0x38, 0xa9, 0x99, 0x69, 0x00, 0x00], true);
+
+            assert_eq!(cpu.accumulator, 0x00);
+            assert!(cpu.status_register.contains(StatusRegister::CARRY));
+            assert!(!cpu.status_register.contains(StatusRegister::ZERO));
+        }
+
+        #[test]
+        fn sbc() {
+            // SED; SEC; LDA #$46; SBC #$12
+            let cpu = run_6502(&[0xf8, 0x38, 0xa9, 0x46, 0xe9, 0x12, 0x00], true);
+
+            assert_eq!(cpu.accumulator, 0x34);
+            assert!(cpu.status_register.contains(StatusRegister::CARRY));
+        }
+
+        #[test]
+        fn sbc_borrow() {
+            // SED; SEC; LDA #$00; SBC #$01
+            let cpu = run_6502(&[0xf8, 0x38, 0xa9, 0x00, 0xe9, 0x01, 0x00], true);
+
+            assert_eq!(cpu.accumulator, 0x99);
+            assert!(!cpu.status_register.contains(StatusRegister::CARRY));
+        }
+
+        #[test]
+        fn disabled_by_default() {
+            // SED; CLC; LDA #$09; ADC #$01
+            let cpu = run_6502(&[0xf8, 0x18, 0xa9, 0x09, 0x69, 0x01, 0x00], false);
+
+            assert_eq!(cpu.accumulator, 0x0a);
+        }
+    }
+
+    mod unstable {
+        use super::*;
+
+        #[test]
+        fn shx_ands_with_high_byte_plus_one() {
+            // LDX #$ff; LDY #$01; SHX $0200,Y
+            let data = [0xa2, 0xff, 0xa0, 0x01, 0x9e, 0x00, 0x02, 0x00];
+            let mut cpu = CpuBuilder::new().build_and_run(&data);
+
+            assert_eq!(cpu.read_byte(Address::new(0x0201)), 0x03);
+        }
+
+        #[test]
+        fn shx_page_cross_corrupts_high_byte() {
+            // LDX #$05; LDY #$01; SHX $02ff,Y
+            let data = [0xa2, 0x05, 0xa0, 0x01, 0x9e, 0xff, 0x02, 0x00];
+            let mut cpu = CpuBuilder::new().build_and_run(&data);
+
+            // $0300 becomes $0100, X & $03 = $01
+            assert_eq!(cpu.read_byte(Address::new(0x0100)), 0x01);
+            assert_eq!(cpu.read_byte(Address::new(0x0300)), 0x00);
+        }
+
+        #[test]
+        fn shy() {
+            // LDY #$0f; LDX #$01; SHY $0400,X
+            let data = [0xa0, 0x0f, 0xa2, 0x01, 0x9c, 0x00, 0x04, 0x00];
+            let mut cpu = CpuBuilder::new().build_and_run(&data);
+
+            assert_eq!(cpu.read_byte(Address::new(0x0401)), 0x05);
+        }
+
+        #[test]
+        fn sha_absolute_y() {
+            // LDA #$3c; LDX #$f7; LDY #$02; SHA $0500,Y
+            let data = [0xa9, 0x3c, 0xa2, 0xf7, 0xa0, 0x02, 0x9f, 0x00, 0x05, 0x00];
+            let mut cpu = CpuBuilder::new().build_and_run(&data);
+
+            assert_eq!(cpu.read_byte(Address::new(0x0502)), 0x34 & 0x06);
+        }
+
+        #[test]
+        fn sha_indirect_y() {
+            // LDA #$ff; LDX #$ff; LDY #$05; SHA ($10),Y
+            let data = [0xa9, 0xff, 0xa2, 0xff, 0xa0, 0x05, 0x93, 0x10, 0x00];
+            let mut cpu = CpuBuilder::new()
+                .write_word(0x0010u16, 0x0320u16)
+                .build_and_run(&data);
+
+            assert_eq!(cpu.read_byte(Address::new(0x0325)), 0x04);
+        }
+
+        #[test]
+        fn shs_sets_stack_pointer() {
+            // LDA #$f3; LDX #$3f; LDY #$00; SHS $0200,Y
+            let data = [0xa9, 0xf3, 0xa2, 0x3f, 0xa0, 0x00, 0x9b, 0x00, 0x02, 0x00];
+            let mut cpu = CpuBuilder::new().build_and_run(&data);
+
+            assert_eq!(cpu.stack_pointer().value(), 0x33);
+            assert_eq!(cpu.read_byte(Address::new(0x0200)), 0x03);
+        }
+
+        #[test]
+        fn las() {
+            // LDY #$04; LAS $0230,Y
+            let data = [0xa0, 0x04, 0xbb, 0x30, 0x02, 0x00];
+            let cpu = CpuBuilder::new()
+                .write_byte(0x0234u16, 0xaf)
+                .build_and_run(&data);
+
+            // S was $fd
+            assert_eq!(cpu.accumulator, 0xad);
+            assert_eq!(cpu.register_x, 0xad);
+            assert_eq!(cpu.stack_pointer().value(), 0xad);
+            assert!(cpu.status_register.contains(StatusRegister::NEGATIVE));
+        }
+
+        /// SHX $0200,Y with X = $ff after a delay loop and `nops` NOPs, with a looping DMC
+        /// sample at the fastest rate. Returns the byte stored.
+        fn shx_after_delay(nops: usize) -> u8 {
+            // LDY #167; DEY; BNE -3; NOP...; LDX #$ff; SHX $0200,Y
+            let mut program = vec![0xa0, 167, 0x88, 0xd0, 0xfd];
+            program.resize(program.len() + nops, 0xea);
+            program.extend([0xa2, 0xff, 0x9e, 0x00, 0x02, 0x00]);
+            let mut cpu = CpuBuilder::new()
+                .write_byte(0x4010u16, 0x4f)
+                .write_byte(0x4012u16, 0x00)
+                .write_byte(0x4013u16, 0x00)
+                .write_byte(0x4015u16, 0x10)
+                .build_and_run(&program);
+
+            cpu.read_byte(Address::new(0x0200)).value()
+        }
+
+        #[test]
+        fn dma_on_dummy_read_skips_high_byte_and() {
+            // The second sample fetch lands on the dummy read
+            assert_eq!(shx_after_delay(5), 0xff);
+        }
+
+        #[test]
+        fn dma_off_the_dummy_read_keeps_high_byte_and() {
+            assert_eq!(shx_after_delay(4), 0x03);
+            assert_eq!(shx_after_delay(6), 0x03);
+        }
+    }
+
+    mod jam {
+        use super::*;
+
+        fn jammed_cpu() -> Cpu {
+            // LDA #$01; JAM; LDA #$02
+            let mut cpu = CpuBuilder::new().build(&[0xa9, 0x01, 0x02, 0xa9, 0x02]);
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+
+            cpu
+        }
+
+        #[test]
+        fn halts_cpu() {
+            let mut cpu = jammed_cpu();
+            assert_eq!(cpu.halted_at(), Some(Address::new(0x0602)));
+
+            for _ in 0..10 {
+                cpu.step().unwrap();
+            }
+
+            assert_eq!(cpu.accumulator, 0x01);
+            assert_eq!(cpu.program_counter, 0x0603);
+        }
+
+        #[test]
+        fn bus_keeps_ticking() {
+            let mut cpu = jammed_cpu();
+            let cycles = cpu.bus().cycles();
+
+            cpu.record_bus_accesses(true);
+            for _ in 0..100 {
+                cpu.step().unwrap();
+            }
+
+            assert_eq!(cpu.bus().cycles(), cycles + 100);
+            assert!(
+                cpu.take_bus_accesses()
+                    .iter()
+                    .all(|access| access.address == 0xffff && access.kind == BusAccessKind::Read)
+            );
+        }
+
+        #[test]
+        fn interrupts_are_ignored() {
+            let mut cpu = jammed_cpu();
+            cpu.status_register.set_interrupt_flag(false);
+            let stack_pointer = cpu.stack_pointer();
+            // APU frame counter IRQ, raised every ~29830 cycles
+            cpu.write_byte(Address::new(0x4017), Byte::new(0x00));
+
+            for _ in 0..40_000 {
+                cpu.step().unwrap();
+            }
+
+            assert!(cpu.bus().poll_irq_status());
+            assert_eq!(cpu.stack_pointer().value(), stack_pointer.value());
+            assert_eq!(cpu.program_counter, 0x0603);
+        }
+
+        #[test]
+        fn reset_resumes_execution() {
+            let mut cpu = jammed_cpu();
+
+            cpu.reset().unwrap();
+            assert_eq!(cpu.halted_at(), None);
+
+            cpu.program_counter = Address::new(0x0603);
+            cpu.step().unwrap();
+            assert_eq!(cpu.accumulator, 0x02);
+        }
+    }
+
+    mod interrupt_polling {
+        use super::*;
+
+        const IRQ_HANDLER: u16 = 0x0700;
+        const NMI_HANDLER: u16 = 0x0800;
+
+        /// Flat RAM with an IRQ line asserted from a given cycle on and an NMI edge on another.
+        /// A DMA can halt the CPU for a number of cycles from a given cycle on.
+        struct InterruptSource {
+            memory: FlatMemory,
+            irq_from: Option<u64>,
+            nmi_at: Option<u64>,
+            dma_halt: Option<(u64, u64)>,
+        }
+
+        impl Memory for InterruptSource {
+            fn peek_byte(&self, addr: Address) -> Byte {
+                self.memory.peek_byte(addr)
+            }
+
+            fn read_byte(&mut self, addr: Address) -> Byte {
+                self.memory.read_byte(addr)
+            }
+
+            fn write_byte(&mut self, addr: Address, value: Byte) {
+                self.memory.write_byte(addr, value);
+            }
+        }
+
+        impl Clock for InterruptSource {
+            fn tick(&mut self) {
+                self.memory.tick();
+            }
+
+            fn cycles(&self) -> u64 {
+                self.memory.cycles()
+            }
+
+            fn poll_nmi(&mut self) -> bool {
+                let cycles = self.cycles();
+                self.nmi_at.take_if(|&mut cycle| cycle <= cycles).is_some()
+            }
+
+            fn irq_pending(&self) -> bool {
+                self.irq_from.is_some_and(|cycle| cycle <= self.cycles())
+            }
+
+            fn run_dma_cycle(&mut self, cpu_address: Address) -> Option<BusAccess> {
+                let cycle = self.cycles();
+                let (from, remaining) = self.dma_halt.as_mut()?;
+                if *from > cycle || *remaining == 0 {
+                    return None;
+                }
+                *remaining -= 1;
+
+                Some(BusAccess {
+                    cycle,
+                    address: cpu_address,
+                    value: self.memory.peek_byte(cpu_address),
+                    kind: BusAccessKind::Read,
+                })
+            }
+        }
+
+        /// Both handlers start with a NOP
+        fn interrupt_cpu(
+            program: &[u8],
+            status: StatusRegister,
+            irq_from: Option<u64>,
+            nmi_at: Option<u64>,
+        ) -> Cpu<InterruptSource> {
+            let mut cpu = Cpu::new(InterruptSource {
+                memory: FlatMemory::new(),
+                irq_from,
+                nmi_at,
+                dma_halt: None,
+            });
+            let program = program
+                .iter()
+                .map(|&byte| Byte::new(byte))
+                .collect::<Vec<_>>();
+            cpu.load(PROGRAM_ROM_BEGIN_ADDR, &program);
+            cpu.load(Address::new(IRQ_HANDLER), &[Byte::new(0xea)]);
+            cpu.load(Address::new(NMI_HANDLER), &[Byte::new(0xea)]);
+            cpu.load(Address::new(0xfffa), &[0x00, 0x08].map(Byte::new));
+            cpu.load(Address::new(0xfffe), &[0x00, 0x07].map(Byte::new));
+            cpu.program_counter = PROGRAM_ROM_BEGIN_ADDR;
+            cpu.status_register = status;
+
+            cpu
+        }
+
+        fn pushed_status(cpu: &Cpu<InterruptSource>) -> StatusRegister {
+            StatusRegister::from(cpu.peek_byte(Address::new(0x01fb)))
+        }
+
+        #[test]
+        fn irq_waits_for_instruction_after_cli() {
+            // CLI; NOP; NOP
+            let mut cpu = interrupt_cpu(
+                &[0x58, 0xea, 0xea],
+                StatusRegister::INTERRUPT_DISABLE,
+                Some(0),
+                None,
+            );
+
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, 0x0602);
+
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, IRQ_HANDLER + 1);
+            assert_eq!(cpu.peek_byte(Address::new(0x01fc)), 0x02);
+        }
+
+        #[test]
+        fn step_reports_serviced_irq() {
+            // NOP; NOP
+            let mut cpu = interrupt_cpu(&[0xea, 0xea], StatusRegister::empty(), Some(0), None);
+
+            let step = cpu.step().unwrap();
+            assert_eq!(step.interrupt, None);
+            assert_eq!(step.cycles, 2);
+
+            // The interrupt sequence, then the handler's NOP
+            let step = cpu.step().unwrap();
+            assert_eq!(step.interrupt, Some(InterruptKind::Irq));
+            assert_eq!(step.cycles, 9);
+        }
+
+        #[test]
+        fn irq_polled_before_sei_sets_i_flag() {
+            // SEI; NOP
+            let mut cpu = interrupt_cpu(&[0x78, 0xea], StatusRegister::empty(), Some(1), None);
+
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+
+            assert_eq!(cpu.program_counter, IRQ_HANDLER + 1);
+            assert!(pushed_status(&cpu).contains(StatusRegister::INTERRUPT_DISABLE));
+        }
+
+        #[test]
+        fn taken_branch_delays_irq() {
+            // BEQ +0; NOP; NOP, the IRQ arrives during the offset read
+            let mut cpu = interrupt_cpu(
+                &[0xf0, 0x00, 0xea, 0xea],
+                StatusRegister::ZERO,
+                Some(2),
+                None,
+            );
+
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, 0x0603);
+
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, IRQ_HANDLER + 1);
+        }
+
+        #[test]
+        fn dma_halt_cycles_dont_poll_interrupts() {
+            // BEQ +0; NOP; NOP, a DMA halts the branch's last read and raises the IRQ
+            let mut cpu = interrupt_cpu(
+                &[0xf0, 0x00, 0xea, 0xea],
+                StatusRegister::ZERO,
+                Some(3),
+                None,
+            );
+            cpu.bus_mut().dma_halt = Some((2, 3));
+
+            let step = cpu.step().unwrap();
+            assert_eq!(step.dma_cycles, 3);
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, 0x0603);
+
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, IRQ_HANDLER + 1);
+        }
+
+        #[test]
+        fn taken_branch_delays_nmi() {
+            // BEQ +0; NOP; NOP, the NMI arrives during the offset read
+            let mut cpu = interrupt_cpu(
+                &[0xf0, 0x00, 0xea, 0xea],
+                StatusRegister::ZERO,
+                None,
+                Some(2),
+            );
+
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, 0x0603);
+
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, NMI_HANDLER + 1);
+        }
+
+        #[test]
+        fn taken_branch_across_a_page_doesnt_delay_nmi() {
+            // BEQ +1 at $06FD, whose target $0700 is on the next page. The NMI arrives during
+            // the offset read.
+            let mut cpu = interrupt_cpu(&[], StatusRegister::ZERO, None, Some(2));
+            cpu.load(Address::new(0x06fd), &[0xf0, 0x01].map(Byte::new));
+            cpu.program_counter = Address::new(0x06fd);
+
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, NMI_HANDLER + 1);
+        }
+
+        #[test]
+        fn nmi_hijacks_brk() {
+            let mut cpu = interrupt_cpu(&[0x00, 0x00], StatusRegister::empty(), None, Some(3));
+
+            let step = cpu.step().unwrap();
+            assert_eq!(step.interrupt, Some(InterruptKind::Nmi));
+
+            assert_eq!(cpu.program_counter, NMI_HANDLER);
+            assert!(pushed_status(&cpu).contains(StatusRegister::BREAK));
+        }
+
+        #[test]
+        fn nmi_after_status_push_waits_for_handler_instruction() {
+            let mut cpu = interrupt_cpu(&[0x00, 0x00], StatusRegister::empty(), None, Some(6));
+
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, IRQ_HANDLER);
+
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+            assert_eq!(cpu.program_counter, NMI_HANDLER + 1);
+        }
+
+        #[test]
+        fn nmi_hijacks_irq() {
+            // NOP; NOP, the NMI arrives while the IRQ pushes PC
+            let mut cpu = interrupt_cpu(&[0xea, 0xea], StatusRegister::empty(), Some(0), Some(5));
+
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+
+            assert_eq!(cpu.program_counter, NMI_HANDLER + 1);
+            assert!(!pushed_status(&cpu).contains(StatusRegister::BREAK));
+        }
+    }
+
+    mod bus_accesses {
+        use super::*;
+        use BusAccessKind::{Read as R, Write as W};
+        use pretty_assertions::assert_eq;
+
+        /// Record the accesses of a single instruction, checking there's exactly one per cycle
+        fn step_recorded(cpu: &mut Cpu) -> Vec<(u16, u8, BusAccessKind)> {
+            let start_cycle = cpu.bus().cycles();
+            cpu.record_bus_accesses(true);
+            cpu.step().unwrap();
+            let accesses = cpu.take_bus_accesses();
+            cpu.record_bus_accesses(false);
+
+            let cycles = accesses
+                .iter()
+                .map(|access| access.cycle)
+                .collect::<Vec<_>>();
+            let expected_cycles = (start_cycle..cpu.bus().cycles()).collect::<Vec<_>>();
+            assert_eq!(cycles, expected_cycles, "one bus access per cycle");
+
+            accesses
+                .into_iter()
+                .map(|access| (access.address.value(), access.value.value(), access.kind))
+                .collect()
+        }
+
+        #[test]
+        fn implied_reads_next_byte() {
+            let mut cpu = CpuBuilder::new().build(&[0xe8, 0xea]); // INX
+
+            assert_eq!(
+                step_recorded(&mut cpu),
+                [(0x0600, 0xe8, R), (0x0601, 0xea, R)]
+            );
+        }
+
+        #[test]
+        fn zero_page_indexed_reads_base_address() {
+            let mut cpu = CpuBuilder::new().build(&[0xb5, 0x10]); // LDA $10,X
+            cpu.register_x = Byte::new(0x05);
+            cpu.write_byte(Address::new(0x0010), Byte::new(0x11));
+            cpu.write_byte(Address::new(0x0015), Byte::new(0x42));
+
+            assert_eq!(
+                step_recorded(&mut cpu),
+                [
+                    (0x0600, 0xb5, R),
+                    (0x0601, 0x10, R),
+                    (0x0010, 0x11, R),
+                    (0x0015, 0x42, R)
+                ]
+            );
+        }
+
+        #[test]
+        fn read_modify_write_writes_twice() {
+            let mut cpu = CpuBuilder::new().build(&[0xee, 0x00, 0x02]); // INC $0200
+            cpu.write_byte(Address::new(0x0200), Byte::new(0x07));
+
+            assert_eq!(
+                step_recorded(&mut cpu),
+                [
+                    (0x0600, 0xee, R),
+                    (0x0601, 0x00, R),
+                    (0x0602, 0x02, R),
+                    (0x0200, 0x07, R),
+                    (0x0200, 0x07, W),
+                    (0x0200, 0x08, W)
+                ]
+            );
+        }
+
+        #[test]
+        fn indexed_store_reads_unfixed_address() {
+            let mut cpu = CpuBuilder::new().build(&[0x9d, 0xff, 0x01]); // STA $01FF,X
+            cpu.register_x = Byte::new(0x01);
+            cpu.accumulator = Byte::new(0x33);
+
+            assert_eq!(
+                step_recorded(&mut cpu),
+                [
+                    (0x0600, 0x9d, R),
+                    (0x0601, 0xff, R),
+                    (0x0602, 0x01, R),
+                    (0x0100, 0x00, R),
+                    (0x0200, 0x33, W)
+                ]
+            );
+        }
+
+        #[test]
+        fn taken_branch_across_page() {
+            let mut cpu = CpuBuilder::new().build(&[0xd0, 0x80, 0xea]); // BNE -128
+
+            assert_eq!(
+                step_recorded(&mut cpu),
+                [
+                    (0x0600, 0xd0, R),
+                    (0x0601, 0x80, R),
+                    (0x0602, 0xea, R),
+                    (0x0682, 0x00, R)
+                ]
+            );
+            assert_eq!(cpu.program_counter, 0x0582);
+        }
+
+        #[test]
+        fn pull_reads_stack_before_increment() {
+            let mut cpu = CpuBuilder::new().build(&[0x68, 0xea]); // PLA
+            cpu.write_byte(Address::new(0x01fe), Byte::new(0x99));
+
+            assert_eq!(
+                step_recorded(&mut cpu),
+                [
+                    (0x0600, 0x68, R),
+                    (0x0601, 0xea, R),
+                    (0x01fd, 0x00, R),
+                    (0x01fe, 0x99, R)
+                ]
+            );
+        }
+
+        #[test]
+        fn rts_reads_return_address() {
+            let mut cpu = CpuBuilder::new().build(&[0x60]); // RTS
+            cpu.write_byte(Address::new(0x01fe), Byte::new(0x34));
+            cpu.write_byte(Address::new(0x01ff), Byte::new(0x02));
+
+            assert_eq!(
+                step_recorded(&mut cpu),
+                [
+                    (0x0600, 0x60, R),
+                    (0x0601, 0x00, R),
+                    (0x01fd, 0x00, R),
+                    (0x01fe, 0x34, R),
+                    (0x01ff, 0x02, R),
+                    (0x0234, 0x00, R)
+                ]
+            );
+            assert_eq!(cpu.program_counter, 0x0235);
+        }
+
+        #[test]
+        fn brk_pushes_state_and_reads_vector() {
+            let mut cpu = CpuBuilder::new().build(&[0x00, 0xff]); // BRK
+
+            assert_eq!(
+                step_recorded(&mut cpu),
+                [
+                    (0x0600, 0x00, R),
+                    (0x0601, 0xff, R),
+                    (0x01fd, 0x06, W),
+                    (0x01fc, 0x02, W),
+                    (0x01fb, 0x30, W),
+                    (0xfffe, 0x00, R),
+                    (0xffff, 0x00, R)
+                ]
+            );
+        }
+
+        #[test]
+        fn one_access_per_cycle_for_every_opcode() {
+            for code in 0..=u8::MAX {
+                let opcode = decode(Byte::new(code));
+                // With all flags clear and zero operands, these branches are taken
+                // to the next instruction, without crossing a page
+                let taken_branch = usize::from(matches!(
+                    opcode.instruction,
+                    Instruction::Bcc | Instruction::Bne | Instruction::Bpl | Instruction::Bvc
+                ));
+
+                let mut cpu = CpuBuilder::new().build(&[code, 0x00, 0x00]);
+                let accesses = step_recorded(&mut cpu);
+
+                assert_eq!(
+                    accesses.len(),
+                    opcode.cycles + taken_branch,
+                    "{} ({code:02X})",
+                    opcode.name
+                );
+            }
+        }
+    }
+
+    mod reset {
+        use super::*;
+
+        fn cpu_with_reset_vector() -> Cpu<FlatMemory> {
+            let mut cpu = Cpu::new(FlatMemory::new());
+            cpu.write_word(RESET_VECTOR_BEGIN_ADDR, Word::new(0x8000));
+            cpu
+        }
+
+        #[test]
+        fn power_on_clears_registers() {
+            let mut cpu = cpu_with_reset_vector();
+            cpu.accumulator = Byte::new(0x12);
+            cpu.register_x = Byte::new(0x34);
+
+            cpu.power_on().unwrap();
+
+            assert_eq!(cpu.accumulator, 0x00);
+            assert_eq!(cpu.register_x, 0x00);
+            assert_eq!(cpu.register_y, 0x00);
+            assert_eq!(cpu.stack_pointer().value(), 0xfd);
+            assert_eq!(cpu.status_register, StatusRegister::INIT);
+            assert_eq!(cpu.program_counter, 0x8000);
+        }
+
+        #[test]
+        fn reset_keeps_registers() {
+            let mut cpu = cpu_with_reset_vector();
+            cpu.power_on().unwrap();
+            cpu.accumulator = Byte::new(0x12);
+            cpu.register_x = Byte::new(0x34);
+            cpu.register_y = Byte::new(0x56);
+            cpu.status_register = StatusRegister::CARRY | StatusRegister::DECIMAL;
+            let cycles = cpu.bus().cycles();
+
+            cpu.reset().unwrap();
+
+            assert_eq!(cpu.accumulator, 0x12);
+            assert_eq!(cpu.register_x, 0x34);
+            assert_eq!(cpu.register_y, 0x56);
+            assert_eq!(cpu.stack_pointer().value(), 0xfa);
+            assert_eq!(
+                cpu.status_register,
+                StatusRegister::CARRY | StatusRegister::DECIMAL | StatusRegister::INTERRUPT_DISABLE
+            );
+            assert_eq!(cpu.program_counter, 0x8000);
+            assert_eq!(cpu.bus().cycles() - cycles, 7);
+        }
+
+        #[test]
+        fn reset_resets_the_bus() {
+            let mut cpu = CpuBuilder::new().build(&[]);
+            cpu.write_byte(Address::new(0x4015), Byte::new(0x01)); // enable square 1
+            cpu.write_byte(Address::new(0x4003), Byte::new(0x08)); // load its length counter
+            assert_eq!(cpu.peek_byte(Address::new(0x4015)) & 0x01, 0x01);
+
+            cpu.reset().unwrap();
+
+            assert_eq!(cpu.peek_byte(Address::new(0x4015)) & 0x01, 0x00);
+        }
+
+        #[test]
+        fn reset_writes_nothing_to_the_stack() {
+            let mut cpu = cpu_with_reset_vector();
+            cpu.power_on().unwrap();
+            cpu.program_counter = Address::new(0x1234);
+
+            cpu.reset().unwrap();
+
+            for address in 0x01fb..=0x01fd {
+                assert_eq!(cpu.peek_byte(Address::new(address)), 0x00);
+            }
+        }
+    }
+
+    mod step_result {
+        use super::*;
+
+        #[test]
+        fn cycles_include_page_cross_penalty() {
+            // LDX #$01; LDA $06FF,X; LDA $0600,X
+            let mut cpu =
+                CpuBuilder::new().build(&[0xa2, 0x01, 0xbd, 0xff, 0x06, 0xbd, 0x00, 0x06]);
+
+            let cycles: Vec<_> = (0..3).map(|_| cpu.step().unwrap().cycles).collect();
+
+            assert_eq!(cycles, [2, 5, 4]);
+        }
+
+        #[test]
+        fn oam_dma_stall_is_reported() {
+            // STA $4014; NOP
+            let mut cpu = CpuBuilder::new().build(&[0x8d, 0x14, 0x40, 0xea]);
+
+            assert_eq!(cpu.step().unwrap().dma_cycles, 0);
+            // The DMA halts the CPU on the opcode fetch after the write
+            let step = cpu.step().unwrap();
+
+            assert!(matches!(step.dma_cycles, 513 | 514));
+            assert_eq!(step.cycles, 2 + step.dma_cycles);
+            assert_eq!(step.interrupt, None);
+        }
+    }
 }
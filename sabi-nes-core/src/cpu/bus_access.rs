@@ -0,0 +1,18 @@
+use crate::{Address, Byte};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BusAccessKind {
+    Read,
+    Write,
+}
+
+/// A single CPU bus access, recorded when [`Cpu::record_bus_accesses`](crate::Cpu::record_bus_accesses)
+/// is enabled. The 6502 accesses the bus on every cycle, dummy reads and writes included.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BusAccess {
+    /// CPU cycle the access happened on
+    pub cycle: u64,
+    pub address: Address,
+    pub value: Byte,
+    pub kind: BusAccessKind,
+}
@@ -0,0 +1,34 @@
+use crate::Address;
+use crate::cpu::BusAccess;
+
+/// Hooks the CPU calls into the system it runs in: the clock driving everything alongside it
+/// and the interrupt lines. The defaults suit a bare 6502 with nothing attached.
+pub trait Clock {
+    /// One CPU cycle has passed
+    fn tick(&mut self);
+
+    /// The console was switched on, everything alongside the CPU starts over
+    fn power_on(&mut self) {}
+
+    /// The reset button was pressed, everything alongside the CPU resets with it
+    fn reset(&mut self) {}
+
+    /// Called before every read with the address the CPU is about to read. While a DMA holds
+    /// the CPU, runs the access it makes this cycle, returning `None` once the read can go ahead.
+    fn run_dma_cycle(&mut self, _cpu_address: Address) -> Option<BusAccess> {
+        None
+    }
+
+    /// CPU cycles elapsed since power-on
+    fn cycles(&self) -> u64;
+
+    /// Whether an NMI was raised since the last poll. Polling acknowledges it.
+    fn poll_nmi(&mut self) -> bool {
+        false
+    }
+
+    /// Whether the IRQ line is asserted
+    fn irq_pending(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,49 @@
+use crate::cpu::{Clock, Memory};
+use crate::{Address, Byte};
+
+const MEMORY_SIZE: usize = 0x10000;
+
+/// A flat 64 KiB of RAM with nothing else attached, for running plain 6502 programs
+pub struct FlatMemory {
+    data: Box<[Byte; MEMORY_SIZE]>,
+    cycles: u64,
+}
+
+impl FlatMemory {
+    pub fn new() -> Self {
+        Self {
+            data: Box::new([Byte::default(); MEMORY_SIZE]),
+            cycles: 0,
+        }
+    }
+}
+
+impl Default for FlatMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Memory for FlatMemory {
+    fn peek_byte(&self, addr: Address) -> Byte {
+        self.data[addr.as_usize()]
+    }
+
+    fn read_byte(&mut self, addr: Address) -> Byte {
+        self.data[addr.as_usize()]
+    }
+
+    fn write_byte(&mut self, addr: Address, value: Byte) {
+        self.data[addr.as_usize()] = value;
+    }
+}
+
+impl Clock for FlatMemory {
+    fn tick(&mut self) {
+        self.cycles += 1;
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+}
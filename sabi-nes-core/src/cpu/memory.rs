@@ -1,6 +1,8 @@
 use crate::{Address, Byte, Word};
 
 pub trait Memory {
+    /// Read a byte without side effects, for debuggers and tracing
+    fn peek_byte(&self, addr: Address) -> Byte;
     fn read_byte(&mut self, addr: Address) -> Byte;
     fn write_byte(&mut self, addr: Address, value: Byte);
 
@@ -1,12 +1,95 @@
 use crate::Byte;
 use crate::cpu::addressing_mode::AddressingMode;
-use once_cell::sync::Lazy;
-use std::collections::HashMap;
 
-#[derive(Debug)]
+/// Operation performed by an opcode, what the CPU dispatches on.
+/// Unofficial opcodes duplicating an official operation (e.g. `*NOP`, `*SBC`) share its variant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Adc,
+    And,
+    Asl,
+    Bcc,
+    Bcs,
+    Beq,
+    Bit,
+    Bmi,
+    Bne,
+    Bpl,
+    Brk,
+    Bvc,
+    Bvs,
+    Clc,
+    Cld,
+    Cli,
+    Clv,
+    Cmp,
+    Cpx,
+    Cpy,
+    Dec,
+    Dex,
+    Dey,
+    Eor,
+    Inc,
+    Inx,
+    Iny,
+    Jmp,
+    Jsr,
+    Lda,
+    Ldx,
+    Ldy,
+    Lsr,
+    Nop,
+    Ora,
+    Pha,
+    Php,
+    Pla,
+    Plp,
+    Rol,
+    Ror,
+    Rti,
+    Rts,
+    Sbc,
+    Sec,
+    Sed,
+    Sei,
+    Sta,
+    Stx,
+    Sty,
+    Tax,
+    Tay,
+    Tsx,
+    Txa,
+    Txs,
+    Tya,
+    // Unofficial
+    Alr,
+    Anc,
+    Ane,
+    Arr,
+    Axs,
+    Dcp,
+    Isb,
+    Las,
+    Lax,
+    Lxa,
+    Rla,
+    Rra,
+    Sax,
+    Sha,
+    Shs,
+    Shx,
+    Shy,
+    Slo,
+    Sre,
+    Jam,
+}
+
+#[derive(Debug, Copy, Clone)]
 pub struct Opcode {
     pub code: Byte,
+    /// Mnemonic for disassembly, unofficial opcodes are prefixed with `*`
     pub name: &'static str,
+    pub instruction: Instruction,
     pub bytes: usize,
     pub cycles: usize,
     pub addressing_mode: AddressingMode,
@@ -17,6 +100,7 @@ impl Opcode {
     pub const fn new(
         code: u8,
         name: &'static str,
+        instruction: Instruction,
         bytes: usize,
         cycles: usize,
         addressing_mode: AddressingMode,
@@ -25,6 +109,7 @@ impl Opcode {
         Self {
             code: Byte::new(code),
             name,
+            instruction,
             bytes,
             cycles,
             addressing_mode,
@@ -37,304 +122,362 @@ impl Opcode {
     }
 }
 
+/// Look up the opcode for an instruction byte
+pub fn decode(code: Byte) -> &'static Opcode {
+    &OPCODE_TABLE[code.as_usize()]
+}
+
+#[rustfmt::skip]
 const OPCODES: &[Opcode] = &[
-    Opcode::new(0xea, "NOP", 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xea, "NOP", Instruction::Nop, 1, 2, AddressingMode::Implied, false),
     // -- flag clear/set instructions
-    Opcode::new(0x18, "CLC", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xd8, "CLD", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x58, "CLI", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xb8, "CLV", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x38, "SEC", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xf8, "SED", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x78, "SEI", 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x18, "CLC", Instruction::Clc, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xd8, "CLD", Instruction::Cld, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x58, "CLI", Instruction::Cli, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xb8, "CLV", Instruction::Clv, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x38, "SEC", Instruction::Sec, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xf8, "SED", Instruction::Sed, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x78, "SEI", Instruction::Sei, 1, 2, AddressingMode::Implied, false),
     // -- logical instructions --
     // AND
-    Opcode::new(0x29, "AND", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0x25, "AND", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x35, "AND", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x2d, "AND", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x3d, "AND", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x39, "AND", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x21, "AND", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0x31, "AND", 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x29, "AND", Instruction::And, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x25, "AND", Instruction::And, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x35, "AND", Instruction::And, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x2d, "AND", Instruction::And, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x3d, "AND", Instruction::And, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x39, "AND", Instruction::And, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x21, "AND", Instruction::And, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0x31, "AND", Instruction::And, 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
     // BIT
-    Opcode::new(0x2c, "BIT", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x24, "BIT", 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x2c, "BIT", Instruction::Bit, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x24, "BIT", Instruction::Bit, 2, 3, AddressingMode::ZeroPage, false),
     // EOR
-    Opcode::new(0x49, "EOR", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0x45, "EOR", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x55, "EOR", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x4d, "EOR", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x5d, "EOR", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x59, "EOR", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x41, "EOR", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0x51, "EOR", 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x49, "EOR", Instruction::Eor, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x45, "EOR", Instruction::Eor, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x55, "EOR", Instruction::Eor, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x4d, "EOR", Instruction::Eor, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x5d, "EOR", Instruction::Eor, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x59, "EOR", Instruction::Eor, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x41, "EOR", Instruction::Eor, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0x51, "EOR", Instruction::Eor, 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
     // ORA
-    Opcode::new(0x09, "ORA", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0x05, "ORA", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x15, "ORA", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x0d, "ORA", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x1d, "ORA", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x19, "ORA", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x01, "ORA", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0x11, "ORA", 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x09, "ORA", Instruction::Ora, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x05, "ORA", Instruction::Ora, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x15, "ORA", Instruction::Ora, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x0d, "ORA", Instruction::Ora, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x1d, "ORA", Instruction::Ora, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x19, "ORA", Instruction::Ora, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x01, "ORA", Instruction::Ora, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0x11, "ORA", Instruction::Ora, 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
     // -- load/store instructions --
     // LDA
-    Opcode::new(0xa9, "LDA", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xa5, "LDA", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0xb5, "LDA", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xad, "LDA", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0xbd, "LDA", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0xb9, "LDA", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
-    Opcode::new(0xa1, "LDA", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0xb1, "LDA", 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xa9, "LDA", Instruction::Lda, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xa5, "LDA", Instruction::Lda, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0xb5, "LDA", Instruction::Lda, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xad, "LDA", Instruction::Lda, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0xbd, "LDA", Instruction::Lda, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xb9, "LDA", Instruction::Lda, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xa1, "LDA", Instruction::Lda, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0xb1, "LDA", Instruction::Lda, 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
     // LDX
-    Opcode::new(0xa2, "LDX", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xae, "LDX", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0xbe, "LDX", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
-    Opcode::new(0xa6, "LDX", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0xb6, "LDX", 2, 4, AddressingMode::ZeroPageY, false),
+    Opcode::new(0xa2, "LDX", Instruction::Ldx, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xae, "LDX", Instruction::Ldx, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0xbe, "LDX", Instruction::Ldx, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xa6, "LDX", Instruction::Ldx, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0xb6, "LDX", Instruction::Ldx, 2, 4, AddressingMode::ZeroPageY, false),
     // LDY
-    Opcode::new(0xa0, "LDY", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xac, "LDY", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0xbc, "LDY", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0xa4, "LDY", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0xb4, "LDY", 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xa0, "LDY", Instruction::Ldy, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xac, "LDY", Instruction::Ldy, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0xbc, "LDY", Instruction::Ldy, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xa4, "LDY", Instruction::Ldy, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0xb4, "LDY", Instruction::Ldy, 2, 4, AddressingMode::ZeroPageX, false),
     // STA
-    Opcode::new(0x85, "STA", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x95, "STA", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x8d, "STA", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x9d, "STA", 3, 5, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x99, "STA", 3, 5, AddressingMode::AbsoluteY, false),
-    Opcode::new(0x81, "STA", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0x91, "STA", 2, 6, AddressingMode::IndirectY, false),
+    Opcode::new(0x85, "STA", Instruction::Sta, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x95, "STA", Instruction::Sta, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x8d, "STA", Instruction::Sta, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x9d, "STA", Instruction::Sta, 3, 5, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x99, "STA", Instruction::Sta, 3, 5, AddressingMode::AbsoluteY, false),
+    Opcode::new(0x81, "STA", Instruction::Sta, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0x91, "STA", Instruction::Sta, 2, 6, AddressingMode::IndirectY, false),
     // STX
-    Opcode::new(0x8e, "STX", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x86, "STX", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x96, "STX", 2, 4, AddressingMode::ZeroPageY, false),
+    Opcode::new(0x8e, "STX", Instruction::Stx, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x86, "STX", Instruction::Stx, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x96, "STX", Instruction::Stx, 2, 4, AddressingMode::ZeroPageY, false),
     // STY
-    Opcode::new(0x8c, "STY", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x84, "STY", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x94, "STY", 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x8c, "STY", Instruction::Sty, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x84, "STY", Instruction::Sty, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x94, "STY", Instruction::Sty, 2, 4, AddressingMode::ZeroPageX, false),
     // -- transfer instructions --
-    Opcode::new(0xaa, "TAX", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xa8, "TAY", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xba, "TSX", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x8a, "TXA", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x9a, "TXS", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x98, "TYA", 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xaa, "TAX", Instruction::Tax, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xa8, "TAY", Instruction::Tay, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xba, "TSX", Instruction::Tsx, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x8a, "TXA", Instruction::Txa, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x9a, "TXS", Instruction::Txs, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x98, "TYA", Instruction::Tya, 1, 2, AddressingMode::Implied, false),
     // -- stack instructions --
-    Opcode::new(0x48, "PHA", 1, 3, AddressingMode::Implied, false),
-    Opcode::new(0x08, "PHP", 1, 3, AddressingMode::Implied, false),
-    Opcode::new(0x68, "PLA", 1, 4, AddressingMode::Implied, false),
-    Opcode::new(0x28, "PLP", 1, 4, AddressingMode::Implied, false),
+    Opcode::new(0x48, "PHA", Instruction::Pha, 1, 3, AddressingMode::Implied, false),
+    Opcode::new(0x08, "PHP", Instruction::Php, 1, 3, AddressingMode::Implied, false),
+    Opcode::new(0x68, "PLA", Instruction::Pla, 1, 4, AddressingMode::Implied, false),
+    Opcode::new(0x28, "PLP", Instruction::Plp, 1, 4, AddressingMode::Implied, false),
     // -- increment/decrement instructions --
-    Opcode::new(0xce, "DEC", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0xde, "DEC", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0xc6, "DEC", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0xd6, "DEC", 2, 6, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xca, "DEX", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x88, "DEY", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xee, "INC", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0xfe, "INC", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0xe6, "INC", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0xf6, "INC", 2, 6, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xe8, "INX", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xc8, "INY", 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xce, "DEC", Instruction::Dec, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0xde, "DEC", Instruction::Dec, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0xc6, "DEC", Instruction::Dec, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0xd6, "DEC", Instruction::Dec, 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xca, "DEX", Instruction::Dex, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x88, "DEY", Instruction::Dey, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xee, "INC", Instruction::Inc, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0xfe, "INC", Instruction::Inc, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0xe6, "INC", Instruction::Inc, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0xf6, "INC", Instruction::Inc, 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xe8, "INX", Instruction::Inx, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xc8, "INY", Instruction::Iny, 1, 2, AddressingMode::Implied, false),
     // -- shift instructions --
     // ASL
-    Opcode::new(0x0a, "ASL", 1, 2, AddressingMode::Accumulator, false),
-    Opcode::new(0x0e, "ASL", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0x1e, "ASL", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x06, "ASL", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0x16, "ASL", 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x0a, "ASL", Instruction::Asl, 1, 2, AddressingMode::Accumulator, false),
+    Opcode::new(0x0e, "ASL", Instruction::Asl, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0x1e, "ASL", Instruction::Asl, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x06, "ASL", Instruction::Asl, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0x16, "ASL", Instruction::Asl, 2, 6, AddressingMode::ZeroPageX, false),
     // LSR
-    Opcode::new(0x4a, "LSR", 1, 2, AddressingMode::Accumulator, false),
-    Opcode::new(0x4e, "LSR", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0x5e, "LSR", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x46, "LSR", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0x56, "LSR", 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x4a, "LSR", Instruction::Lsr, 1, 2, AddressingMode::Accumulator, false),
+    Opcode::new(0x4e, "LSR", Instruction::Lsr, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0x5e, "LSR", Instruction::Lsr, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x46, "LSR", Instruction::Lsr, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0x56, "LSR", Instruction::Lsr, 2, 6, AddressingMode::ZeroPageX, false),
     // ROL
-    Opcode::new(0x2a, "ROL", 1, 2, AddressingMode::Accumulator, false),
-    Opcode::new(0x2e, "ROL", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0x3e, "ROL", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x26, "ROL", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0x36, "ROL", 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x2a, "ROL", Instruction::Rol, 1, 2, AddressingMode::Accumulator, false),
+    Opcode::new(0x2e, "ROL", Instruction::Rol, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0x3e, "ROL", Instruction::Rol, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x26, "ROL", Instruction::Rol, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0x36, "ROL", Instruction::Rol, 2, 6, AddressingMode::ZeroPageX, false),
     // ROR
-    Opcode::new(0x6a, "ROR", 1, 2, AddressingMode::Accumulator, false),
-    Opcode::new(0x6e, "ROR", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0x7e, "ROR", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x66, "ROR", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0x76, "ROR", 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x6a, "ROR", Instruction::Ror, 1, 2, AddressingMode::Accumulator, false),
+    Opcode::new(0x6e, "ROR", Instruction::Ror, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0x7e, "ROR", Instruction::Ror, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x66, "ROR", Instruction::Ror, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0x76, "ROR", Instruction::Ror, 2, 6, AddressingMode::ZeroPageX, false),
     // -- branch instructions --
-    Opcode::new(0x90, "BCC", 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
-    Opcode::new(0xb0, "BCS", 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
-    Opcode::new(0xf0, "BEQ", 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
-    Opcode::new(0x30, "BMI", 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
-    Opcode::new(0xd0, "BNE", 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
-    Opcode::new(0x10, "BPL", 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
-    Opcode::new(0x50, "BVC", 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
-    Opcode::new(0x70, "BVS", 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
+    Opcode::new(0x90, "BCC", Instruction::Bcc, 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
+    Opcode::new(0xb0, "BCS", Instruction::Bcs, 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
+    Opcode::new(0xf0, "BEQ", Instruction::Beq, 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
+    Opcode::new(0x30, "BMI", Instruction::Bmi, 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
+    Opcode::new(0xd0, "BNE", Instruction::Bne, 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
+    Opcode::new(0x10, "BPL", Instruction::Bpl, 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
+    Opcode::new(0x50, "BVC", Instruction::Bvc, 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
+    Opcode::new(0x70, "BVS", Instruction::Bvs, 2, 2, AddressingMode::Relative, true), // +1 if page is crossed, +1 if branch is taken
     // -- arithmetic instructions --
     // ADC
-    Opcode::new(0x69, "ADC", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0x6d, "ADC", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x7d, "ADC", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page is crossed
-    Opcode::new(0x79, "ADC", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page is crossed
-    Opcode::new(0x65, "ADC", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x75, "ADC", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x61, "ADC", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0x71, "ADC", 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x69, "ADC", Instruction::Adc, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x6d, "ADC", Instruction::Adc, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x7d, "ADC", Instruction::Adc, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page is crossed
+    Opcode::new(0x79, "ADC", Instruction::Adc, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page is crossed
+    Opcode::new(0x65, "ADC", Instruction::Adc, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x75, "ADC", Instruction::Adc, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x61, "ADC", Instruction::Adc, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0x71, "ADC", Instruction::Adc, 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
     // CMP
-    Opcode::new(0xc9, "CMP", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xcd, "CMP", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0xdd, "CMP", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page is crossed
-    Opcode::new(0xd9, "CMP", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page is crossed
-    Opcode::new(0xc5, "CMP", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0xd5, "CMP", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xc1, "CMP", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0xd1, "CMP", 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xc9, "CMP", Instruction::Cmp, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xcd, "CMP", Instruction::Cmp, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0xdd, "CMP", Instruction::Cmp, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page is crossed
+    Opcode::new(0xd9, "CMP", Instruction::Cmp, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page is crossed
+    Opcode::new(0xc5, "CMP", Instruction::Cmp, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0xd5, "CMP", Instruction::Cmp, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xc1, "CMP", Instruction::Cmp, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0xd1, "CMP", Instruction::Cmp, 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
     // CPX
-    Opcode::new(0xe0, "CPX", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xec, "CPX", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0xe4, "CPX", 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0xe0, "CPX", Instruction::Cpx, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xec, "CPX", Instruction::Cpx, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0xe4, "CPX", Instruction::Cpx, 2, 3, AddressingMode::ZeroPage, false),
     // CPY
-    Opcode::new(0xc0, "CPY", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xcc, "CPY", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0xc4, "CPY", 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0xc0, "CPY", Instruction::Cpy, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xcc, "CPY", Instruction::Cpy, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0xc4, "CPY", Instruction::Cpy, 2, 3, AddressingMode::ZeroPage, false),
     // SBC
-    Opcode::new(0xe9, "SBC", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xed, "SBC", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0xfd, "SBC", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page is crossed
-    Opcode::new(0xf9, "SBC", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page is crossed
-    Opcode::new(0xe5, "SBC", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0xf5, "SBC", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xe1, "SBC", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0xf1, "SBC", 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xe9, "SBC", Instruction::Sbc, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xed, "SBC", Instruction::Sbc, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0xfd, "SBC", Instruction::Sbc, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page is crossed
+    Opcode::new(0xf9, "SBC", Instruction::Sbc, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page is crossed
+    Opcode::new(0xe5, "SBC", Instruction::Sbc, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0xf5, "SBC", Instruction::Sbc, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xe1, "SBC", Instruction::Sbc, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0xf1, "SBC", Instruction::Sbc, 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
     // -- control instructions --
-    Opcode::new(0x00, "BRK", 1, 7, AddressingMode::Implied, false),
-    Opcode::new(0x4c, "JMP", 3, 3, AddressingMode::Absolute, false),
-    Opcode::new(0x6c, "JMP", 3, 5, AddressingMode::Indirect, false),
-    Opcode::new(0x20, "JSR", 3, 6, AddressingMode::Implied, false),
-    Opcode::new(0x40, "RTI", 3, 6, AddressingMode::Implied, false),
-    Opcode::new(0x60, "RTS", 3, 6, AddressingMode::Implied, false),
+    Opcode::new(0x00, "BRK", Instruction::Brk, 1, 7, AddressingMode::Implied, false),
+    Opcode::new(0x4c, "JMP", Instruction::Jmp, 3, 3, AddressingMode::Absolute, false),
+    Opcode::new(0x6c, "JMP", Instruction::Jmp, 3, 5, AddressingMode::Indirect, false),
+    Opcode::new(0x20, "JSR", Instruction::Jsr, 3, 6, AddressingMode::Implied, false),
+    Opcode::new(0x40, "RTI", Instruction::Rti, 3, 6, AddressingMode::Implied, false),
+    Opcode::new(0x60, "RTS", Instruction::Rts, 3, 6, AddressingMode::Implied, false),
     //------------------------------------- NON-STANDARD OPCODES -------------------------------------
     // *NOP
-    Opcode::new(0x1a, "*NOP", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x3a, "*NOP", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x5a, "*NOP", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x7a, "*NOP", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xda, "*NOP", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0xfa, "*NOP", 1, 2, AddressingMode::Implied, false),
-    Opcode::new(0x80, "*NOP", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0x82, "*NOP", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0x89, "*NOP", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xc2, "*NOP", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0xe2, "*NOP", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0x0c, "*NOP", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x1c, "*NOP", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x3c, "*NOP", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x5c, "*NOP", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x7c, "*NOP", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0xdc, "*NOP", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0xfc, "*NOP", 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
-    Opcode::new(0x04, "*NOP", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x44, "*NOP", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x64, "*NOP", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x14, "*NOP", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x34, "*NOP", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x54, "*NOP", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x74, "*NOP", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xd4, "*NOP", 2, 4, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xf4, "*NOP", 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x1a, "*NOP", Instruction::Nop, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x3a, "*NOP", Instruction::Nop, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x5a, "*NOP", Instruction::Nop, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x7a, "*NOP", Instruction::Nop, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xda, "*NOP", Instruction::Nop, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xfa, "*NOP", Instruction::Nop, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x80, "*NOP", Instruction::Nop, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x82, "*NOP", Instruction::Nop, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x89, "*NOP", Instruction::Nop, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xc2, "*NOP", Instruction::Nop, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xe2, "*NOP", Instruction::Nop, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x0c, "*NOP", Instruction::Nop, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x1c, "*NOP", Instruction::Nop, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x3c, "*NOP", Instruction::Nop, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x5c, "*NOP", Instruction::Nop, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x7c, "*NOP", Instruction::Nop, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xdc, "*NOP", Instruction::Nop, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xfc, "*NOP", Instruction::Nop, 3, 4, AddressingMode::AbsoluteX, true), // +1 cycle if page boundary crossed
+    Opcode::new(0x04, "*NOP", Instruction::Nop, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x44, "*NOP", Instruction::Nop, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x64, "*NOP", Instruction::Nop, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x14, "*NOP", Instruction::Nop, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x34, "*NOP", Instruction::Nop, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x54, "*NOP", Instruction::Nop, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x74, "*NOP", Instruction::Nop, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xd4, "*NOP", Instruction::Nop, 2, 4, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xf4, "*NOP", Instruction::Nop, 2, 4, AddressingMode::ZeroPageX, false),
     // *LAX
     // note: $AB immediate is LXA (unstable) — handled separately below
-    Opcode::new(0xaf, "*LAX", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0xbf, "*LAX", 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
-    Opcode::new(0xa7, "*LAX", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0xb7, "*LAX", 2, 4, AddressingMode::ZeroPageY, false),
-    Opcode::new(0xa3, "*LAX", 2, 6, AddressingMode::IndirectX, false),
-    Opcode::new(0xb3, "*LAX", 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xaf, "*LAX", Instruction::Lax, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0xbf, "*LAX", Instruction::Lax, 3, 4, AddressingMode::AbsoluteY, true), // +1 cycle if page boundary crossed
+    Opcode::new(0xa7, "*LAX", Instruction::Lax, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0xb7, "*LAX", Instruction::Lax, 2, 4, AddressingMode::ZeroPageY, false),
+    Opcode::new(0xa3, "*LAX", Instruction::Lax, 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0xb3, "*LAX", Instruction::Lax, 2, 5, AddressingMode::IndirectY, true), // +1 cycle if page boundary crossed
     // *SAX
-    Opcode::new(0x8f, "*SAX", 3, 4, AddressingMode::Absolute, false),
-    Opcode::new(0x87, "*SAX", 2, 3, AddressingMode::ZeroPage, false),
-    Opcode::new(0x97, "*SAX", 2, 4, AddressingMode::ZeroPageY, false),
-    Opcode::new(0x83, "*SAX", 2, 6, AddressingMode::IndirectX, false),
+    Opcode::new(0x8f, "*SAX", Instruction::Sax, 3, 4, AddressingMode::Absolute, false),
+    Opcode::new(0x87, "*SAX", Instruction::Sax, 2, 3, AddressingMode::ZeroPage, false),
+    Opcode::new(0x97, "*SAX", Instruction::Sax, 2, 4, AddressingMode::ZeroPageY, false),
+    Opcode::new(0x83, "*SAX", Instruction::Sax, 2, 6, AddressingMode::IndirectX, false),
     // *SBC
-    Opcode::new(0xeb, "*SBC", 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xeb, "*SBC", Instruction::Sbc, 2, 2, AddressingMode::Immediate, false),
     // *DCP
-    Opcode::new(0xcf, "*DCP", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0xdf, "*DCP", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0xdb, "*DCP", 3, 7, AddressingMode::AbsoluteY, false),
-    Opcode::new(0xc7, "*DCP", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0xd7, "*DCP", 2, 6, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xc3, "*DCP", 2, 8, AddressingMode::IndirectX, false),
-    Opcode::new(0xd3, "*DCP", 2, 8, AddressingMode::IndirectY, false),
+    Opcode::new(0xcf, "*DCP", Instruction::Dcp, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0xdf, "*DCP", Instruction::Dcp, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0xdb, "*DCP", Instruction::Dcp, 3, 7, AddressingMode::AbsoluteY, false),
+    Opcode::new(0xc7, "*DCP", Instruction::Dcp, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0xd7, "*DCP", Instruction::Dcp, 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xc3, "*DCP", Instruction::Dcp, 2, 8, AddressingMode::IndirectX, false),
+    Opcode::new(0xd3, "*DCP", Instruction::Dcp, 2, 8, AddressingMode::IndirectY, false),
     // *ISB
-    Opcode::new(0xef, "*ISB", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0xff, "*ISB", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0xfb, "*ISB", 3, 7, AddressingMode::AbsoluteY, false),
-    Opcode::new(0xe7, "*ISB", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0xf7, "*ISB", 2, 6, AddressingMode::ZeroPageX, false),
-    Opcode::new(0xe3, "*ISB", 2, 8, AddressingMode::IndirectX, false),
-    Opcode::new(0xf3, "*ISB", 2, 8, AddressingMode::IndirectY, false),
+    Opcode::new(0xef, "*ISB", Instruction::Isb, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0xff, "*ISB", Instruction::Isb, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0xfb, "*ISB", Instruction::Isb, 3, 7, AddressingMode::AbsoluteY, false),
+    Opcode::new(0xe7, "*ISB", Instruction::Isb, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0xf7, "*ISB", Instruction::Isb, 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0xe3, "*ISB", Instruction::Isb, 2, 8, AddressingMode::IndirectX, false),
+    Opcode::new(0xf3, "*ISB", Instruction::Isb, 2, 8, AddressingMode::IndirectY, false),
     // *SLO
-    Opcode::new(0x0f, "*SLO", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0x1f, "*SLO", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x1b, "*SLO", 3, 7, AddressingMode::AbsoluteY, false),
-    Opcode::new(0x07, "*SLO", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0x17, "*SLO", 2, 6, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x03, "*SLO", 2, 8, AddressingMode::IndirectX, false),
-    Opcode::new(0x13, "*SLO", 2, 8, AddressingMode::IndirectY, false),
+    Opcode::new(0x0f, "*SLO", Instruction::Slo, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0x1f, "*SLO", Instruction::Slo, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x1b, "*SLO", Instruction::Slo, 3, 7, AddressingMode::AbsoluteY, false),
+    Opcode::new(0x07, "*SLO", Instruction::Slo, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0x17, "*SLO", Instruction::Slo, 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x03, "*SLO", Instruction::Slo, 2, 8, AddressingMode::IndirectX, false),
+    Opcode::new(0x13, "*SLO", Instruction::Slo, 2, 8, AddressingMode::IndirectY, false),
     // *RLA
-    Opcode::new(0x2f, "*RLA", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0x3f, "*RLA", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x3b, "*RLA", 3, 7, AddressingMode::AbsoluteY, false),
-    Opcode::new(0x27, "*RLA", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0x37, "*RLA", 2, 6, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x23, "*RLA", 2, 8, AddressingMode::IndirectX, false),
-    Opcode::new(0x33, "*RLA", 2, 8, AddressingMode::IndirectY, false),
+    Opcode::new(0x2f, "*RLA", Instruction::Rla, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0x3f, "*RLA", Instruction::Rla, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x3b, "*RLA", Instruction::Rla, 3, 7, AddressingMode::AbsoluteY, false),
+    Opcode::new(0x27, "*RLA", Instruction::Rla, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0x37, "*RLA", Instruction::Rla, 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x23, "*RLA", Instruction::Rla, 2, 8, AddressingMode::IndirectX, false),
+    Opcode::new(0x33, "*RLA", Instruction::Rla, 2, 8, AddressingMode::IndirectY, false),
     // *SRE
-    Opcode::new(0x4f, "*SRE", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0x5f, "*SRE", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x5b, "*SRE", 3, 7, AddressingMode::AbsoluteY, false),
-    Opcode::new(0x47, "*SRE", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0x57, "*SRE", 2, 6, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x43, "*SRE", 2, 8, AddressingMode::IndirectX, false),
-    Opcode::new(0x53, "*SRE", 2, 8, AddressingMode::IndirectY, false),
+    Opcode::new(0x4f, "*SRE", Instruction::Sre, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0x5f, "*SRE", Instruction::Sre, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x5b, "*SRE", Instruction::Sre, 3, 7, AddressingMode::AbsoluteY, false),
+    Opcode::new(0x47, "*SRE", Instruction::Sre, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0x57, "*SRE", Instruction::Sre, 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x43, "*SRE", Instruction::Sre, 2, 8, AddressingMode::IndirectX, false),
+    Opcode::new(0x53, "*SRE", Instruction::Sre, 2, 8, AddressingMode::IndirectY, false),
     // *ANC (AND with carry set to bit 7)
-    Opcode::new(0x0b, "*ANC", 2, 2, AddressingMode::Immediate, false),
-    Opcode::new(0x2b, "*ANC", 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x0b, "*ANC", Instruction::Anc, 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x2b, "*ANC", Instruction::Anc, 2, 2, AddressingMode::Immediate, false),
     // *ALR (AND + LSR)
-    Opcode::new(0x4b, "*ALR", 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x4b, "*ALR", Instruction::Alr, 2, 2, AddressingMode::Immediate, false),
     // *ARR (AND + ROR with special flags)
-    Opcode::new(0x6b, "*ARR", 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x6b, "*ARR", Instruction::Arr, 2, 2, AddressingMode::Immediate, false),
     // *ANE (unstable: (A | magic) & X & imm → A)
-    Opcode::new(0x8b, "*ANE", 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0x8b, "*ANE", Instruction::Ane, 2, 2, AddressingMode::Immediate, false),
     // *LXA (unstable: (A | magic) & imm → A, X)
-    Opcode::new(0xab, "*LXA", 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xab, "*LXA", Instruction::Lxa, 2, 2, AddressingMode::Immediate, false),
     // *AXS (A & X - imm → X, sets N, Z, C)
-    Opcode::new(0xcb, "*AXS", 2, 2, AddressingMode::Immediate, false),
+    Opcode::new(0xcb, "*AXS", Instruction::Axs, 2, 2, AddressingMode::Immediate, false),
     // *SHA (stores A & X & (addr_high + 1))
-    Opcode::new(0x93, "*SHA", 2, 6, AddressingMode::IndirectY, false),
-    Opcode::new(0x9f, "*SHA", 3, 5, AddressingMode::AbsoluteY, false),
+    Opcode::new(0x93, "*SHA", Instruction::Sha, 2, 6, AddressingMode::IndirectY, false),
+    Opcode::new(0x9f, "*SHA", Instruction::Sha, 3, 5, AddressingMode::AbsoluteY, false),
     // *SHX (stores X & (addr_high + 1))
-    Opcode::new(0x9e, "*SHX", 3, 5, AddressingMode::AbsoluteY, false),
+    Opcode::new(0x9e, "*SHX", Instruction::Shx, 3, 5, AddressingMode::AbsoluteY, false),
     // *SHY (stores Y & (addr_high + 1))
-    Opcode::new(0x9c, "*SHY", 3, 5, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x9c, "*SHY", Instruction::Shy, 3, 5, AddressingMode::AbsoluteX, false),
     // *SHS (S = A & X; stores A & X & (addr_high + 1))
-    Opcode::new(0x9b, "*SHS", 3, 5, AddressingMode::AbsoluteY, false),
+    Opcode::new(0x9b, "*SHS", Instruction::Shs, 3, 5, AddressingMode::AbsoluteY, false),
     // *LAS (A = X = S = mem & S)
-    Opcode::new(0xbb, "*LAS", 3, 4, AddressingMode::AbsoluteY, true),
+    Opcode::new(0xbb, "*LAS", Instruction::Las, 3, 4, AddressingMode::AbsoluteY, true),
     // *RRA
-    Opcode::new(0x6f, "*RRA", 3, 6, AddressingMode::Absolute, false),
-    Opcode::new(0x7f, "*RRA", 3, 7, AddressingMode::AbsoluteX, false),
-    Opcode::new(0x7b, "*RRA", 3, 7, AddressingMode::AbsoluteY, false),
-    Opcode::new(0x67, "*RRA", 2, 5, AddressingMode::ZeroPage, false),
-    Opcode::new(0x77, "*RRA", 2, 6, AddressingMode::ZeroPageX, false),
-    Opcode::new(0x63, "*RRA", 2, 8, AddressingMode::IndirectX, false),
-    Opcode::new(0x73, "*RRA", 2, 8, AddressingMode::IndirectY, false),
+    Opcode::new(0x6f, "*RRA", Instruction::Rra, 3, 6, AddressingMode::Absolute, false),
+    Opcode::new(0x7f, "*RRA", Instruction::Rra, 3, 7, AddressingMode::AbsoluteX, false),
+    Opcode::new(0x7b, "*RRA", Instruction::Rra, 3, 7, AddressingMode::AbsoluteY, false),
+    Opcode::new(0x67, "*RRA", Instruction::Rra, 2, 5, AddressingMode::ZeroPage, false),
+    Opcode::new(0x77, "*RRA", Instruction::Rra, 2, 6, AddressingMode::ZeroPageX, false),
+    Opcode::new(0x63, "*RRA", Instruction::Rra, 2, 8, AddressingMode::IndirectX, false),
+    Opcode::new(0x73, "*RRA", Instruction::Rra, 2, 8, AddressingMode::IndirectY, false),
+    // *JAM (KIL: halts the CPU until reset)
+    Opcode::new(0x02, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x12, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x22, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x32, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x42, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x52, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x62, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x72, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0x92, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xb2, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xd2, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
+    Opcode::new(0xf2, "*JAM", Instruction::Jam, 1, 2, AddressingMode::Implied, false),
 ];
 
-pub static OPCODES_MAPPING: Lazy<HashMap<Byte, &'static Opcode>> =
-    Lazy::new(|| HashMap::from_iter(OPCODES.iter().map(|opcode| (opcode.code, opcode))));
+static OPCODE_TABLE: [Opcode; 256] = opcode_table();
+
+const fn opcode_table() -> [Opcode; 256] {
+    assert!(OPCODES.len() == 256, "every byte must decode to an opcode");
+    let mut table = [OPCODES[0]; 256];
+    let mut defined = [false; 256];
+    let mut index = 0;
+    while index < OPCODES.len() {
+        let opcode = OPCODES[index];
+        let code = opcode.code.as_usize();
+        assert!(!defined[code], "duplicate opcode");
+        table[code] = opcode;
+        defined[code] = true;
+        index += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_match_instructions() {
+        for opcode in OPCODES {
+            let name = opcode.name.trim_start_matches('*');
+            let instruction = format!("{:?}", opcode.instruction).to_ascii_uppercase();
+            assert_eq!(name, instruction, "opcode {:02X}", opcode.code);
+        }
+    }
+
+    #[test]
+    fn decode_opcodes() {
+        assert_eq!(decode(Byte::new(0xa9)).name, "LDA");
+        assert_eq!(decode(Byte::new(0xeb)).instruction, Instruction::Sbc);
+        assert_eq!(decode(Byte::new(0x02)).instruction, Instruction::Jam);
+        for code in 0..=u8::MAX {
+            assert_eq!(decode(Byte::new(code)).code, Byte::new(code));
+        }
+    }
+}
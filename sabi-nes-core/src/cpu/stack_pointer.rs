@@ -41,10 +41,6 @@ impl StackPointer {
         self.0 = value;
     }
 
-    pub fn reset(&mut self) {
-        self.0 = STACK_RESET;
-    }
-
     pub fn decrement(&mut self) {
         self.0 = self.0.wrapping_sub(1);
     }
@@ -0,0 +1,505 @@
+//! 6502 disassembler for debuggers, tracers and tools.
+//!
+//! Instructions are decoded from a [`ByteSource`], which is read without side effects,
+//! so disassembling never disturbs the emulated machine.
+
+use crate::cpu::AddressingMode;
+use crate::cpu::opcodes::{Instruction, Opcode, decode};
+use crate::{Address, Byte, Memory, Word};
+use std::collections::HashMap;
+use std::iter;
+
+/// Anything instructions can be decoded from
+pub trait ByteSource {
+    /// The byte at `address`, fetched without side effects
+    fn peek(&self, address: Address) -> Byte;
+}
+
+impl<M: Memory> ByteSource for M {
+    fn peek(&self, address: Address) -> Byte {
+        self.peek_byte(address)
+    }
+}
+
+/// Code held in a buffer, e.g. a PRG ROM bank, as if it were mapped at `origin`.
+/// Addresses outside the buffer read as $00.
+pub struct CodeBuffer<'a> {
+    origin: Address,
+    bytes: &'a [Byte],
+}
+
+impl<'a> CodeBuffer<'a> {
+    pub fn new(origin: Address, bytes: &'a [Byte]) -> Self {
+        Self { origin, bytes }
+    }
+}
+
+impl ByteSource for CodeBuffer<'_> {
+    fn peek(&self, address: Address) -> Byte {
+        let offset = address.value().wrapping_sub(self.origin.value());
+        self.bytes
+            .get(usize::from(offset))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Decoded operand of an instruction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operand {
+    None,
+    Accumulator,
+    Immediate(Byte),
+    ZeroPage(Byte),
+    ZeroPageX(Byte),
+    ZeroPageY(Byte),
+    Absolute(Address),
+    AbsoluteX(Address),
+    AbsoluteY(Address),
+    Indirect(Address),
+    IndirectX(Byte),
+    IndirectY(Byte),
+    /// Branch, holding the target address
+    Relative(Address),
+}
+
+/// One instruction, as found at `address`
+#[derive(Debug, Copy, Clone)]
+pub struct DecodedInstruction {
+    pub address: Address,
+    pub opcode: &'static Opcode,
+    pub operand: Operand,
+    bytes: [Byte; 3],
+    size: usize,
+}
+
+impl DecodedInstruction {
+    /// Opcode byte followed by the operand bytes
+    pub fn bytes(&self) -> &[Byte] {
+        &self.bytes[..self.size]
+    }
+
+    /// Number of bytes the instruction takes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Address of the instruction that follows in memory
+    pub fn next_address(&self) -> Address {
+        self.address.wrapping_add(self.size as u16)
+    }
+
+    pub fn is_unofficial(&self) -> bool {
+        self.opcode.name.starts_with('*')
+    }
+
+    /// Mnemonic without the `*` marking unofficial opcodes
+    pub fn mnemonic(&self) -> &'static str {
+        self.opcode.name.trim_start_matches('*')
+    }
+}
+
+/// Decode the instruction at `address`
+pub fn decode_at<S: ByteSource + ?Sized>(source: &S, address: Address) -> DecodedInstruction {
+    let opcode = decode(source.peek(address));
+    let size = 1 + operand_size(opcode);
+    let mut bytes = [Byte::default(); 3];
+    for (offset, byte) in (0u16..).zip(bytes.iter_mut().take(size)) {
+        *byte = source.peek(address.wrapping_add(offset));
+    }
+
+    let byte = bytes[1];
+    let word = Word::from_le_bytes(bytes[1], bytes[2]).as_address();
+    let operand = match opcode.addressing_mode {
+        // JSR is Implied internally to control its cycle order, but takes an absolute address
+        AddressingMode::Implied if opcode.instruction == Instruction::Jsr => {
+            Operand::Absolute(word)
+        }
+        AddressingMode::Implied => Operand::None,
+        AddressingMode::Accumulator => Operand::Accumulator,
+        AddressingMode::Immediate => Operand::Immediate(byte),
+        AddressingMode::ZeroPage => Operand::ZeroPage(byte),
+        AddressingMode::ZeroPageX => Operand::ZeroPageX(byte),
+        AddressingMode::ZeroPageY => Operand::ZeroPageY(byte),
+        AddressingMode::Absolute => Operand::Absolute(word),
+        AddressingMode::AbsoluteX => Operand::AbsoluteX(word),
+        AddressingMode::AbsoluteY => Operand::AbsoluteY(word),
+        AddressingMode::Indirect => Operand::Indirect(word),
+        AddressingMode::IndirectX => Operand::IndirectX(byte),
+        AddressingMode::IndirectY => Operand::IndirectY(byte),
+        AddressingMode::Relative => {
+            let offset = i16::from(byte.value().cast_signed());
+            let target = address.value().wrapping_add(2).wrapping_add_signed(offset);
+            Operand::Relative(Address::new(target))
+        }
+    };
+
+    DecodedInstruction {
+        address,
+        opcode,
+        operand,
+        bytes,
+        size,
+    }
+}
+
+/// Decode consecutive instructions starting at `address`
+pub fn decode_from<S: ByteSource + ?Sized>(
+    source: &S,
+    address: Address,
+) -> impl Iterator<Item = DecodedInstruction> + '_ {
+    iter::successors(Some(decode_at(source, address)), |instruction| {
+        Some(decode_at(source, instruction.next_address()))
+    })
+}
+
+/// Operand bytes following the opcode. The opcode table's byte counts describe how the CPU
+/// advances PC (e.g. RTS and RTI count 3), not the instruction's size in memory.
+fn operand_size(opcode: &Opcode) -> usize {
+    match opcode.addressing_mode {
+        AddressingMode::Implied if opcode.instruction == Instruction::Jsr => 2,
+        AddressingMode::Implied | AddressingMode::Accumulator => 0,
+        AddressingMode::Immediate
+        | AddressingMode::ZeroPage
+        | AddressingMode::ZeroPageX
+        | AddressingMode::ZeroPageY
+        | AddressingMode::IndirectX
+        | AddressingMode::IndirectY
+        | AddressingMode::Relative => 1,
+        AddressingMode::Absolute
+        | AddressingMode::AbsoluteX
+        | AddressingMode::AbsoluteY
+        | AddressingMode::Indirect => 2,
+    }
+}
+
+/// Assembly syntax of the disassembly
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Syntax {
+    /// Upper case, unofficial opcodes marked with `*`, as in the nestest log
+    #[default]
+    Nestest,
+    /// Lower case, reassembles with ca65's `6502X` CPU. Unofficial opcodes ca65 can't
+    /// reproduce byte for byte are emitted as `.byte`.
+    Ca65,
+}
+
+/// Index registers, for resolving effective addresses in annotated disassembly
+#[derive(Debug, Default, Copy, Clone)]
+pub struct IndexRegisters {
+    pub x: Byte,
+    pub y: Byte,
+}
+
+/// Formats decoded instructions, substituting labels for the addresses that have one
+#[derive(Debug, Default)]
+pub struct Disassembler {
+    syntax: Syntax,
+    labels: HashMap<Address, String>,
+}
+
+impl Disassembler {
+    pub fn new(syntax: Syntax) -> Self {
+        Self {
+            syntax,
+            labels: HashMap::new(),
+        }
+    }
+
+    pub fn syntax(&self) -> Syntax {
+        self.syntax
+    }
+
+    /// Show `name` instead of `address` wherever it's an operand
+    pub fn add_label(&mut self, address: Address, name: impl Into<String>) {
+        self.labels.insert(address, name.into());
+    }
+
+    pub fn label(&self, address: Address) -> Option<&str> {
+        self.labels.get(&address).map(String::as_str)
+    }
+
+    /// The instruction on its own, e.g. `LDA $0400,X`
+    pub fn format(&self, instruction: &DecodedInstruction) -> String {
+        match self.syntax {
+            Syntax::Nestest => self.format_nestest(instruction),
+            Syntax::Ca65 => self.format_ca65(instruction),
+        }
+    }
+
+    /// The instruction with the effective address and the value it points to, as the nestest
+    /// log shows them, e.g. `LDA $0400,X @ 0405 = AA`. Only the nestest syntax is annotated.
+    pub fn format_annotated<S: ByteSource + ?Sized>(
+        &self,
+        instruction: &DecodedInstruction,
+        source: &S,
+        registers: IndexRegisters,
+    ) -> String {
+        if self.syntax != Syntax::Nestest {
+            return self.format(instruction);
+        }
+
+        let peek = |address: Address| source.peek(address);
+        let zero_page_word = |pointer: Byte| {
+            let low = peek(pointer.as_address());
+            let high = peek(pointer.wrapping_add(1).as_address());
+            Word::from_le_bytes(low, high).as_address()
+        };
+        let annotation = match instruction.operand {
+            Operand::ZeroPage(address) => {
+                let value = peek(address.as_address());
+                format!("{} = {value:02X}", self.zero_page(address))
+            }
+            Operand::ZeroPageX(address) | Operand::ZeroPageY(address) => {
+                let (index, register) = match instruction.operand {
+                    Operand::ZeroPageX(_) => (registers.x, 'X'),
+                    _ => (registers.y, 'Y'),
+                };
+                let target = address.wrapping_add(index);
+                let value = peek(target.as_address());
+                format!(
+                    "{},{register} @ {target:02X} = {value:02X}",
+                    self.zero_page(address)
+                )
+            }
+            Operand::Absolute(address) => match instruction.opcode.instruction {
+                Instruction::Jmp | Instruction::Jsr => self.absolute(address),
+                _ => format!("{} = {:02X}", self.absolute(address), peek(address)),
+            },
+            Operand::AbsoluteX(address) | Operand::AbsoluteY(address) => {
+                let (index, register) = match instruction.operand {
+                    Operand::AbsoluteX(_) => (registers.x, 'X'),
+                    _ => (registers.y, 'Y'),
+                };
+                let target = address.wrapping_add(index);
+                format!(
+                    "{},{register} @ {target:04X} = {:02X}",
+                    self.absolute(address),
+                    peek(target)
+                )
+            }
+            Operand::Indirect(address) => {
+                // The pointer's high byte is fetched without carrying into the page
+                let high_address = (address & 0xff00) | (address.wrapping_add(1u16) & 0x00ff);
+                let target = Word::from_le_bytes(peek(address), peek(high_address)).as_address();
+                format!("({}) = {target:04X}", self.absolute(address))
+            }
+            Operand::IndirectX(address) => {
+                let pointer = address.wrapping_add(registers.x);
+                let target = zero_page_word(pointer);
+                format!(
+                    "({},X) @ {pointer:02X} = {target:04X} = {:02X}",
+                    self.zero_page(address),
+                    peek(target)
+                )
+            }
+            Operand::IndirectY(address) => {
+                let base = zero_page_word(address);
+                let target = base.wrapping_add(registers.y);
+                format!(
+                    "({}),Y = {base:04X} @ {target:04X} = {:02X}",
+                    self.zero_page(address),
+                    peek(target)
+                )
+            }
+            _ => return self.format(instruction),
+        };
+
+        format!("{} {annotation}", instruction.opcode.name)
+    }
+
+    fn format_nestest(&self, instruction: &DecodedInstruction) -> String {
+        let operand = match instruction.operand {
+            Operand::None => return instruction.opcode.name.to_owned(),
+            Operand::Accumulator => "A".to_owned(),
+            Operand::Immediate(value) => format!("#${value:02X}"),
+            Operand::ZeroPage(address) => self.zero_page(address),
+            Operand::ZeroPageX(address) => format!("{},X", self.zero_page(address)),
+            Operand::ZeroPageY(address) => format!("{},Y", self.zero_page(address)),
+            Operand::Absolute(address) | Operand::Relative(address) => self.absolute(address),
+            Operand::AbsoluteX(address) => format!("{},X", self.absolute(address)),
+            Operand::AbsoluteY(address) => format!("{},Y", self.absolute(address)),
+            Operand::Indirect(address) => format!("({})", self.absolute(address)),
+            Operand::IndirectX(address) => format!("({},X)", self.zero_page(address)),
+            Operand::IndirectY(address) => format!("({}),Y", self.zero_page(address)),
+        };
+
+        format!("{} {operand}", instruction.opcode.name)
+    }
+
+    fn format_ca65(&self, instruction: &DecodedInstruction) -> String {
+        let Some(mnemonic) = ca65_mnemonic(instruction.opcode) else {
+            let bytes: Vec<_> = instruction
+                .bytes()
+                .iter()
+                .map(|byte| format!("${byte:02X}"))
+                .collect();
+            return format!(".byte {}", bytes.join(", "));
+        };
+
+        // ca65 picks zero page addressing for small addresses unless told otherwise
+        let absolute = |address: Address| match address < 0x100 {
+            true => format!("a:{}", self.absolute(address)),
+            false => self.absolute(address),
+        };
+        let operand = match instruction.operand {
+            Operand::None => return mnemonic,
+            Operand::Accumulator => "a".to_owned(),
+            Operand::Immediate(value) => format!("#${value:02X}"),
+            Operand::ZeroPage(address) => self.zero_page(address),
+            Operand::ZeroPageX(address) => format!("{},x", self.zero_page(address)),
+            Operand::ZeroPageY(address) => format!("{},y", self.zero_page(address)),
+            Operand::Absolute(address) => absolute(address),
+            Operand::AbsoluteX(address) => format!("{},x", absolute(address)),
+            Operand::AbsoluteY(address) => format!("{},y", absolute(address)),
+            Operand::Relative(address) => self.absolute(address),
+            Operand::Indirect(address) => format!("({})", self.absolute(address)),
+            Operand::IndirectX(address) => format!("({},x)", self.zero_page(address)),
+            Operand::IndirectY(address) => format!("({}),y", self.zero_page(address)),
+        };
+
+        format!("{mnemonic} {operand}")
+    }
+
+    fn zero_page(&self, address: Byte) -> String {
+        match self.label(address.as_address()) {
+            Some(label) => label.to_owned(),
+            None => format!("${address:02X}"),
+        }
+    }
+
+    fn absolute(&self, address: Address) -> String {
+        match self.label(address) {
+            Some(label) => label.to_owned(),
+            None => format!("${address:04X}"),
+        }
+    }
+}
+
+/// ca65 mnemonic reassembling to the same bytes, `None` for opcodes it would encode differently
+/// (duplicates of official opcodes, alternative encodings) or doesn't know
+fn ca65_mnemonic(opcode: &Opcode) -> Option<String> {
+    let mnemonic = match opcode.instruction {
+        _ if !opcode.name.starts_with('*') => opcode.name,
+        Instruction::Alr => "alr",
+        Instruction::Anc if opcode.code == 0x0b => "anc",
+        Instruction::Arr => "arr",
+        Instruction::Axs => "axs",
+        Instruction::Dcp => "dcp",
+        Instruction::Isb => "isc",
+        Instruction::Las => "las",
+        Instruction::Lax => "lax",
+        Instruction::Rla => "rla",
+        Instruction::Rra => "rra",
+        Instruction::Sax => "sax",
+        Instruction::Slo => "slo",
+        Instruction::Sre => "sre",
+        _ => return None,
+    };
+
+    Some(mnemonic.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Clock, FlatMemory};
+
+    /// Decode `bytes` as if they were at $8000
+    fn decode_bytes(bytes: &[u8]) -> DecodedInstruction {
+        let bytes: Vec<_> = bytes.iter().map(|&byte| Byte::new(byte)).collect();
+        decode_at(
+            &CodeBuffer::new(Address::new(0x8000), &bytes),
+            Address::new(0x8000),
+        )
+    }
+
+    #[test]
+    fn decodes_operands() {
+        let lda = decode_bytes(&[0xbd, 0x00, 0x04]);
+        assert_eq!(lda.operand, Operand::AbsoluteX(Address::new(0x0400)));
+        assert_eq!(lda.size(), 3);
+        assert_eq!(lda.next_address(), 0x8003);
+
+        let jsr = decode_bytes(&[0x20, 0x34, 0x12]);
+        assert_eq!(jsr.operand, Operand::Absolute(Address::new(0x1234)));
+        assert_eq!(jsr.bytes().len(), 3);
+
+        assert_eq!(decode_bytes(&[0x60]).size(), 1); // RTS
+        assert_eq!(
+            decode_bytes(&[0xd0, 0xfe]).operand, // BNE to itself
+            Operand::Relative(Address::new(0x8000))
+        );
+    }
+
+    #[test]
+    fn decoding_has_no_side_effects() {
+        let mut memory = FlatMemory::new();
+        memory.write_byte(Address::new(0x0200), Byte::new(0xea));
+
+        let instructions: Vec<_> = decode_from(&memory, Address::new(0x0200)).take(2).collect();
+
+        assert_eq!(instructions[0].mnemonic(), "NOP");
+        assert_eq!(instructions[1].address, 0x0201);
+        assert_eq!(memory.cycles(), 0);
+    }
+
+    #[test]
+    fn nestest_syntax() {
+        let disassembler = Disassembler::new(Syntax::Nestest);
+        let format = |bytes: &[u8]| disassembler.format(&decode_bytes(bytes));
+
+        assert_eq!(format(&[0xa9, 0x01]), "LDA #$01");
+        assert_eq!(format(&[0x0a]), "ASL A");
+        assert_eq!(format(&[0xca]), "DEX");
+        assert_eq!(format(&[0x91, 0x33]), "STA ($33),Y");
+        assert_eq!(format(&[0x6c, 0xff, 0x02]), "JMP ($02FF)");
+        assert_eq!(format(&[0x04, 0xa9]), "*NOP $A9");
+    }
+
+    #[test]
+    fn annotated_nestest_syntax() {
+        let mut memory = FlatMemory::new();
+        memory.write_byte(Address::new(0x33), Byte::new(0x00));
+        memory.write_byte(Address::new(0x34), Byte::new(0x04));
+        memory.write_byte(Address::new(0x0405), Byte::new(0xaa));
+        let registers = IndexRegisters {
+            x: Byte::new(0x00),
+            y: Byte::new(0x05),
+        };
+        let disassembler = Disassembler::new(Syntax::Nestest);
+        let annotated =
+            |bytes: &[u8]| disassembler.format_annotated(&decode_bytes(bytes), &memory, registers);
+
+        assert_eq!(annotated(&[0x11, 0x33]), "ORA ($33),Y = 0400 @ 0405 = AA");
+        assert_eq!(annotated(&[0xb9, 0x00, 0x04]), "LDA $0400,Y @ 0405 = AA");
+        assert_eq!(annotated(&[0x4c, 0x00, 0x04]), "JMP $0400");
+    }
+
+    #[test]
+    fn ca65_syntax() {
+        let disassembler = Disassembler::new(Syntax::Ca65);
+        let format = |bytes: &[u8]| disassembler.format(&decode_bytes(bytes));
+
+        assert_eq!(format(&[0xa9, 0x01]), "lda #$01");
+        assert_eq!(format(&[0x0a]), "asl a");
+        assert_eq!(format(&[0xad, 0x12, 0x00]), "lda a:$0012");
+        assert_eq!(format(&[0xb1, 0x33]), "lda ($33),y");
+        assert_eq!(format(&[0xe7, 0x10]), "isc $10");
+        assert_eq!(format(&[0xeb, 0x01]), ".byte $EB, $01");
+        assert_eq!(format(&[0x04, 0xa9]), ".byte $04, $A9");
+    }
+
+    #[test]
+    fn labels_replace_addresses() {
+        let mut disassembler = Disassembler::new(Syntax::Nestest);
+        disassembler.add_label(Address::new(0x8000), "reset");
+        disassembler.add_label(Address::new(0x0010), "counter");
+        let format = |bytes: &[u8]| disassembler.format(&decode_bytes(bytes));
+
+        assert_eq!(format(&[0x4c, 0x00, 0x80]), "JMP reset");
+        assert_eq!(format(&[0xd0, 0xfe]), "BNE reset");
+        assert_eq!(format(&[0xe6, 0x10]), "INC counter");
+        assert_eq!(format(&[0xee, 0x10, 0x00]), "INC counter");
+    }
+}
@@ -1,12 +1,35 @@
+use crate::bus::RamInit;
+use crate::cpu::Clock;
 use crate::frontend::Frontend;
+use crate::ppu::PpuPosition;
 use crate::render::{Frame, Renderer, SystemPalette};
-use crate::{Bus, Cpu, Result, Rom};
+use crate::trace::TraceLogger;
+use crate::{Address, Bus, Byte, Cpu, Result, Rom};
+use log::{info, warn};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// How often (in frames) battery-backed RAM is checked for changes and written to disk
+const SAVE_INTERVAL_FRAMES: u32 = 60;
 
 pub struct Emulator<F> {
     frontend: F,
     frame: Frame,
     cpu: Cpu,
     palette: SystemPalette,
+    save_file: Option<SaveFile>,
+    tracer: Option<TraceLogger>,
+    /// Whether the frontend has been told about the current CPU halt
+    halt_reported: bool,
+}
+
+/// `.sav` file backing the battery-backed RAM of the cartridge
+struct SaveFile {
+    path: PathBuf,
+    /// Contents as of the last load/flush, so unchanged RAM isn't rewritten
+    saved: Vec<Byte>,
+    frames_since_flush: u32,
 }
 
 impl<F> Emulator<F>
@@ -14,23 +37,159 @@ where
     F: Frontend,
 {
     pub fn new(frontend: F, rom: Rom) -> Result<Self> {
-        let bus = Bus::new(rom);
-        let mut cpu = Cpu::new(bus);
-        cpu.reset()?;
-        Ok(Self {
+        Self::with_ram_init(frontend, rom, RamInit::default())
+    }
+
+    /// Create an emulator whose RAM comes up with `ram_init` on every power-on
+    pub fn with_ram_init(frontend: F, rom: Rom, ram_init: RamInit) -> Result<Self> {
+        let mut bus = Bus::new(rom);
+        bus.set_ram_init(ram_init);
+        let mut emulator = Self {
             frontend,
             frame: Frame::new(),
-            cpu,
+            cpu: Cpu::new(bus),
             palette: SystemPalette::new(),
-        })
+            save_file: None,
+            tracer: None,
+            halt_reported: false,
+        };
+        emulator.power_on()?;
+
+        Ok(emulator)
+    }
+
+    /// Power-cycle the console: RAM is refilled and every component starts over.
+    /// Battery-backed RAM keeps its contents.
+    pub fn power_on(&mut self) -> Result<()> {
+        self.halt_reported = false;
+        self.cpu.power_on()
+    }
+
+    /// Press the reset button, which also brings a halted CPU back to life
+    pub fn reset(&mut self) -> Result<()> {
+        self.halt_reported = false;
+        self.cpu.reset()
+    }
+
+    /// Log every instruction executed from now on with `tracer`
+    pub fn set_tracer(&mut self, tracer: TraceLogger) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn tracer(&self) -> Option<&TraceLogger> {
+        self.tracer.as_ref()
+    }
+
+    /// Stop tracing, handing back the logger
+    pub fn take_tracer(&mut self) -> Option<TraceLogger> {
+        self.tracer.take()
+    }
+
+    /// CPU cycles elapsed since power-on
+    pub fn cpu_cycles(&self) -> u64 {
+        self.cpu.bus().cycles()
+    }
+
+    /// Master clock cycles elapsed since power-on
+    pub fn master_cycles(&self) -> u64 {
+        self.cpu.bus().master_cycles()
+    }
+
+    /// Frames completed since power-on
+    pub fn frame_count(&self) -> u64 {
+        self.cpu.bus().frame_count()
+    }
+
+    pub fn ppu_position(&self) -> PpuPosition {
+        self.cpu.bus().ppu_position()
+    }
+
+    /// Address of the JAM opcode the CPU is halted on, `None` while it is running
+    pub fn cpu_halted_at(&self) -> Option<Address> {
+        self.cpu.halted_at()
+    }
+
+    /// Persist the battery-backed RAM in `path`. The RAM is loaded from the file if it exists,
+    /// then written back every second if it changed, and on [`Emulator::flush_save`].
+    /// Does nothing for cartridges without battery-backed memory.
+    pub fn attach_save_file(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+        let path = path.into();
+        let Some(battery_ram) = self.cpu.bus().battery_ram() else {
+            return Ok(());
+        };
+
+        let saved = match fs::read(&path) {
+            Ok(data) => {
+                let data: Vec<_> = data.into_iter().map(Byte::new).collect();
+                if data.len() != battery_ram.len() {
+                    warn!(
+                        "Save file `{}` has {} bytes, expected {}",
+                        path.display(),
+                        data.len(),
+                        battery_ram.len()
+                    );
+                }
+                self.cpu.bus_mut().load_battery_ram(&data);
+                info!("Loaded save file `{}`", path.display());
+                self.cpu.bus().battery_ram().unwrap_or_default()
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => battery_ram,
+            Err(error) => return Err(error.into()),
+        };
+
+        self.save_file = Some(SaveFile {
+            path,
+            saved,
+            frames_since_flush: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Write the battery-backed RAM to the save file if it changed since the last flush.
+    /// Frontends should call this before exiting.
+    pub fn flush_save(&mut self) -> Result<()> {
+        let (Some(save_file), Some(battery_ram)) =
+            (self.save_file.as_mut(), self.cpu.bus().battery_ram())
+        else {
+            return Ok(());
+        };
+        save_file.frames_since_flush = 0;
+        if save_file.saved == battery_ram {
+            return Ok(());
+        }
+
+        // Write to a temporary file first, so a crash mid-write doesn't corrupt the save
+        let data: Vec<_> = battery_ram.iter().map(|byte| byte.value()).collect();
+        if let Some(dir) = save_file.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp_path = save_file.path.with_extension("sav.tmp");
+        fs::write(&temp_path, data)?;
+        fs::rename(&temp_path, &save_file.path)?;
+        save_file.saved = battery_ram;
+
+        Ok(())
     }
 
     /// Advances emulation until one frame is complete.
     /// Returns `Ok(true)` to continue, `Ok(false)` to quit.
     pub fn step_frame(&mut self) -> Result<bool> {
         loop {
+            if let Some(tracer) = &mut self.tracer
+                && self.cpu.halted_at().is_none()
+            {
+                tracer.log(&self.cpu)?;
+            }
             self.cpu.step()?;
 
+            if !self.halt_reported
+                && let Some(address) = self.cpu.halted_at()
+            {
+                self.halt_reported = true;
+                self.frontend.cpu_halted(address);
+            }
+
             if self.cpu.bus().is_frame_ready() {
                 let samples = self.cpu.bus_mut().drain_audio_samples();
                 let mut renderer = Renderer::new(
@@ -47,12 +206,173 @@ where
                 let should_continue = self
                     .frontend
                     .handle_input(self.cpu.bus_mut().joypad_mut())?;
+                if self.frontend.take_reset_request() {
+                    self.reset()?;
+                }
 
                 self.frontend.frame_limit();
                 self.cpu.bus_mut().clear_frame_ready();
+                self.flush_save_periodically();
 
                 return Ok(should_continue);
             }
         }
     }
+
+    fn flush_save_periodically(&mut self) {
+        let Some(save_file) = self.save_file.as_mut() else {
+            return;
+        };
+        save_file.frames_since_flush += 1;
+        if save_file.frames_since_flush >= SAVE_INTERVAL_FRAMES
+            && let Err(error) = self.flush_save()
+        {
+            warn!("Failed to write save file: {error:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mappers::Nrom128;
+    use crate::cartridge::{MirroringType, PRG_ROM_BANK_SIZE};
+    use crate::input::joypad::Joypad;
+    use crate::{Address, Memory};
+
+    struct NullFrontend;
+
+    impl Frontend for NullFrontend {
+        fn render_frame(&mut self, _: &Frame) -> Result<()> {
+            Ok(())
+        }
+        fn handle_input(&mut self, _: &mut Joypad) -> Result<bool> {
+            Ok(true)
+        }
+        fn frame_limit(&mut self) {}
+    }
+
+    #[derive(Default)]
+    struct RecordingFrontend {
+        frames: usize,
+        halted_at: Vec<Address>,
+        reset_requested: bool,
+    }
+
+    impl Frontend for RecordingFrontend {
+        fn render_frame(&mut self, _: &Frame) -> Result<()> {
+            self.frames += 1;
+            Ok(())
+        }
+        fn handle_input(&mut self, _: &mut Joypad) -> Result<bool> {
+            Ok(true)
+        }
+        fn frame_limit(&mut self) {}
+        fn cpu_halted(&mut self, address: Address) {
+            self.halted_at.push(address);
+        }
+        fn take_reset_request(&mut self) -> bool {
+            std::mem::take(&mut self.reset_requested)
+        }
+    }
+
+    fn battery_backed_emulator() -> Emulator<NullFrontend> {
+        let mut rom = Rom::new(
+            vec![Byte::default(); PRG_ROM_BANK_SIZE],
+            vec![],
+            Box::new(Nrom128::default()),
+            MirroringType::Horizontal,
+        );
+        rom.header.has_battery = true;
+        rom.header.prg_nvram_size = 0x2000;
+
+        Emulator::new(NullFrontend, rom).unwrap()
+    }
+
+    #[test]
+    fn save_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("sabi-nes-{}.sav", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut emulator = battery_backed_emulator();
+        emulator.attach_save_file(&path).unwrap();
+        emulator.flush_save().unwrap();
+        assert!(!path.exists(), "unchanged RAM shouldn't be written");
+
+        let bus = emulator.cpu.bus_mut();
+        bus.write_byte(Address::new(0x6000), Byte::new(0x42));
+        emulator.flush_save().unwrap();
+
+        let mut emulator = battery_backed_emulator();
+        emulator.attach_save_file(&path).unwrap();
+        let value = emulator.cpu.bus_mut().read_byte(Address::new(0x6000));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(value, 0x42);
+    }
+
+    #[test]
+    fn save_file_directory_is_created() {
+        let dir = std::env::temp_dir().join(format!("sabi-nes-saves-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("game.sav");
+
+        let mut emulator = battery_backed_emulator();
+        emulator.attach_save_file(&path).unwrap();
+        let bus = emulator.cpu.bus_mut();
+        bus.write_byte(Address::new(0x6000), Byte::new(0x42));
+        emulator.flush_save().unwrap();
+        let saved = fs::read(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(saved.len(), 0x2000);
+        assert_eq!(saved[0], 0x42);
+    }
+
+    fn jammed_emulator() -> Emulator<RecordingFrontend> {
+        // JAM at $8000, which the reset vector points to
+        let mut prg_rom = vec![Byte::default(); PRG_ROM_BANK_SIZE];
+        prg_rom[0] = Byte::new(0x02);
+        prg_rom[0x3ffd] = Byte::new(0x80);
+        let rom = Rom::new(
+            prg_rom,
+            vec![],
+            Box::new(Nrom128::default()),
+            MirroringType::Horizontal,
+        );
+
+        Emulator::new(RecordingFrontend::default(), rom).unwrap()
+    }
+
+    #[test]
+    fn jam_halts_cpu_but_frames_keep_coming() {
+        let mut emulator = jammed_emulator();
+
+        for _ in 0..3 {
+            assert!(emulator.step_frame().unwrap());
+        }
+
+        assert_eq!(emulator.cpu_halted_at(), Some(Address::new(0x8000)));
+        assert_eq!(emulator.frontend.frames, 3);
+        assert_eq!(emulator.frontend.halted_at, vec![Address::new(0x8000)]);
+
+        emulator.reset().unwrap();
+        assert_eq!(emulator.cpu_halted_at(), None);
+        emulator.step_frame().unwrap();
+        assert_eq!(emulator.frontend.halted_at.len(), 2);
+    }
+
+    #[test]
+    fn frontend_reset_request_resets() {
+        let mut emulator = jammed_emulator();
+        emulator.step_frame().unwrap();
+        assert_eq!(emulator.frontend.halted_at.len(), 1);
+
+        emulator.frontend.reset_requested = true;
+        emulator.step_frame().unwrap();
+        assert!(!emulator.frontend.reset_requested);
+        emulator.step_frame().unwrap();
+        // The JAM at the reset vector halts the CPU again
+        assert_eq!(emulator.frontend.halted_at.len(), 2);
+    }
 }
@@ -1,6 +1,6 @@
-use crate::Result;
 use crate::input::joypad::Joypad;
 use crate::render::Frame;
+use crate::{Address, Result};
 
 /// Trait for emulator frontends (rendering, input, timing, audio)
 pub trait Frontend {
@@ -17,4 +17,13 @@ pub trait Frontend {
     /// Push audio samples to the output device.
     /// Default implementation discards samples (no audio).
     fn queue_audio(&mut self, _samples: &[f32]) {}
+
+    /// Called once when a JAM opcode at `address` halts the CPU.
+    /// Emulation keeps producing frames and audio until the next reset.
+    fn cpu_halted(&mut self, _address: Address) {}
+
+    /// Whether the reset button was pressed since the last call
+    fn take_reset_request(&mut self) -> bool {
+        false
+    }
 }
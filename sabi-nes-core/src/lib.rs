@@ -2,17 +2,19 @@ pub mod apu;
 pub mod bus;
 pub mod cartridge;
 pub mod cpu;
+pub mod disasm;
 pub mod emulator;
 pub mod frontend;
 pub mod input;
 pub mod ppu;
 mod primitives;
 pub mod render;
+pub mod trace;
 mod utils;
 
 pub use anyhow::{Error, Result};
 pub use bus::Bus;
 pub use cartridge::Rom;
-pub use cpu::{Cpu, Memory};
+pub use cpu::{Clock, Cpu, FlatMemory, Memory};
 pub use emulator::Emulator;
 pub use primitives::{Address, Byte, Word};
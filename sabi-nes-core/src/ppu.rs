@@ -5,16 +5,20 @@ mod registers;
 pub use nmi_status::NmiStatus;
 pub use registers::{SpriteData, SpriteSize};
 
-use crate::cartridge::MirroringType;
 use crate::cartridge::mappers::Mapper;
+use crate::cartridge::{MirroringType, NametableSource};
 use crate::ppu::open_bus::OpenBus;
 use crate::ppu::registers::PpuRegisters;
 use crate::utils::MirroredAddress;
 use crate::{Address, Byte};
 use log::error;
 
-const VRAM_SIZE: usize = 2048;
+const NAMETABLE_SIZE: usize = 1024;
+// 2KiB of internal VRAM, plus 2KiB of extra RAM found on four-screen boards
+const VRAM_SIZE: usize = 4 * NAMETABLE_SIZE;
 const PALETTE_TABLE_SIZE: usize = 64;
+/// First scanline of vertical blank, where a frame is complete
+pub const VBLANK_SCANLINE: usize = 241;
 const MIRRORS: [Address; 4] = [
     Address::new(0x3f10),
     Address::new(0x3f14),
@@ -22,11 +26,19 @@ const MIRRORS: [Address; 4] = [
     Address::new(0x3f1c),
 ];
 
+/// Where the PPU is in the frame
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PpuPosition {
+    pub scanline: usize,
+    pub dot: usize,
+}
+
 #[derive(Debug)]
 pub struct Ppu {
     /// Internal memory to keep palette tables used by the screen
     pub palette_table: [Byte; PALETTE_TABLE_SIZE],
-    /// 2KiB of space to hold background information
+    /// Space to hold background information. Only the first 2KiB are used,
+    /// unless the cartridge uses four-screen mirroring.
     pub vram: [Byte; VRAM_SIZE],
 
     /// Mirroring type
@@ -37,6 +49,8 @@ pub struct Ppu {
 
     pub scanline: usize,
     pub cycles: usize,
+    /// Frames completed since power-on
+    frame: u64,
     pub nmi_status: NmiStatus,
 
     internal_data_buffer: Byte,
@@ -56,6 +70,14 @@ pub struct Ppu {
     /// Per-scanline snapshot of (scroll_x, scroll_y, nametable_address) recorded
     /// at the end of each visible scanline. Index = scanline number (0–239).
     scanline_scroll: [(Byte, Byte, Address); 240],
+
+    /// Whether the mapper wants to observe every PPU address bus access
+    /// (see [`crate::cartridge::mappers::MapperHooks::PPU_ADDRESS`]).
+    notify_mapper_fetches: bool,
+
+    /// Set after power-on and reset, until the end of the next vblank. Writes to
+    /// PPUCTRL, PPUMASK, PPUSCROLL and PPUADDR are ignored meanwhile.
+    warming_up: bool,
 }
 
 impl Ppu {
@@ -67,15 +89,64 @@ impl Ppu {
             registers: PpuRegisters::default(),
             cycles: 0,
             scanline: 0,
+            frame: 0,
             nmi_status: NmiStatus::Inactive,
             internal_data_buffer: Byte::default(),
             bg_shift_regs_loaded: false,
             open_bus: OpenBus::new(),
             total_cycles: 0,
             scanline_scroll: [(Byte::new(0), Byte::new(0), Address::new(0x2000)); 240],
+            notify_mapper_fetches: false,
+            warming_up: false,
         }
     }
 
+    /// Power-on state: registers cleared, rendering from the top of the frame, and the
+    /// warm-up period before register writes take effect.
+    pub fn power_on(&mut self) {
+        self.registers = PpuRegisters::default();
+        self.scanline = 0;
+        self.cycles = 0;
+        self.frame = 0;
+        self.nmi_status = NmiStatus::Inactive;
+        self.internal_data_buffer = Byte::default();
+        self.bg_shift_regs_loaded = false;
+        self.open_bus = OpenBus::new();
+        self.scanline_scroll = [(Byte::new(0), Byte::new(0), Address::new(0x2000)); 240];
+        self.warming_up = true;
+    }
+
+    /// Reset button: the PPU keeps rendering where it was, but its control registers
+    /// and the PPUDATA read buffer are cleared, and it warms up again.
+    pub fn reset(&mut self) {
+        self.registers.reset();
+        self.nmi_status = NmiStatus::Inactive;
+        self.internal_data_buffer = Byte::default();
+        self.warming_up = true;
+    }
+
+    /// Frames completed since power-on
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn position(&self) -> PpuPosition {
+        PpuPosition {
+            scanline: self.scanline,
+            dot: self.cycles,
+        }
+    }
+
+    /// Whether writes to PPUCTRL, PPUMASK, PPUSCROLL and PPUADDR are still being ignored
+    pub fn is_warming_up(&self) -> bool {
+        self.warming_up
+    }
+
+    /// Enable or disable reporting PPU address bus accesses to the mapper.
+    pub fn notify_mapper_fetches(&mut self, enabled: bool) {
+        self.notify_mapper_fetches = enabled;
+    }
+
     /// Returns the PPU open bus value, or 0 if it has fully decayed.
     /// Real hardware capacitors discharge over ~600 ms; we model the full
     /// byte as decayed after roughly one second of PPU cycles.
@@ -88,13 +159,17 @@ impl Ppu {
         self.open_bus.write(value, self.total_cycles);
     }
 
-    pub fn tick(&mut self, cycles: usize, mapper: &dyn Mapper) -> NmiStatus {
+    pub fn tick(&mut self, cycles: usize, mapper: &mut dyn Mapper) -> NmiStatus {
+        if self.notify_mapper_fetches && self.registers.is_rendering_active() {
+            self.report_fetches(cycles, mapper);
+        }
+
         self.cycles += cycles;
         self.total_cycles += cycles;
 
         // Sprite zero hit fires at the specific PPU cycle within the scanline (X+1),
         // not at the end of the scanline, so we check continuously here.
-        if !self.registers.is_sprite_zero_hit_set() && self.is_sprite_zero_hit(mapper) {
+        if !self.registers.is_sprite_zero_hit_set() && self.is_sprite_zero_hit(&*mapper) {
             self.registers.set_sprite_zero_hit();
         }
 
@@ -130,7 +205,7 @@ impl Ppu {
                 self.registers.reset_oam_address();
             }
 
-            if self.scanline == 241 {
+            if self.scanline == VBLANK_SCANLINE {
                 self.registers.set_vblank().reset_sprite_overflow();
                 if self.registers.is_generating_nmi() {
                     self.nmi_status = NmiStatus::Active;
@@ -139,6 +214,8 @@ impl Ppu {
 
             if self.scanline >= 262 {
                 self.scanline = 0;
+                self.frame += 1;
+                self.warming_up = false;
                 self.nmi_status = NmiStatus::Inactive;
                 self.registers
                     .reset_vblank()
@@ -170,10 +247,16 @@ impl Ppu {
     }
 
     pub fn write_to_addr_register(&mut self, value: Byte) {
+        if self.warming_up {
+            return;
+        }
         self.registers.write_address(value);
     }
 
     pub fn write_to_control_register(&mut self, value: Byte) {
+        if self.warming_up {
+            return;
+        }
         let before = self.registers.is_generating_nmi();
         self.registers.write_control(value);
         let after = self.registers.is_generating_nmi();
@@ -184,6 +267,9 @@ impl Ppu {
     }
 
     pub fn write_to_mask_register(&mut self, value: Byte) {
+        if self.warming_up {
+            return;
+        }
         self.registers.write_mask(value);
     }
 
@@ -195,11 +281,10 @@ impl Ppu {
         self.registers.write_oam_data(value);
     }
 
-    pub fn write_to_oam_dma(&mut self, buffer: &[Byte; 256]) {
-        self.registers.write_oam_dma(buffer);
-    }
-
     pub fn write_to_scroll_register(&mut self, value: Byte) {
+        if self.warming_up {
+            return;
+        }
         self.registers.write_scroll(value);
     }
 
@@ -209,14 +294,16 @@ impl Ppu {
 
     pub fn write(&mut self, value: Byte, mapper: &mut dyn Mapper) {
         let addr = self.registers.read_address();
+        if self.notify_mapper_fetches {
+            mapper.on_ppu_address(addr);
+        }
 
         match addr.value() {
             0x0000..=0x1fff => {
                 mapper.write_chr(addr, value);
             }
             0x2000..=0x2fff => {
-                let mirrorred = self.mirror_vram_addr(addr);
-                self.vram[mirrorred.as_usize()] = value;
+                self.write_nametable(addr, value, mapper);
             }
             0x3000..=0x3eff => {
                 // Should not happen, so at least log an error if any niche
@@ -243,9 +330,12 @@ impl Ppu {
         self.increment_vram_address();
     }
 
-    pub fn read(&mut self, mapper: &dyn Mapper) -> Byte {
+    pub fn read(&mut self, mapper: &mut dyn Mapper) -> Byte {
         let address = self.registers.read_address();
         self.increment_vram_address();
+        if self.notify_mapper_fetches {
+            mapper.on_ppu_address(address);
+        }
 
         match address.value() {
             0x0000..=0x1fff => {
@@ -256,8 +346,7 @@ impl Ppu {
             }
             0x2000..=0x2fff => {
                 let result = self.internal_data_buffer;
-                let mirrored = self.mirror_vram_addr(address);
-                self.internal_data_buffer = self.vram[mirrored.as_usize()];
+                self.internal_data_buffer = self.read_nametable(address, mapper);
 
                 result
             }
@@ -268,8 +357,7 @@ impl Ppu {
             0x3000..=0x3eff => {
                 let address = address - 0x1000;
                 let result = self.internal_data_buffer;
-                let mirrored = self.mirror_vram_addr(address);
-                self.internal_data_buffer = self.vram[mirrored.as_usize()];
+                self.internal_data_buffer = self.read_nametable(address, mapper);
 
                 result
             }
@@ -284,8 +372,7 @@ impl Ppu {
                 // buffer is loaded with nametable data from the mirrored address
                 // at $2F00–$2FFF (addr - $1000).
                 let nametable_addr = address - 0x1000;
-                let mirrored = self.mirror_vram_addr(nametable_addr);
-                self.internal_data_buffer = self.vram[mirrored.as_usize()];
+                self.internal_data_buffer = self.read_nametable(nametable_addr, mapper);
 
                 let offset = ((address - 0x3f00) & 0x1F).as_usize();
                 // Palette RAM is 6-bit; upper 2 bits come from the PPU open bus.
@@ -305,18 +392,147 @@ impl Ppu {
         }
     }
 
-    pub fn mirror_vram_addr(&self, addr: Address) -> Address {
-        let mirrored_vram_addr = addr.mirror_ppu_addr();
-        let vram_index = mirrored_vram_addr - 0x2000;
-        let name_table = vram_index / 0x0400;
+    /// Memory backing the logical nametable `index` (0-3). The mapper gets to decide first,
+    /// otherwise the mirroring from the ROM header is used.
+    pub fn nametable_source(&self, index: usize, mapper: &dyn Mapper) -> NametableSource {
+        mapper
+            .nametable_source(index)
+            .unwrap_or_else(|| self.mirroring.nametable_source(index))
+    }
+
+    /// Contents of the logical nametable `index` (0-3).
+    /// Both rendering and $2007 accesses go through here, so they always agree on the mapping.
+    pub fn nametable<'a>(&'a self, index: usize, mapper: &'a dyn Mapper) -> &'a [Byte] {
+        match self.nametable_source(index, mapper) {
+            NametableSource::Vram(page) => &self.vram[page * NAMETABLE_SIZE..][..NAMETABLE_SIZE],
+            NametableSource::Cartridge(page) => {
+                mapper.nametable(page).unwrap_or(&UNMAPPED_NAMETABLE)
+            }
+        }
+    }
+
+    /// Read a byte from the nametable area ($2000-$2FFF, mirrored up to $3EFF)
+    pub fn read_nametable(&self, address: Address, mapper: &dyn Mapper) -> Byte {
+        let (index, offset) = nametable_index(address);
+
+        self.nametable(index, mapper)
+            .get(offset)
+            .copied()
+            .unwrap_or_default()
+    }
 
-        let offset = match (self.mirroring, name_table.value()) {
-            (MirroringType::Vertical, 2 | 3) | (MirroringType::Horizontal, 3) => 0x800,
-            (MirroringType::Horizontal, 1 | 2) => 0x400,
-            _ => 0x000,
+    fn write_nametable(&mut self, address: Address, value: Byte, mapper: &mut dyn Mapper) {
+        let (index, offset) = nametable_index(address);
+        let target = match self.nametable_source(index, mapper) {
+            NametableSource::Vram(page) => self.vram.get_mut(page * NAMETABLE_SIZE + offset),
+            // Writes to nametable ROM are ignored
+            NametableSource::Cartridge(page) => mapper
+                .nametable_mut(page)
+                .and_then(|nametable| nametable.get_mut(offset)),
         };
 
-        vram_index - offset
+        if let Some(byte) = target {
+            *byte = value;
+        }
+    }
+
+    /// Report the addresses the PPU fetches during the next `cycles` dots to the mapper.
+    fn report_fetches(&self, cycles: usize, mapper: &mut dyn Mapper) {
+        for dot in self.cycles..self.cycles + cycles {
+            let (scanline, dot) = match dot {
+                0..341 => (self.scanline, dot),
+                _ => ((self.scanline + 1) % 262, dot - 341),
+            };
+            if let Some(address) = self.fetch_address(scanline, dot, &*mapper) {
+                mapper.on_ppu_address(address);
+            }
+        }
+    }
+
+    /// Address put on the PPU bus by the rendering pipeline at the given dot, if a
+    /// fetch starts there. Each fetch takes two dots; the address is reported on the first.
+    ///
+    /// ```text
+    /// dots   1-256: NT, AT, BG low, BG high (tiles for the current scanline)
+    /// dots 257-320: NT, NT, sprite low, sprite high (garbage NT fetches)
+    /// dots 321-336: NT, AT, BG low, BG high (first two tiles of the next scanline)
+    /// dots 337-340: NT, NT (unused fetches)
+    /// ```
+    fn fetch_address(&self, scanline: usize, dot: usize, mapper: &dyn Mapper) -> Option<Address> {
+        if scanline >= 240 && scanline != 261 {
+            return None;
+        }
+
+        let phase = (dot.checked_sub(1)? % 8) / 2;
+        let is_first_dot = (dot - 1).is_multiple_of(2);
+        if !is_first_dot {
+            return None;
+        }
+
+        // Pre-render line prefetches the tiles of scanline 0
+        let next_line = if scanline == 261 { 0 } else { scanline + 1 };
+        match dot {
+            1..=256 => {
+                let tile_x = ((dot - 1) / 8 + 2) * 8;
+                Some(self.background_fetch_address(tile_x, scanline, phase, mapper))
+            }
+            257..=320 => {
+                let tile_base = match self.registers.sprite_size() {
+                    // Unused sprite slots fetch tile $FF
+                    SpriteSize::Small => self.registers.read_sprite_pattern_address() + 0x0ff0,
+                    SpriteSize::Large => Address::new(0x1ff0),
+                };
+                Some(match phase {
+                    0 | 1 => self.background_fetch_address(0, next_line, 0, mapper),
+                    2 => tile_base,
+                    _ => tile_base + 8,
+                })
+            }
+            321..=336 => {
+                let tile_x = ((dot - 321) / 8) * 8;
+                Some(self.background_fetch_address(tile_x, next_line, phase, mapper))
+            }
+            337 | 339 => Some(self.background_fetch_address(0, next_line, 0, mapper)),
+            _ => None,
+        }
+    }
+
+    /// Address of one of the four background fetches (0 = nametable, 1 = attribute,
+    /// 2 = pattern low, 3 = pattern high) for the tile at screen position (x, y).
+    fn background_fetch_address(
+        &self,
+        x: usize,
+        y: usize,
+        phase: usize,
+        mapper: &dyn Mapper,
+    ) -> Address {
+        let base_nt_id = (self.registers.read_name_table_address() - 0x2000).as_usize() / 0x400;
+        let scroll_x = self.registers.read_scroll_x().as_usize() + (base_nt_id % 2) * 256;
+        let scroll_y = self.registers.read_scroll_y().as_usize() + (base_nt_id / 2) * 240;
+        let eff_x = (x + scroll_x) % 512;
+        let eff_y = (y + scroll_y) % 480;
+
+        let nt_id = (eff_y / 240) * 2 + (eff_x / 256);
+        let nt_base_addr = 0x2000 + nt_id * 0x400;
+        let local_x = eff_x % 256;
+        let local_y = eff_y % 240;
+
+        let address = match phase {
+            0 => nt_base_addr + (local_y / 8) * 32 + local_x / 8,
+            1 => nt_base_addr + 0x3c0 + (local_y / 32) * 8 + local_x / 32,
+            _ => {
+                let nt_addr =
+                    Address::new((nt_base_addr + (local_y / 8) * 32 + local_x / 8) as u16);
+                let tile_index = self.read_nametable(nt_addr, mapper).as_usize();
+                let bg_pattern_base = self.registers.background_pattern_address().as_usize();
+                let plane_offset = if phase == 3 { 8 } else { 0 };
+
+                bg_pattern_base + tile_index * 16 + local_y % 8 + plane_offset
+            }
+        };
+
+        // All fetch addresses are below $3000, so the conversion is lossless
+        Address::new(address as u16)
     }
 
     fn is_sprite_zero_hit(&self, mapper: &dyn Mapper) -> bool {
@@ -425,8 +641,7 @@ impl Ppu {
         let tile_idx = (local_y / 8) * 32 + (local_x / 8);
 
         let nt_addr = Address::new(nt_base_addr + tile_idx as u16);
-        let mirrored = self.mirror_vram_addr(nt_addr);
-        let tile_index = self.vram[mirrored.as_usize()].as_usize();
+        let tile_index = self.read_nametable(nt_addr, mapper).as_usize();
 
         let bg_pattern_base = self.registers.background_pattern_address().value() as usize;
         let tile_base = bg_pattern_base + tile_index * 16;
@@ -464,6 +679,15 @@ impl Ppu {
     }
 }
 
+static UNMAPPED_NAMETABLE: [Byte; NAMETABLE_SIZE] = [Byte::new(0); NAMETABLE_SIZE];
+
+/// Splits a nametable area address into the logical nametable index (0-3) and the offset within it.
+fn nametable_index(address: Address) -> (usize, usize) {
+    let vram_index = (address.mirror_ppu_addr() - 0x2000).as_usize();
+
+    (vram_index / NAMETABLE_SIZE, vram_index % NAMETABLE_SIZE)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -483,6 +707,60 @@ mod tests {
         fn write_chr(&mut self, _: Address, _: Byte) {}
     }
 
+    /// Counts filtered rising edges of PPU A12, the way MMC3 clocks its scanline counter:
+    /// A12 has to stay low for a while before a rise counts, which hides the
+    /// back-to-back sprite fetches at dots 257-320.
+    #[derive(Default)]
+    struct A12Mapper {
+        low_fetches: usize,
+        rising_edges: usize,
+    }
+
+    impl Mapper for A12Mapper {
+        fn map_address(&self, _: Address) -> usize {
+            0
+        }
+        fn write(&mut self, _: Address, _: Byte) {}
+        fn load_chr(&mut self, _: Vec<Byte>) {}
+        fn read_chr(&self, _: Address) -> Byte {
+            Byte::default()
+        }
+        fn write_chr(&mut self, _: Address, _: Byte) {}
+        fn on_ppu_address(&mut self, address: Address) {
+            if address & 0x1000 == 0 {
+                self.low_fetches += 1;
+                return;
+            }
+            if self.low_fetches >= 8 {
+                self.rising_edges += 1;
+            }
+            self.low_fetches = 0;
+        }
+    }
+
+    /// Mapper providing a 1KiB nametable ROM in place of the $2800 nametable.
+    struct NametableRomMapper {
+        rom: Vec<Byte>,
+    }
+
+    impl Mapper for NametableRomMapper {
+        fn map_address(&self, _: Address) -> usize {
+            0
+        }
+        fn write(&mut self, _: Address, _: Byte) {}
+        fn load_chr(&mut self, _: Vec<Byte>) {}
+        fn read_chr(&self, _: Address) -> Byte {
+            Byte::default()
+        }
+        fn write_chr(&mut self, _: Address, _: Byte) {}
+        fn nametable_source(&self, index: usize) -> Option<NametableSource> {
+            (index == 2).then_some(NametableSource::Cartridge(0))
+        }
+        fn nametable(&self, page: usize) -> Option<&[Byte]> {
+            (page == 0).then_some(self.rom.as_slice())
+        }
+    }
+
     impl Ppu {
         fn test_ppu() -> Self {
             Self::new(MirroringType::Horizontal)
@@ -508,10 +786,10 @@ mod tests {
         ppu.write_to_addr_register(0x23.into());
         ppu.write_to_addr_register(0x05.into());
 
-        ppu.read(&NullMapper);
+        ppu.read(&mut NullMapper);
 
         assert_eq!(ppu.registers.read_address(), 0x2306);
-        assert_eq!(ppu.read(&NullMapper), 0x66);
+        assert_eq!(ppu.read(&mut NullMapper), 0x66);
     }
 
     #[test]
@@ -526,11 +804,11 @@ mod tests {
         ppu.registers.write_address(0x21.into());
         ppu.registers.write_address(0xff.into());
 
-        ppu.read(&NullMapper);
+        ppu.read(&mut NullMapper);
 
-        assert_eq!(ppu.read(&NullMapper), 0x66);
-        assert_eq!(ppu.read(&NullMapper), 0x77);
-        assert_eq!(ppu.read(&NullMapper), 0x88);
+        assert_eq!(ppu.read(&mut NullMapper), 0x66);
+        assert_eq!(ppu.read(&mut NullMapper), 0x77);
+        assert_eq!(ppu.read(&mut NullMapper), 0x88);
     }
 
     #[test]
@@ -550,14 +828,14 @@ mod tests {
         ppu.registers.write_address(0x20.into());
         ppu.registers.write_address(0x05.into());
 
-        ppu.read(&NullMapper);
-        assert_eq!(ppu.read(&NullMapper), 0x66);
+        ppu.read(&mut NullMapper);
+        assert_eq!(ppu.read(&mut NullMapper), 0x66);
 
         ppu.registers.write_address(0x2c.into());
         ppu.registers.write_address(0x05.into());
 
-        ppu.read(&NullMapper);
-        assert_eq!(ppu.read(&NullMapper), 0x77);
+        ppu.read(&mut NullMapper);
+        assert_eq!(ppu.read(&mut NullMapper), 0x77);
     }
 
     #[test]
@@ -578,14 +856,54 @@ mod tests {
         ppu.registers.write_address(0x28.into());
         ppu.registers.write_address(0x05.into());
 
-        ppu.read(&NullMapper);
-        assert_eq!(ppu.read(&NullMapper), 0x66);
+        ppu.read(&mut NullMapper);
+        assert_eq!(ppu.read(&mut NullMapper), 0x66);
 
         ppu.registers.write_address(0x24.into());
         ppu.registers.write_address(0x05.into());
 
-        ppu.read(&NullMapper);
-        assert_eq!(ppu.read(&NullMapper), 0x77);
+        ppu.read(&mut NullMapper);
+        assert_eq!(ppu.read(&mut NullMapper), 0x77);
+    }
+
+    #[test]
+    fn vram_four_screen() {
+        let mut ppu = Ppu::new(MirroringType::FourScreen);
+
+        for (high, value) in [(0x20, 0x11), (0x24, 0x22), (0x28, 0x33), (0x2c, 0x44)] {
+            ppu.registers.write_address(Byte::new(high));
+            ppu.registers.write_address(0x05.into());
+            ppu.write(Byte::new(value), &mut NullMapper);
+        }
+
+        for (index, value) in [0x11, 0x22, 0x33, 0x44].into_iter().enumerate() {
+            assert_eq!(ppu.nametable(index, &NullMapper)[0x05], value);
+        }
+        assert_eq!(ppu.vram[0x0c05], 0x44);
+    }
+
+    #[test]
+    fn cartridge_nametable_rom() {
+        let mut ppu = Ppu::test_ppu();
+        let mut mapper = NametableRomMapper {
+            rom: vec![Byte::new(0xab); 0x400],
+        };
+
+        ppu.registers.write_address(0x28.into());
+        ppu.registers.write_address(0x05.into());
+        // Nametable ROM ignores writes
+        ppu.write(0x66.into(), &mut mapper);
+
+        ppu.registers.write_address(0x28.into());
+        ppu.registers.write_address(0x05.into());
+        ppu.read(&mut mapper);
+        assert_eq!(ppu.read(&mut mapper), 0xab);
+        assert_eq!(ppu.nametable(2, &mapper)[0x05], 0xab);
+        // Horizontal mirroring from the header still applies to the other nametables
+        assert_eq!(
+            ppu.nametable(3, &mapper).as_ptr(),
+            ppu.vram[0x400..].as_ptr()
+        );
     }
 
     #[test]
@@ -597,16 +915,16 @@ mod tests {
         ppu.registers.write_address(0x23.into());
         ppu.registers.write_address(0x05.into());
 
-        ppu.read(&NullMapper);
-        assert_ne!(ppu.read(&NullMapper), 0x66);
+        ppu.read(&mut NullMapper);
+        assert_ne!(ppu.read(&mut NullMapper), 0x66);
 
         ppu.read_status_register();
 
         ppu.registers.write_address(0x23.into());
         ppu.registers.write_address(0x05.into());
 
-        ppu.read(&NullMapper);
-        assert_eq!(ppu.read(&NullMapper), 0x66);
+        ppu.read(&mut NullMapper);
+        assert_eq!(ppu.read(&mut NullMapper), 0x66);
     }
 
     #[test]
@@ -618,8 +936,8 @@ mod tests {
         ppu.registers.write_address(0x63.into());
         ppu.registers.write_address(0x05.into());
 
-        ppu.read(&NullMapper);
-        assert_eq!(ppu.read(&NullMapper), 0x66);
+        ppu.read(&mut NullMapper);
+        assert_eq!(ppu.read(&mut NullMapper), 0x66);
     }
 
     #[test]
@@ -650,18 +968,49 @@ mod tests {
     #[test]
     fn scanline_scroll_records_per_scanline() {
         let mut ppu = Ppu::test_ppu();
-        let mapper = NullMapper;
+        let mut mapper = NullMapper;
 
         // Set initial scroll state
         ppu.write_to_scroll_register(Byte::new(10)); // scroll_x = 10
         ppu.write_to_scroll_register(Byte::new(20)); // scroll_y = 20
 
         // Tick through scanline 0 (341 PPU cycles)
-        ppu.tick(341, &mapper);
+        ppu.tick(341, &mut mapper);
 
         let (sx, sy, nt) = ppu.scanline_scroll()[0];
         assert_eq!(sx, Byte::new(10));
         assert_eq!(sy, Byte::new(20));
         assert_eq!(nt, Address::new(0x2000)); // default nametable
     }
+
+    #[test]
+    fn a12_rises_once_per_rendered_scanline() {
+        let mut ppu = Ppu::test_ppu();
+        let mut mapper = A12Mapper::default();
+        ppu.notify_mapper_fetches(true);
+        // Background from $0000, 8x8 sprites from $1000, rendering enabled
+        ppu.write_to_control_register(Byte::new(0b0000_1000));
+        ppu.write_to_mask_register(Byte::new(0b0001_1000));
+
+        for _ in 0..(341 * 262 / 3) {
+            ppu.tick(3, &mut mapper);
+        }
+
+        // 240 visible scanlines + the pre-render scanline
+        assert_eq!(mapper.rising_edges, 241);
+    }
+
+    #[test]
+    fn no_fetches_reported_unless_requested() {
+        let mut ppu = Ppu::test_ppu();
+        let mut mapper = A12Mapper::default();
+        ppu.write_to_control_register(Byte::new(0b0000_1000));
+        ppu.write_to_mask_register(Byte::new(0b0001_1000));
+
+        for _ in 0..(341 * 262 / 3) {
+            ppu.tick(3, &mut mapper);
+        }
+
+        assert_eq!(mapper.rising_edges, 0);
+    }
 }
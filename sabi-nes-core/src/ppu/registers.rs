@@ -26,6 +26,15 @@ pub struct PpuRegisters {
 }
 
 impl PpuRegisters {
+    /// The reset button clears PPUCTRL, PPUMASK, the scroll and the write latch.
+    /// PPUADDR, PPUSTATUS and OAM keep their contents.
+    pub fn reset(&mut self) {
+        self.control = ControlRegister::default();
+        self.mask = MaskRegister::default();
+        self.scroll = ScrollRegister::default();
+        self.address.reset_latch();
+    }
+
     pub fn read_address(&self) -> Address {
         self.address.get()
     }
@@ -84,10 +93,6 @@ impl PpuRegisters {
         self.oam.write(value);
     }
 
-    pub fn write_oam_dma(&mut self, buffer: &[Byte]) {
-        self.oam.write_all(buffer);
-    }
-
     pub fn write_scroll(&mut self, value: Byte) {
         self.scroll.write(value);
     }
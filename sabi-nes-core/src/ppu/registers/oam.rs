@@ -107,10 +107,4 @@ impl Oam {
     pub fn write_address(&mut self, address: Byte) {
         self.address = address;
     }
-
-    pub fn write_all(&mut self, buffer: &[Byte]) {
-        for byte in buffer {
-            self.write(*byte);
-        }
-    }
 }
@@ -3,7 +3,6 @@ mod frame;
 mod palettes;
 mod tile_palette;
 
-use crate::cartridge::MirroringType;
 use crate::cartridge::mappers::Mapper;
 use crate::ppu::{Ppu, SpriteData, SpriteSize};
 use crate::{Address, Byte};
@@ -65,33 +64,14 @@ where
             let scroll_x = scroll_x_byte.as_usize();
             let scroll_y = scroll_y_byte.as_usize();
 
-            // Determine the four nametable quadrants (top-left, top-right, bottom-left, bottom-right).
-            // Vertical mirroring: $2000=$2800, $2400=$2C00 → left/right differ, top/bottom same.
-            // Horizontal mirroring: $2000=$2400, $2800=$2C00 → top/bottom differ, left/right same.
-            let (top_left, top_right, bot_left, bot_right) =
-                match (self.ppu.mirroring, name_table_address.value()) {
-                    (MirroringType::Vertical, 0x2000 | 0x2800) => {
-                        let a = &self.ppu.vram[0..0x0400];
-                        let b = &self.ppu.vram[0x0400..0x0800];
-                        (a, b, a, b)
-                    }
-                    (MirroringType::Vertical, 0x2400 | 0x2c00) => {
-                        let a = &self.ppu.vram[0x0400..0x0800];
-                        let b = &self.ppu.vram[0..0x0400];
-                        (a, b, a, b)
-                    }
-                    (MirroringType::Horizontal, 0x2000 | 0x2400) => {
-                        let a = &self.ppu.vram[0..0x0400];
-                        let b = &self.ppu.vram[0x0400..0x0800];
-                        (a, b, b, b)
-                    }
-                    (MirroringType::Horizontal, 0x2800 | 0x2c00) => {
-                        let a = &self.ppu.vram[0x0400..0x0800];
-                        let b = &self.ppu.vram[0..0x0400];
-                        (a, b, b, b)
-                    }
-                    _ => todo!("Four screen mirroring (used in e.g. Gauntlet"),
-                };
+            // Determine the four nametable quadrants (top-left, top-right, bottom-left, bottom-right)
+            // relative to the base nametable. The PPU resolves mirroring and cartridge-provided
+            // nametables, so this always matches what $2007 sees.
+            let base = (name_table_address - 0x2000).as_usize() / 0x0400;
+            let top_left = self.ppu.nametable(base, self.mapper);
+            let top_right = self.ppu.nametable(base ^ 1, self.mapper);
+            let bot_left = self.ppu.nametable(base ^ 2, self.mapper);
+            let bot_right = self.ppu.nametable(base ^ 3, self.mapper);
 
             let total_y = screen_y + scroll_y;
             // When total_y >= 240 the visible row is in the nametable below the base.
@@ -300,3 +280,70 @@ fn bg_palette(
         ppu.palette_table[palette_start + 2],
     ])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::MirroringType;
+    use crate::cartridge::mappers::Nrom128;
+
+    /// Pixel colour at (x, y) in the frame
+    fn pixel(frame: &Frame, x: usize, y: usize) -> Colour {
+        let base = (y * Frame::WIDTH + x) * 3;
+        let rgb = &frame.pixel_data()[base..base + 3];
+        Colour::new(rgb[0], rgb[1], rgb[2])
+    }
+
+    #[test]
+    fn render_four_screen_background() {
+        let mut ppu = Ppu::new(MirroringType::FourScreen);
+        let mut mapper = Nrom128::default();
+        // Tile n is filled with colour n
+        let mut chr = vec![Byte::default(); 0x2000];
+        for tile in 1..4 {
+            let planes = &mut chr[tile * 16..(tile + 1) * 16];
+            planes[..8].fill(Byte::new(if tile & 1 != 0 { 0xff } else { 0x00 }));
+            planes[8..].fill(Byte::new(if tile & 2 != 0 { 0xff } else { 0x00 }));
+        }
+        mapper.load_chr(chr);
+        let palette = SystemPalette::new();
+        for (index, colour) in [0x0f, 0x16, 0x2a, 0x12].into_iter().enumerate() {
+            ppu.palette_table[index] = Byte::new(colour);
+        }
+
+        // Nametable n is filled with tile n through $2007, the attributes select palette 0
+        for nametable in 0..4u8 {
+            ppu.write_to_addr_register(Byte::new(0x20 + nametable * 4));
+            ppu.write_to_addr_register(Byte::new(0x00));
+            for _ in 0..960 {
+                ppu.write(Byte::new(nametable), &mut mapper);
+            }
+        }
+
+        // Scroll to the middle of nametable 0, so each quadrant of the screen shows
+        // a different nametable
+        ppu.write_to_scroll_register(Byte::new(128));
+        ppu.write_to_scroll_register(Byte::new(120));
+        ppu.write_to_control_register(Byte::new(0x00));
+        ppu.write_to_mask_register(Byte::new(0b0000_1010));
+        for _ in 0..Frame::HEIGHT {
+            ppu.tick(341, &mut mapper);
+        }
+
+        let mut frame = Frame::new();
+        Renderer::new(&ppu, &mapper, &mut frame, &palette).render_frame();
+
+        let quadrants = [(64, 60), (192, 60), (64, 180), (192, 180)];
+        let colours = quadrants.map(|(x, y)| pixel(&frame, x, y));
+        for (nametable, colour) in colours.iter().enumerate() {
+            ppu.write_to_addr_register(Byte::new(0x20 + nametable as u8 * 4));
+            ppu.write_to_addr_register(Byte::new(0x00));
+            ppu.read(&mut mapper);
+            let tile = ppu.read(&mut mapper).as_usize();
+
+            assert_eq!(tile, nametable);
+            assert_eq!(*colour, palette.get(ppu.palette_table[tile].as_usize()));
+            assert!(!colours[..nametable].contains(colour));
+        }
+    }
+}
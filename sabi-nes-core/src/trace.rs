@@ -0,0 +1,289 @@
+//! Execution trace logger, writing one line per instruction in the layout of other emulators'
+//! trace logs so they can be diffed against each other.
+
+use crate::disasm::{Disassembler, IndexRegisters, Syntax, decode_at};
+use crate::{Address, Clock, Cpu};
+use anyhow::{Error, Result, bail};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Layout of the trace lines
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// `C000  4C F5 C5  JMP $C5F5    A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7`
+    #[default]
+    Nestest,
+    /// `c7          A:00 X:00 Y:00 S:FD P:nvUbdIzc  $C000:4C F5 C5  JMP $C5F5`
+    Fceux,
+    /// `C000  JMP $C5F5    A:00 X:00 Y:00 S:FD P:nv--dIzc V:0   H:21  Fr:0 Cycle:7`
+    Mesen,
+}
+
+impl FromStr for TraceFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value {
+            "nestest" => Self::Nestest,
+            "fceux" => Self::Fceux,
+            "mesen" => Self::Mesen,
+            _ => bail!("Unknown trace format `{value}`, expected nestest, fceux or mesen"),
+        })
+    }
+}
+
+/// When instructions are logged. An instruction is logged if every condition that's set holds.
+#[derive(Debug, Default, Clone)]
+pub struct TraceConditions {
+    /// Only log instructions whose address is in this range
+    pub pc_range: Option<RangeInclusive<Address>>,
+    /// Start logging at this frame
+    pub start_frame: Option<u64>,
+    /// Stop logging after this frame
+    pub stop_frame: Option<u64>,
+    /// Start logging the first time this instruction address is reached
+    pub trigger: Option<Address>,
+}
+
+enum Sink {
+    Writer(Box<dyn Write>),
+    /// Only the most recent lines are kept
+    RingBuffer {
+        lines: VecDeque<String>,
+        capacity: usize,
+    },
+}
+
+/// Logs the CPU state before every instruction it's given
+pub struct TraceLogger {
+    format: TraceFormat,
+    conditions: TraceConditions,
+    sink: Sink,
+    disassembler: Disassembler,
+    /// Whether the trigger address was reached
+    triggered: bool,
+}
+
+impl TraceLogger {
+    /// Stream the trace to a new file at `path`
+    pub fn to_file(path: impl AsRef<Path>, format: TraceFormat) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::to_writer(BufWriter::new(file), format))
+    }
+
+    /// Stream the trace to `writer`
+    pub fn to_writer(writer: impl Write + 'static, format: TraceFormat) -> Self {
+        Self::new(Sink::Writer(Box::new(writer)), format)
+    }
+
+    /// Keep the last `capacity` lines in memory, see [`TraceLogger::lines`]
+    pub fn ring_buffer(capacity: usize, format: TraceFormat) -> Self {
+        let sink = Sink::RingBuffer {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+        };
+        Self::new(sink, format)
+    }
+
+    fn new(sink: Sink, format: TraceFormat) -> Self {
+        Self {
+            format,
+            conditions: TraceConditions::default(),
+            sink,
+            disassembler: Disassembler::new(Syntax::Nestest),
+            triggered: true,
+        }
+    }
+
+    pub fn with_conditions(mut self, conditions: TraceConditions) -> Self {
+        self.triggered = conditions.trigger.is_none();
+        self.conditions = conditions;
+        self
+    }
+
+    /// Substitute labels for addresses in the disassembly. Trace lines are in nestest
+    /// syntax whatever the format, so a ca65 disassembler is rejected.
+    pub fn with_disassembler(mut self, disassembler: Disassembler) -> Result<Self> {
+        if disassembler.syntax() != Syntax::Nestest {
+            bail!(
+                "Trace lines need a disassembler in nestest syntax, got {:?}",
+                disassembler.syntax()
+            );
+        }
+        self.disassembler = disassembler;
+
+        Ok(self)
+    }
+
+    /// Lines held in ring buffer mode, oldest first. Empty when streaming.
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        let lines = match &self.sink {
+            Sink::RingBuffer { lines, .. } => Some(lines.iter().map(String::as_str)),
+            Sink::Writer(_) => None,
+        };
+        lines.into_iter().flatten()
+    }
+
+    /// Log the instruction the CPU is about to execute, if the conditions allow
+    pub fn log(&mut self, cpu: &Cpu) -> Result<()> {
+        if !self.should_log(cpu) {
+            return Ok(());
+        }
+
+        let line = format_line(cpu, self.format, &self.disassembler);
+        match &mut self.sink {
+            Sink::Writer(writer) => writeln!(writer, "{line}")?,
+            Sink::RingBuffer { lines, capacity } => {
+                if lines.len() == *capacity {
+                    lines.pop_front();
+                }
+                if *capacity > 0 {
+                    lines.push_back(line);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        if let Sink::Writer(writer) = &mut self.sink {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn should_log(&mut self, cpu: &Cpu) -> bool {
+        let pc = cpu.program_counter;
+        let frame = cpu.bus().frame_count();
+        let conditions = &self.conditions;
+
+        if !self.triggered && conditions.trigger == Some(pc) {
+            self.triggered = true;
+        }
+
+        self.triggered
+            && conditions
+                .pc_range
+                .as_ref()
+                .is_none_or(|range| range.contains(&pc))
+            && conditions.start_frame.is_none_or(|start| frame >= start)
+            && conditions.stop_frame.is_none_or(|stop| frame <= stop)
+    }
+}
+
+impl Drop for TraceLogger {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// The trace line for the instruction the CPU is about to execute. The disassembly columns
+/// come from `disassembler`, which should use [`Syntax::Nestest`] for lines that diff against
+/// other emulators' logs.
+pub fn format_line(cpu: &Cpu, format: TraceFormat, disassembler: &Disassembler) -> String {
+    let instruction = decode_at(cpu, cpu.program_counter);
+    let bytes = instruction
+        .bytes()
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let registers = format!(
+        "A:{:02X} X:{:02X} Y:{:02X}",
+        cpu.accumulator, cpu.register_x, cpu.register_y
+    );
+    let status = cpu.status_register.bits();
+    let stack_pointer = cpu.stack_pointer();
+    let cycles = cpu.bus().cycles();
+    let ppu = cpu.bus().ppu_position();
+
+    match format {
+        TraceFormat::Nestest => {
+            let index_registers = IndexRegisters {
+                x: cpu.register_x,
+                y: cpu.register_y,
+            };
+            let asm = disassembler.format_annotated(&instruction, cpu, index_registers);
+            // The `*` marking unofficial opcodes takes the last column of the bytes
+            let (bytes_width, asm_width) = match instruction.is_unofficial() {
+                true => (9, 33),
+                false => (10, 32),
+            };
+            format!(
+                "{:04X}  {bytes:<bytes_width$}{asm:<asm_width$}{registers} P:{status:02X} \
+                 SP:{stack_pointer} PPU:{:>3},{:>3} CYC:{cycles}",
+                cpu.program_counter, ppu.scanline, ppu.dot
+            )
+        }
+        TraceFormat::Fceux => {
+            let asm = disassembler.format(&instruction);
+            format!(
+                "c{cycles:<11}{registers} S:{stack_pointer} P:{}  ${:04X}:{bytes:<9} {asm}",
+                flag_letters(status, "NVUBDIZC"),
+                cpu.program_counter
+            )
+        }
+        TraceFormat::Mesen => {
+            let asm = disassembler.format(&instruction);
+            format!(
+                "{:04X}  {asm:<32} {registers} S:{stack_pointer} P:{} V:{:<3} H:{:<3} \
+                 Fr:{} Cycle:{cycles}",
+                cpu.program_counter,
+                flag_letters(status, "NV--DIZC"),
+                ppu.scanline,
+                ppu.dot,
+                cpu.bus().frame_count()
+            )
+        }
+    }
+}
+
+/// One letter per status flag from bit 7 down, upper case if set. `-` marks an unnamed bit.
+fn flag_letters(status: u8, letters: &str) -> String {
+    letters
+        .chars()
+        .zip((0..8).rev())
+        .map(|(letter, bit)| match status & (1 << bit) != 0 {
+            true => letter,
+            false => letter.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_letters_show_set_flags_in_upper_case() {
+        assert_eq!(flag_letters(0x24, "NVUBDIZC"), "nvUbdIzc");
+        assert_eq!(flag_letters(0xc3, "NV--DIZC"), "NV--diZC");
+    }
+
+    #[test]
+    fn rejects_ca65_disassembler() {
+        let logger = || TraceLogger::ring_buffer(1, TraceFormat::Nestest);
+        assert!(
+            logger()
+                .with_disassembler(Disassembler::new(Syntax::Ca65))
+                .is_err()
+        );
+        assert!(
+            logger()
+                .with_disassembler(Disassembler::new(Syntax::Nestest))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn parses_formats() {
+        assert_eq!("fceux".parse::<TraceFormat>().unwrap(), TraceFormat::Fceux);
+        assert!("bizhawk".parse::<TraceFormat>().is_err());
+    }
+}
@@ -67,11 +67,14 @@ fn accuracy_coin_tests() {
     let test_cases = load_test_case_data();
 
     let rom = Rom::from_file("tests/test_roms/AccuracyCoin.nes").unwrap();
-    let bus = Bus::new(rom);
-    let mut cpu = Cpu::new(bus);
-    cpu.reset().unwrap();
+    let mut cpu = Cpu::new(Bus::new(rom));
+    cpu.power_on().unwrap();
 
     // Make sure the test ROM is initialised properly and ready to take input.
+    // Initialisation runs with NMI disabled, the menu only reads the joypad once it's enabled.
+    while !cpu.bus().ppu().registers.is_generating_nmi() {
+        cpu.step().unwrap();
+    }
     run_frames(&mut cpu, 5);
 
     // Press Start once to trigger AutomaticallyRunEveryTestInROM.
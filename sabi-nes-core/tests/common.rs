@@ -1,9 +1,8 @@
-use anyhow::anyhow;
 use once_cell::sync::Lazy;
+use sabi_nes_core::Cpu;
 use sabi_nes_core::cartridge::{CHR_ROM_BANK_SIZE, PRG_ROM_BANK_SIZE};
-use sabi_nes_core::cpu::AddressingMode;
-use sabi_nes_core::cpu::opcodes::{OPCODES_MAPPING, Opcode};
-use sabi_nes_core::{Address, Cpu, Memory, Result};
+use sabi_nes_core::disasm::Disassembler;
+use sabi_nes_core::trace::{TraceFormat, format_line};
 
 pub static TEST_ROM: Lazy<Vec<u8>> = Lazy::new(|| {
     let mut rom = vec![];
@@ -21,160 +20,22 @@ pub static TEST_ROM: Lazy<Vec<u8>> = Lazy::new(|| {
     rom
 });
 
-pub fn trace(cpu: &mut Cpu) -> Result<String> {
-    let code = cpu.read_byte(cpu.program_counter);
-    let opcode = OPCODES_MAPPING
-        .get(&code)
-        .ok_or_else(|| anyhow!("Opcode `{code:#x}` not supported"))?;
-    let opcode_hex = opcode_hex_representation(opcode, cpu);
-    let opcode_asm = opcode_asm_representation(opcode, cpu)?;
-
-    // Unofficial opcodes (starting with '*') use a 9-char hex field and 33-char ASM field.
-    // This keeps the register columns aligned despite the extra '*' character.
-    //   Official:   "C5F7  86 00     STX $00 = 00                    A:00 X:00..."
-    //   Unofficial: "C6BD  04 A9    *NOP $A9 = 00                    A:AA X:97..."
-    let (hex_width, asm_width) = if opcode.name.starts_with('*') {
-        (9, 33)
-    } else {
-        (10, 32)
-    };
-
-    Ok(format!(
-        "{:>04X}  {:<hex_width$}{:<asm_width$}A:{:02X} X:{:02X} Y:{:02X} P:{} SP:{}",
-        cpu.program_counter,
-        opcode_hex,
-        opcode_asm,
-        cpu.accumulator,
-        cpu.register_x,
-        cpu.register_y,
-        cpu.status_register,
-        cpu.stack_pointer(),
-    ))
-}
-
-fn opcode_hex_representation(opcode: &Opcode, cpu: &mut Cpu) -> String {
-    match opcode.length() {
-        0 => format!("{:02X}", opcode.code),
-        1 => format!(
-            "{:02X} {:02X}",
-            opcode.code,
-            cpu.read_byte(cpu.program_counter + 1)
-        ),
-        2 => match opcode.addressing_mode {
-            AddressingMode::Implied if opcode.name != "JSR" => {
-                format!("{:02X}", opcode.code)
-            }
-            _ => {
-                format!(
-                    "{:02X} {:02X} {:02X}",
-                    opcode.code,
-                    cpu.read_byte(cpu.program_counter + 1),
-                    cpu.read_byte(cpu.program_counter + 2),
-                )
-            }
-        },
-        _ => unreachable!("Got opcode with more than 2 args"),
+/// nestest log line for the instruction about to run. The expected logs were captured
+/// without the PPU and CYC columns, so they're cut off.
+pub fn trace(cpu: &Cpu) -> String {
+    let mut line = format_line(cpu, TraceFormat::Nestest, &Disassembler::default());
+    if let Some(end) = line.find(" PPU:") {
+        line.truncate(end);
     }
-}
-
-fn opcode_asm_representation(opcode: &Opcode, cpu: &mut Cpu) -> Result<String> {
-    let value = cpu.read_byte(cpu.program_counter + 1);
-    let address = cpu.read_word(cpu.program_counter + 1).as_address();
-    let target_address = cpu.operand_address(opcode, cpu.program_counter + 1)?;
-
-    let opcode_asm_args = match opcode.addressing_mode {
-        AddressingMode::Immediate => {
-            format!("#${:02X}", value)
-        }
-        AddressingMode::ZeroPage => {
-            let stored_value = cpu.peek_byte(value.into());
-            format!("${:02X} = {:02X}", value, stored_value)
-        }
-        AddressingMode::ZeroPageX => {
-            let stored_value = cpu.peek_byte(target_address);
-
-            format!(
-                "${:02X},X @ {:02X} = {:02X}",
-                value, target_address, stored_value
-            )
-        }
-        AddressingMode::ZeroPageY => {
-            let stored_value = cpu.peek_byte(target_address);
-            format!(
-                "${:02X},Y @ {:02X} = {:02X}",
-                value, target_address, stored_value
-            )
-        }
-        AddressingMode::Absolute => {
-            let stored_value = cpu.peek_byte(target_address);
-            match opcode.name {
-                "JMP" | "JSR" => format!("${:04X}", target_address),
-                _ => format!("${:04X} = {:02X}", target_address, stored_value),
-            }
-        }
-        AddressingMode::AbsoluteX => {
-            let incremented = address.wrapping_add(cpu.register_x);
-            let value = cpu.peek_byte(incremented);
-
-            format!("${:04X},X @ {:04X} = {:02X}", address, incremented, value)
-        }
-        AddressingMode::AbsoluteY => {
-            let incremented = address.wrapping_add(cpu.register_y);
-            let value = cpu.peek_byte(incremented);
-
-            format!("${:04X},Y @ {:04X} = {:02X}", address, incremented, value)
-        }
-        AddressingMode::IndirectX => {
-            let incremented = value.wrapping_add(cpu.register_x);
-            let target_cell_value = cpu.peek_byte(target_address);
-
-            format!(
-                "(${:02X},X) @ {:02X} = {:04X} = {:02X}",
-                value, incremented, target_address, target_cell_value
-            )
-        }
-        AddressingMode::IndirectY => {
-            let address = target_address.wrapping_sub(cpu.register_y);
-            let target_cell_value = cpu.peek_byte(target_address);
-
-            format!(
-                "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
-                value, address, target_address, target_cell_value,
-            )
-        }
-        AddressingMode::Implied if opcode.name == "JSR" => {
-            // JSR uses Implied mode internally (to control cycle order) but displays like Absolute.
-            format!("${:04X}", address)
-        }
-        AddressingMode::Implied => String::new(),
-        AddressingMode::Accumulator => "A".into(),
-        AddressingMode::Relative => {
-            let offset = value.value().cast_signed();
-            #[allow(clippy::cast_sign_loss)]
-            let jump_address = cpu
-                .program_counter
-                .wrapping_add(2u16)
-                // NOTE: This is a quirky behaviour of what the NES CPU does
-                //       for branching instructions, and it's intended here.
-                //       We do 2 wrapping adds separately, since their sum itself
-                //       could overflow.
-                .wrapping_add(offset as u16);
-
-            format!("${jump_address:04X}")
-        }
-        AddressingMode::Indirect => {
-            format!("(${address:04X}) = {target_address:04X}")
-        }
-    };
 
-    Ok(format!("{} {opcode_asm_args}", opcode.name))
+    line
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
-    use sabi_nes_core::{Bus, Rom};
+    use sabi_nes_core::{Address, Bus, Memory, Result, Rom};
 
     #[test]
     fn trace_format() -> Result<()> {
@@ -194,7 +55,7 @@ mod tests {
 
         let mut traces = vec![];
         loop {
-            let t = trace(&mut cpu)?;
+            let t = trace(&cpu);
             let at_brk = t.contains("BRK");
             traces.push(t);
             cpu.step()?;
@@ -234,7 +95,7 @@ mod tests {
         cpu.program_counter = Address::new(0x64);
         cpu.register_y = 0x05.into();
 
-        let trace = trace(&mut cpu)?;
+        let trace = trace(&cpu);
         cpu.step()?;
 
         assert_eq!(
@@ -0,0 +1,121 @@
+//! blargg's cpu_interrupts_v2 test ROMs, one per interrupt timing quirk.
+//! ROM source: https://github.com/christopherpow/nes-test-roms/tree/master/cpu_interrupts_v2
+//!
+//! The ROMs aren't checked in yet, so the test is ignored. Copy `rom_singles/*.nes` into
+//! `tests/test_roms/cpu_interrupts_v2/` and run with `--ignored`; a missing ROM fails the test.
+//! Add `--nocapture` for the report of which sub-ROMs pass.
+
+use sabi_nes_core::{Address, Bus, Byte, Cpu, Rom};
+use std::path::Path;
+
+const ROM_DIR: &str = "tests/test_roms/cpu_interrupts_v2";
+const ROMS: [&str; 5] = [
+    "1-cli_latency.nes",
+    "2-nmi_and_brk.nes",
+    "3-nmi_and_irq.nes",
+    "4-irq_and_dma.nes",
+    "5-branch_delays_irq.nes",
+];
+
+/// Result code, $80 while the test runs, $81 when it wants the reset button pressed
+const STATUS: u16 = 0x6000;
+const RUNNING: u8 = 0x80;
+const NEEDS_RESET: u8 = 0x81;
+/// Written after the status once it's valid
+const SIGNATURE: u16 = 0x6001;
+const SIGNATURE_BYTES: [u8; 3] = [0xde, 0xb0, 0x61];
+/// Zero-terminated text of what the test printed
+const MESSAGE: u16 = 0x6004;
+/// The ROMs finish in a few seconds
+const FRAME_LIMIT: u64 = 60 * 30;
+/// blargg asks for the reset to be pressed at least 100 ms after the request
+const RESET_DELAY_FRAMES: u64 = 6;
+
+enum Outcome {
+    Missing,
+    Passed,
+    Failed { code: u8, message: String },
+    TimedOut,
+}
+
+impl Outcome {
+    fn describe(&self) -> String {
+        match self {
+            Self::Missing => "missing, see the module docs".to_owned(),
+            Self::Passed => "passed".to_owned(),
+            Self::Failed { code, message } => format!("failed ({code:#04x}): {message}"),
+            Self::TimedOut => format!("no result after {FRAME_LIMIT} frames"),
+        }
+    }
+}
+
+fn read_message(cpu: &Cpu) -> String {
+    (MESSAGE..)
+        .map(|address| cpu.peek_byte(Address::new(address)).value())
+        .take_while(|&byte| byte != 0)
+        .map(char::from)
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+fn has_signature(cpu: &Cpu) -> bool {
+    (SIGNATURE..)
+        .zip(SIGNATURE_BYTES)
+        .all(|(address, byte)| cpu.peek_byte(Address::new(address)) == Byte::new(byte))
+}
+
+fn run_rom(path: &Path) -> Outcome {
+    if !path.exists() {
+        return Outcome::Missing;
+    }
+
+    let rom = Rom::from_file(path).unwrap();
+    let mut cpu = Cpu::new(Bus::new(rom));
+    cpu.power_on().unwrap();
+
+    let mut reset_at = None;
+    while cpu.bus().frame_count() < FRAME_LIMIT {
+        cpu.step().unwrap();
+        if !has_signature(&cpu) {
+            continue;
+        }
+
+        let frame = cpu.bus().frame_count();
+        match cpu.peek_byte(Address::new(STATUS)).value() {
+            RUNNING => {}
+            NEEDS_RESET => match reset_at {
+                None => reset_at = Some(frame + RESET_DELAY_FRAMES),
+                Some(at) if frame >= at => {
+                    reset_at = None;
+                    cpu.reset().unwrap();
+                }
+                Some(_) => {}
+            },
+            0x00 => return Outcome::Passed,
+            code => {
+                return Outcome::Failed {
+                    code,
+                    message: read_message(&cpu),
+                };
+            }
+        }
+    }
+
+    Outcome::TimedOut
+}
+
+#[test]
+#[ignore = "needs the cpu_interrupts_v2 ROMs in tests/test_roms, see the module docs"]
+fn cpu_interrupts_v2() {
+    let mut failures = vec![];
+    for name in ROMS {
+        let outcome = run_rom(&Path::new(ROM_DIR).join(name));
+        eprintln!("{name:<24} {}", outcome.describe());
+        if !matches!(outcome, Outcome::Passed) {
+            failures.push(name);
+        }
+    }
+
+    assert!(failures.is_empty(), "failed: {failures:?}");
+}
@@ -0,0 +1,178 @@
+//! Klaus Dormann's 6502 functional test and Bruce Clark's decimal mode test, run on a bare
+//! 6502 with flat RAM. Sources: https://github.com/Klaus2m5/6502_65C02_functional_tests (GPL-3.0)
+//!
+//! The binaries aren't checked in yet, so the tests are ignored. Assemble them with the default
+//! options (NMOS 6502, `report` disabled) into `tests/test_roms/` and run with `--ignored`.
+//! Until then, decimal mode is checked against plain decimal arithmetic, the flags of the binary
+//! operation and worked examples of the NMOS N and V rules described in Bruce Clark's
+//! "Decimal Mode" tutorial (http://www.6502.org/tutorials/decimal_mode.html).
+
+use sabi_nes_core::{Address, Byte, Cpu, FlatMemory};
+
+const FUNCTIONAL_TEST: &str = "tests/test_roms/6502_functional_test.bin";
+const FUNCTIONAL_TEST_START: u16 = 0x0400;
+/// `jmp *` the functional test ends in once every test passed
+const FUNCTIONAL_TEST_SUCCESS: u16 = 0x3469;
+
+const DECIMAL_TEST: &str = "tests/test_roms/6502_decimal_test.bin";
+const DECIMAL_TEST_START: u16 = 0x0200;
+/// Zero page byte the decimal test leaves at 0 if all results matched
+const DECIMAL_TEST_ERROR: u16 = 0x000b;
+
+/// Run until the program traps itself in a jump or branch to itself, returning the trap address
+fn run_until_trap(cpu: &mut Cpu<FlatMemory>) -> Address {
+    loop {
+        let program_counter = cpu.program_counter;
+        cpu.step().unwrap();
+        if cpu.program_counter == program_counter || cpu.halted_at().is_some() {
+            return program_counter;
+        }
+    }
+}
+
+fn load_binary(path: &str, address: u16) -> Cpu<FlatMemory> {
+    let binary = std::fs::read(path)
+        .unwrap_or_else(|error| panic!("Failed to read `{path}`, see the module docs: {error}"));
+    let binary = binary.into_iter().map(Byte::new).collect::<Vec<_>>();
+
+    let mut cpu = Cpu::new(FlatMemory::new());
+    cpu.set_decimal_mode(true);
+    cpu.load(Address::new(address), &binary);
+
+    cpu
+}
+
+#[test]
+#[ignore = "needs tests/test_roms/6502_functional_test.bin, see the module docs"]
+fn functional_test() {
+    // The image covers the whole address space, vectors included
+    let mut cpu = load_binary(FUNCTIONAL_TEST, 0x0000);
+    cpu.program_counter = Address::new(FUNCTIONAL_TEST_START);
+
+    let trap = run_until_trap(&mut cpu);
+
+    assert_eq!(
+        trap, FUNCTIONAL_TEST_SUCCESS,
+        "trapped at ${trap:04X}, look it up in the listing"
+    );
+}
+
+#[test]
+#[ignore = "needs tests/test_roms/6502_decimal_test.bin, see the module docs"]
+fn decimal_test() {
+    let mut cpu = load_binary(DECIMAL_TEST, DECIMAL_TEST_START);
+    cpu.program_counter = Address::new(DECIMAL_TEST_START);
+
+    run_until_trap(&mut cpu);
+
+    assert_eq!(cpu.peek_byte(Address::new(DECIMAL_TEST_ERROR)), 0x00);
+}
+
+const ADC: u8 = 0x69;
+const SBC: u8 = 0xe9;
+
+const NEGATIVE: u8 = 0x80;
+const OVERFLOW: u8 = 0x40;
+const ZERO: u8 = 0x02;
+const CARRY: u8 = 0x01;
+
+/// 6502 with the decimal flag set, running ADC or SBC immediate
+struct Arithmetic {
+    cpu: Cpu<FlatMemory>,
+}
+
+impl Arithmetic {
+    // SED; CLC; SEC, then the instruction
+    const CLEAR_CARRY: u16 = 0x0201;
+    const INSTRUCTION: u16 = 0x0203;
+
+    fn new(decimal_mode: bool) -> Self {
+        let mut cpu = Cpu::new(FlatMemory::new());
+        cpu.set_decimal_mode(decimal_mode);
+        cpu.load(Address::new(0x0200), &[0xf8, 0x18, 0x38].map(Byte::new));
+        cpu.program_counter = Address::new(0x0200);
+        cpu.step().unwrap();
+
+        Self { cpu }
+    }
+
+    /// A and P after the instruction
+    fn run(&mut self, opcode: u8, a: u8, operand: u8, carry: bool) -> (u8, u8) {
+        let cpu = &mut self.cpu;
+        cpu.program_counter = Address::new(Self::CLEAR_CARRY + u16::from(carry));
+        cpu.step().unwrap();
+        cpu.load(
+            Address::new(Self::INSTRUCTION),
+            &[opcode, operand].map(Byte::new),
+        );
+        cpu.program_counter = Address::new(Self::INSTRUCTION);
+        cpu.accumulator = Byte::new(a);
+        cpu.step().unwrap();
+
+        (cpu.accumulator.value(), cpu.status_register.bits())
+    }
+}
+
+fn to_bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+#[test]
+fn decimal_mode_matches_decimal_arithmetic() {
+    let mut decimal = Arithmetic::new(true);
+    // The stock 2A03 ignores the decimal flag
+    let mut binary = Arithmetic::new(false);
+
+    for a in 0..100 {
+        for operand in 0..100 {
+            for carry in [false, true] {
+                let (bcd_a, bcd_operand) = (to_bcd(a), to_bcd(operand));
+                let case = format!("A=${bcd_a:02X}, operand=${bcd_operand:02X}, C={carry}");
+
+                // Z comes from the binary sum
+                let sum = a + operand + u8::from(carry);
+                let (result, status) = decimal.run(ADC, bcd_a, bcd_operand, carry);
+                let (_, binary_status) = binary.run(ADC, bcd_a, bcd_operand, carry);
+                assert_eq!(result, to_bcd(sum % 100), "ADC {case}");
+                assert_eq!(status & CARRY != 0, sum >= 100, "ADC {case}");
+                assert_eq!(status & ZERO, binary_status & ZERO, "ADC {case}");
+
+                // All flags come from the binary subtraction
+                let difference = i16::from(a) - i16::from(operand) - i16::from(!carry);
+                let expected = u8::try_from(difference.rem_euclid(100)).unwrap();
+                let (result, status) = decimal.run(SBC, bcd_a, bcd_operand, carry);
+                let (_, binary_status) = binary.run(SBC, bcd_a, bcd_operand, carry);
+                assert_eq!(result, to_bcd(expected), "SBC {case}");
+                assert_eq!(status & CARRY != 0, difference >= 0, "SBC {case}");
+                assert_eq!(status, binary_status, "SBC {case}");
+            }
+        }
+    }
+}
+
+#[test]
+fn decimal_adc_negative_and_overflow_come_from_the_unadjusted_sum() {
+    let mut decimal = Arithmetic::new(true);
+    // A, operand, then A and the flags among N, V, Z and C. N and V are those of the sum
+    // after the low digit is adjusted and before the high digit is.
+    let examples = [
+        // $20 + $50 + $10 = $80
+        (0x24, 0x56, 0x80, NEGATIVE | OVERFLOW),
+        // $90 + $80 + $05 = -$EB
+        (0x93, 0x82, 0x75, OVERFLOW | CARRY),
+        // $80 + $70 + $15 = $05
+        (0x89, 0x76, 0x65, CARRY),
+        // $90 + $00 + $10 = $A0, Z is clear as the binary sum is $9A
+        (0x99, 0x01, 0x00, NEGATIVE | CARRY),
+        (0x00, 0x00, 0x00, ZERO),
+    ];
+
+    for (a, operand, result, flags) in examples {
+        let (actual, status) = decimal.run(ADC, a, operand, false);
+        assert_eq!(
+            (actual, status & (NEGATIVE | OVERFLOW | ZERO | CARRY)),
+            (result, flags),
+            "${a:02X} + ${operand:02X}"
+        );
+    }
+}
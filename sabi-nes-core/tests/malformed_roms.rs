@@ -0,0 +1,147 @@
+//! Regression tests for ROM images with broken or unusual headers.
+//! None of these may panic - they either fail to load or run with mirrored/open-bus PRG.
+
+use sabi_nes_core::cartridge::mappers::Nrom256;
+use sabi_nes_core::cartridge::{CHR_ROM_BANK_SIZE, MirroringType, PRG_ROM_BANK_SIZE};
+use sabi_nes_core::{Address, Bus, Byte, Memory, Rom};
+
+fn rom_image(prg_rom_banks: u8, chr_rom_banks: u8, mapper_id: u8, prg_rom: &[u8]) -> Vec<u8> {
+    let mut image = vec![
+        0x4e,
+        0x45,
+        0x53,
+        0x1a,
+        prg_rom_banks,
+        chr_rom_banks,
+        mapper_id << 4,
+        mapper_id & 0xf0,
+    ];
+    image.resize(16, 0x00);
+    image.extend(prg_rom);
+    image.resize(
+        image.len() + usize::from(chr_rom_banks) * CHR_ROM_BANK_SIZE,
+        0x00,
+    );
+
+    image
+}
+
+/// PRG ROM where every 16kB bank is filled with its bank number
+fn numbered_banks(banks: usize) -> Vec<u8> {
+    (0..banks)
+        .flat_map(|bank| vec![bank as u8; PRG_ROM_BANK_SIZE])
+        .collect()
+}
+
+fn read(bus: &mut Bus, address: u16) -> Byte {
+    bus.read_byte(Address::new(address))
+}
+
+#[test]
+fn truncated_images_fail_to_load() {
+    let header_only = rom_image(2, 1, 0, &[]);
+    let truncated_prg = rom_image(2, 0, 0, &numbered_banks(1));
+    let mut truncated_chr = rom_image(1, 1, 0, &numbered_banks(1));
+    truncated_chr.truncate(truncated_chr.len() - 1);
+
+    assert!(Rom::from_bytes(&header_only[..10]).is_err());
+    assert!(Rom::from_bytes(&header_only).is_err());
+    assert!(Rom::from_bytes(&truncated_prg).is_err());
+    assert!(Rom::from_bytes(&truncated_chr).is_err());
+}
+
+#[test]
+fn missing_nes_tag_fails_to_load() {
+    let mut image = rom_image(1, 1, 0, &numbered_banks(1));
+    image[3] = 0x00;
+
+    assert!(Rom::from_bytes(&image).is_err());
+}
+
+#[test]
+fn no_prg_rom_reads_open_bus() {
+    let rom = Rom::from_bytes(&rom_image(0, 1, 0, &[])).unwrap();
+    let mut bus = Bus::new(rom);
+
+    // Reading RAM leaves its value on the data bus
+    bus.write_byte(Address::new(0x0000), Byte::new(0x42));
+    assert_eq!(read(&mut bus, 0x0000), 0x42);
+
+    assert_eq!(read(&mut bus, 0x8000), 0x42);
+    assert_eq!(read(&mut bus, 0xfffc), 0x42);
+}
+
+#[test]
+fn nrom_with_odd_prg_size_is_mirrored() {
+    // 48kB of PRG ROM doesn't fit the 32kB NROM window
+    let rom = Rom::from_bytes(&rom_image(3, 1, 0, &numbered_banks(3))).unwrap();
+    let mut bus = Bus::new(rom);
+
+    assert_eq!(read(&mut bus, 0x8000), 0);
+    assert_eq!(read(&mut bus, 0xc000), 1);
+    assert_eq!(read(&mut bus, 0xffff), 1);
+}
+
+#[test]
+fn undersized_prg_rom_reads_open_bus_past_the_image() {
+    // The PRG data is shorter than what the NROM-256 mapper expects
+    let rom = Rom::new(
+        vec![Byte::new(0xea); 0x1000],
+        vec![],
+        Box::new(Nrom256::default()),
+        MirroringType::Horizontal,
+    );
+    let mut bus = Bus::new(rom);
+
+    assert_eq!(read(&mut bus, 0x8000), 0xea);
+    assert_eq!(read(&mut bus, 0x8fff), 0xea);
+
+    // Past the image the data bus keeps the last value read, not a mirror of the dump
+    bus.write_byte(Address::new(0x0000), Byte::new(0x42));
+    for address in [0x9000, 0xc000, 0xfffc] {
+        assert_eq!(read(&mut bus, 0x0000), 0x42);
+        assert_eq!(read(&mut bus, address), 0x42);
+    }
+}
+
+#[test]
+fn mmc1_with_single_prg_bank_is_mirrored() {
+    let mut prg_rom = vec![0x5a; PRG_ROM_BANK_SIZE];
+    prg_rom[0x0000] = 0x11;
+    prg_rom[0x3fff] = 0x22;
+    let rom = Rom::from_bytes(&rom_image(1, 1, 1, &prg_rom)).unwrap();
+    let mut bus = Bus::new(rom);
+
+    // Switch to 32kB PRG mode: five serial writes of 0 to the control register
+    for _ in 0..5 {
+        bus.write_byte(Address::new(0x8000), Byte::new(0x00));
+    }
+    // Select bank 7 through the PRG bank register
+    for bit in 0..5 {
+        bus.write_byte(Address::new(0xe000), Byte::new((0x07 >> bit) & 1));
+    }
+
+    // Open bus would return the RAM byte read just before
+    bus.write_byte(Address::new(0x0000), Byte::new(0x42));
+    for (address, value) in [
+        (0x8000, 0x11),
+        (0xbfff, 0x22),
+        (0xc000, 0x11),
+        (0xffff, 0x22),
+    ] {
+        assert_eq!(read(&mut bus, 0x0000), 0x42);
+        assert_eq!(read(&mut bus, address), value);
+    }
+}
+
+#[test]
+fn mmc1_with_no_prg_rom_reads_open_bus() {
+    let rom = Rom::from_bytes(&rom_image(0, 0, 1, &[])).unwrap();
+    let mut bus = Bus::new(rom);
+
+    for address in [0x8000, 0xbfff, 0xc000, 0xffff] {
+        bus.write_byte(Address::new(0x0000), Byte::new(0x5a));
+        let _ = read(&mut bus, 0x0000);
+        assert_eq!(read(&mut bus, address), 0x5a);
+    }
+}
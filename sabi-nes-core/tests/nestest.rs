@@ -27,7 +27,7 @@ fn cpu_validation_test() {
     cpu.program_counter = Address::new(0xc000);
 
     let trace_step = || {
-        let trace = trace(&mut cpu).unwrap();
+        let trace = trace(&cpu);
         cpu.step().unwrap();
         trace
     };
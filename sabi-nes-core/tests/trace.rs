@@ -0,0 +1,89 @@
+use sabi_nes_core::disasm::Disassembler;
+use sabi_nes_core::trace::{TraceConditions, TraceFormat, TraceLogger, format_line};
+use sabi_nes_core::{Address, Bus, Cpu, Rom};
+
+fn nestest_cpu() -> Cpu {
+    let rom = Rom::from_file("tests/test_roms/nestest.nes").unwrap();
+    let mut cpu = Cpu::new(Bus::new(rom));
+    cpu.power_on().unwrap();
+    // Automation mode entry point, as in nestest.log
+    cpu.program_counter = Address::new(0xc000);
+
+    cpu
+}
+
+fn run(cpu: &mut Cpu, tracer: &mut TraceLogger, instructions: usize) {
+    for _ in 0..instructions {
+        tracer.log(cpu).unwrap();
+        cpu.step().unwrap();
+    }
+}
+
+#[test]
+fn nestest_format_matches_nestest_log() {
+    let mut cpu = nestest_cpu();
+    let mut tracer = TraceLogger::ring_buffer(8, TraceFormat::Nestest);
+    run(&mut cpu, &mut tracer, 3);
+
+    let expected = [
+        "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7",
+        "C5F5  A2 00     LDX #$00                        A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 30 CYC:10",
+        "C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 36 CYC:12",
+    ];
+    assert_eq!(tracer.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn other_formats() {
+    let mut cpu = nestest_cpu();
+    let line = format_line(&cpu, TraceFormat::Fceux, &Disassembler::default());
+    assert_eq!(
+        line,
+        "c7          A:00 X:00 Y:00 S:FD P:nvUbdIzc  $C000:4C F5 C5  JMP $C5F5"
+    );
+
+    cpu.step().unwrap();
+    let line = format_line(&cpu, TraceFormat::Mesen, &Disassembler::default());
+    assert_eq!(
+        line,
+        "C5F5  LDX #$00                         A:00 X:00 Y:00 S:FD P:nv--dIzc V:0   H:30  Fr:0 Cycle:10"
+    );
+}
+
+#[test]
+fn ring_buffer_keeps_the_last_lines() {
+    let mut cpu = nestest_cpu();
+    let mut tracer = TraceLogger::ring_buffer(2, TraceFormat::Nestest);
+    run(&mut cpu, &mut tracer, 3);
+
+    let lines: Vec<_> = tracer.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("C5F5"));
+    assert!(lines[1].starts_with("C5F7"));
+}
+
+#[test]
+fn conditions_select_instructions() {
+    let mut cpu = nestest_cpu();
+    let conditions = TraceConditions {
+        pc_range: Some(Address::new(0xc000)..=Address::new(0xc5f6)),
+        trigger: Some(Address::new(0xc5f5)),
+        ..TraceConditions::default()
+    };
+    let mut tracer = TraceLogger::ring_buffer(8, TraceFormat::Nestest).with_conditions(conditions);
+    run(&mut cpu, &mut tracer, 3);
+
+    // $C000 comes before the trigger and $C5F7 is out of range
+    let lines: Vec<_> = tracer.lines().collect();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("C5F5"));
+
+    let mut cpu = nestest_cpu();
+    let conditions = TraceConditions {
+        start_frame: Some(1),
+        ..TraceConditions::default()
+    };
+    let mut tracer = TraceLogger::ring_buffer(8, TraceFormat::Nestest).with_conditions(conditions);
+    run(&mut cpu, &mut tracer, 3);
+    assert_eq!(tracer.lines().count(), 0);
+}
@@ -0,0 +1,97 @@
+//! `sabi-nes-info`: prints what sabi-nes makes of ROM files, without running them.
+//!
+//! Takes ROM files and directories (searched recursively for `.nes`/`.unf`/`.unif` files)
+//! and prints the parsed header, hashes and whether the mapper is supported,
+//! either as text or as JSON.
+
+mod report;
+
+use crate::report::RomReport;
+use anyhow::{Result, bail};
+use clap::Parser;
+use sabi_nes_core::cartridge::HeaderParsing;
+use std::path::{Path, PathBuf};
+
+const ROM_EXTENSIONS: &[&str] = &["nes", "unf", "unif"];
+
+#[derive(Debug, Parser)]
+#[command(name = "sabi-nes-info", about = "Inspect NES ROM files")]
+struct Config {
+    /// ROM files or directories containing them
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Print a JSON array instead of text
+    #[arg(long = "json")]
+    json: bool,
+    /// Take headers as they are, without correcting them from the game database
+    #[arg(long = "strict")]
+    strict: bool,
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+
+    let config = Config::parse();
+    let parsing = match config.strict {
+        true => HeaderParsing::Strict,
+        false => HeaderParsing::Lenient,
+    };
+
+    let mut rom_paths = Vec::new();
+    for path in &config.paths {
+        collect_roms(path, &mut rom_paths)?;
+    }
+    let reports = rom_paths
+        .iter()
+        .map(|path| RomReport::from_file(path, parsing))
+        .collect::<Vec<_>>();
+
+    match config.json {
+        true => println!("{}", serde_json::to_string_pretty(&reports)?),
+        false => {
+            for report in &reports {
+                println!("{report}");
+            }
+        }
+    }
+
+    let failed = reports.iter().filter(|report| report.is_error()).count();
+    if failed > 0 {
+        bail!("{failed} of {} ROMs could not be read", reports.len());
+    }
+
+    Ok(())
+}
+
+/// Files given explicitly are always inspected, directories are searched for ROM files
+fn collect_roms(path: &Path, rom_paths: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        rom_paths.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            collect_roms(&entry, rom_paths)?;
+        } else if has_rom_extension(&entry) {
+            rom_paths.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+fn has_rom_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            ROM_EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
@@ -0,0 +1,287 @@
+use anyhow::Result;
+use sabi_nes_core::cartridge::checksum::{crc32, sha1};
+use sabi_nes_core::cartridge::mappers::{find_mapper, mapper_name};
+use sabi_nes_core::cartridge::{ConsoleType, HeaderFormat, HeaderParsing, RomImage, TimingRegion};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// What sabi-nes makes of a single ROM file
+#[derive(Debug, Serialize)]
+pub struct RomReport {
+    pub path: String,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Outcome {
+    Parsed(Box<RomInfo>),
+    Failed { error: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct RomInfo {
+    pub format: &'static str,
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mapper_name: Option<&'static str>,
+    /// Whether sabi-nes can run the ROM's mapper
+    pub supported: bool,
+    pub prg_rom_size: usize,
+    pub chr_rom_size: usize,
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub mirroring: String,
+    pub battery: bool,
+    pub trainer: bool,
+    pub region: &'static str,
+    pub console: String,
+    /// Title of the game database entry the header was corrected with
+    pub database_title: Option<&'static str>,
+    /// Hashes of the whole file
+    pub image: Hashes,
+    pub prg_rom: Hashes,
+    pub chr_rom: Hashes,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Hashes {
+    pub crc32: String,
+    pub sha1: String,
+}
+
+impl Hashes {
+    fn of(data: &[u8]) -> Self {
+        Self {
+            crc32: format!("{:08X}", crc32(data)),
+            sha1: sha1(data)
+                .iter()
+                .map(|byte| format!("{byte:02X}"))
+                .collect(),
+        }
+    }
+}
+
+impl RomReport {
+    pub fn from_file(path: &Path, parsing: HeaderParsing) -> Self {
+        let outcome = std::fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| RomInfo::from_bytes(&data, parsing));
+
+        Self {
+            path: path.display().to_string(),
+            outcome: match outcome {
+                Ok(info) => Outcome::Parsed(Box::new(info)),
+                Err(error) => Outcome::Failed {
+                    error: format!("{error:#}"),
+                },
+            },
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed { .. })
+    }
+}
+
+impl RomInfo {
+    pub fn from_bytes(data: &[u8], parsing: HeaderParsing) -> Result<Self> {
+        let image = RomImage::parse(data, parsing)?;
+        let header = &image.header;
+
+        Ok(Self {
+            format: match header.format {
+                HeaderFormat::INes => "iNES",
+                HeaderFormat::ArchaicINes => "archaic iNES",
+                HeaderFormat::Nes2 => "NES 2.0",
+                HeaderFormat::Unif => "UNIF",
+            },
+            mapper: header.mapper_id,
+            submapper: header.submapper,
+            mapper_name: mapper_name(header.mapper_id),
+            supported: find_mapper(header.mapper_id, header.submapper).is_some(),
+            prg_rom_size: header.prg_rom_size,
+            chr_rom_size: header.chr_rom_size,
+            prg_ram_size: header.prg_ram_size,
+            prg_nvram_size: header.prg_nvram_size,
+            chr_ram_size: header.chr_ram_size,
+            chr_nvram_size: header.chr_nvram_size,
+            mirroring: format!("{:?}", header.mirroring),
+            battery: header.has_battery,
+            trainer: image.trainer.is_some(),
+            region: match header.timing {
+                TimingRegion::Ntsc => "NTSC",
+                TimingRegion::Pal => "PAL",
+                TimingRegion::MultiRegion => "multi-region",
+                TimingRegion::Dendy => "Dendy",
+            },
+            console: match header.console_type {
+                ConsoleType::Nes => "NES".to_string(),
+                ConsoleType::VsSystem => "Vs. System".to_string(),
+                ConsoleType::Playchoice10 => "PlayChoice-10".to_string(),
+                ConsoleType::Extended(console) => format!("extended ({console})"),
+            },
+            database_title: image.game.map(|game| game.title),
+            image: Hashes::of(data),
+            prg_rom: Hashes::of(&image.prg_rom),
+            chr_rom: Hashes::of(&image.chr_rom),
+        })
+    }
+}
+
+impl Display for RomReport {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", self.path)?;
+        let info = match &self.outcome {
+            Outcome::Parsed(info) => info,
+            Outcome::Failed { error } => return writeln!(f, "  Error:      {error}"),
+        };
+
+        writeln!(f, "  Format:     {}", info.format)?;
+        write!(f, "  Mapper:     {}.{}", info.mapper, info.submapper)?;
+        if let Some(name) = info.mapper_name {
+            write!(f, " {name}")?;
+        }
+        match info.supported {
+            true => writeln!(f, " (supported)")?,
+            false => writeln!(f, " (not supported)")?,
+        }
+        writeln!(
+            f,
+            "  PRG ROM:    {:<8}  CRC32 {}  SHA-1 {}",
+            size(info.prg_rom_size),
+            info.prg_rom.crc32,
+            info.prg_rom.sha1
+        )?;
+        writeln!(
+            f,
+            "  CHR ROM:    {:<8}  CRC32 {}  SHA-1 {}",
+            size(info.chr_rom_size),
+            info.chr_rom.crc32,
+            info.chr_rom.sha1
+        )?;
+        writeln!(
+            f,
+            "  PRG RAM:    {}",
+            ram_size(info.prg_ram_size, info.prg_nvram_size)
+        )?;
+        writeln!(
+            f,
+            "  CHR RAM:    {}",
+            ram_size(info.chr_ram_size, info.chr_nvram_size)
+        )?;
+        writeln!(f, "  Mirroring:  {}", info.mirroring)?;
+        writeln!(f, "  Battery:    {}", yes_no(info.battery))?;
+        writeln!(f, "  Trainer:    {}", yes_no(info.trainer))?;
+        writeln!(f, "  Region:     {}", info.region)?;
+        writeln!(f, "  Console:    {}", info.console)?;
+        writeln!(
+            f,
+            "  Image:      CRC32 {}  SHA-1 {}",
+            info.image.crc32, info.image.sha1
+        )?;
+        if let Some(title) = info.database_title {
+            writeln!(f, "  Database:   {title}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn size(bytes: usize) -> String {
+    match bytes {
+        0 => "none".to_string(),
+        _ if bytes.is_multiple_of(1024) => format!("{} KiB", bytes / 1024),
+        _ => format!("{bytes} B"),
+    }
+}
+
+fn ram_size(volatile: usize, battery_backed: usize) -> String {
+    match battery_backed {
+        0 => size(volatile),
+        _ if volatile == 0 => format!("{} battery-backed", size(battery_backed)),
+        _ => format!(
+            "{} + {} battery-backed",
+            size(volatile),
+            size(battery_backed)
+        ),
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    match value {
+        true => "yes",
+        false => "no",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NESTEST: &str = "../sabi-nes-core/tests/test_roms/nestest.nes";
+
+    #[test]
+    fn nestest_report() {
+        let report = RomReport::from_file(Path::new(NESTEST), HeaderParsing::Lenient);
+        let Outcome::Parsed(info) = &report.outcome else {
+            panic!("nestest should parse: {report:?}");
+        };
+
+        assert_eq!(info.format, "iNES");
+        assert_eq!((info.mapper, info.submapper), (0, 0));
+        assert_eq!(info.mapper_name, Some("NROM"));
+        assert!(info.supported);
+        assert_eq!(info.prg_rom_size, 16384);
+        assert_eq!(info.database_title, Some("nestest"));
+        assert_eq!(
+            info.prg_rom,
+            Hashes::of(&std::fs::read(NESTEST).unwrap()[16..16400])
+        );
+        assert!(
+            report
+                .to_string()
+                .contains("Mapper:     0.0 NROM (supported)")
+        );
+    }
+
+    #[test]
+    fn unsupported_mapper_is_reported() {
+        let mut data = vec![0x4e, 0x45, 0x53, 0x1a, 0x01, 0x01, 0x50, 0x00];
+        data.resize(16 + 16384 + 8192, 0x00);
+
+        let info = RomInfo::from_bytes(&data, HeaderParsing::Strict).unwrap();
+
+        assert_eq!(info.mapper, 5);
+        assert_eq!(info.mapper_name, Some("MMC5"));
+        assert!(!info.supported);
+    }
+
+    #[test]
+    fn json_output() {
+        let report = RomReport {
+            path: "broken.nes".to_string(),
+            outcome: Outcome::Failed {
+                error: "not an iNES file".to_string(),
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r#"{"path":"broken.nes","error":"not an iNES file"}"#
+        );
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(size(0), "none");
+        assert_eq!(size(8192), "8 KiB");
+        assert_eq!(size(100), "100 B");
+        assert_eq!(ram_size(0, 8192), "8 KiB battery-backed");
+        assert_eq!(ram_size(2048, 0), "2 KiB");
+    }
+}
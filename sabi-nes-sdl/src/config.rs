@@ -1,10 +1,30 @@
 use clap::Parser;
+use sabi_nes_core::bus::RamInit;
+use sabi_nes_core::trace::TraceFormat;
 use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
 pub struct Config {
-    #[arg(long = "rom-path")]
-    pub rom_path: PathBuf,
+    #[arg(long = "rom-path", required_unless_present = "list_mappers")]
+    pub rom_path: Option<PathBuf>,
+    /// IPS/UPS/BPS patches applied in order (after any found next to the ROM)
+    #[arg(long = "patch")]
+    pub patches: Vec<PathBuf>,
+    /// Directory for battery-backed `.sav` files, defaults to the ROM's directory
+    #[arg(long = "save-dir")]
+    pub save_dir: Option<PathBuf>,
+    /// Power-on RAM contents: zeros, ones, random[:<seed>] or pattern:<hex bytes>
+    #[arg(default_value = "zeros", long = "ram-init")]
+    pub ram_init: RamInit,
+    /// Write an execution trace of every instruction to this file
+    #[arg(long = "trace")]
+    pub trace_path: Option<PathBuf>,
+    /// Trace layout: nestest, fceux or mesen
+    #[arg(default_value = "nestest", long = "trace-format")]
+    pub trace_format: TraceFormat,
+    /// Print the supported mappers and exit
+    #[arg(long = "list-mappers")]
+    pub list_mappers: bool,
     #[arg(default_value = "256", long = "width")]
     pub window_width: u32,
     #[arg(default_value = "240", long = "height")]
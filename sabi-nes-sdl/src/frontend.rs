@@ -2,14 +2,14 @@ use crate::Config;
 use anyhow::Error;
 use maplit::hashmap;
 use once_cell::sync::Lazy;
-use sabi_nes_core::Result;
 use sabi_nes_core::frontend::Frontend;
 use sabi_nes_core::input::joypad::{Joypad, JoypadButton};
 use sabi_nes_core::render::Frame;
+use sabi_nes_core::{Address, Result};
 use sdl2::EventPump;
 use sdl2::audio::{AudioQueue, AudioSpecDesired};
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::render::WindowCanvas;
 use std::collections::HashMap;
@@ -42,6 +42,10 @@ pub struct SdlFrontend {
     audio_queue: AudioQueue<f32>,
     fps_counter: u32,
     fps_timer: Instant,
+    /// Address of the JAM opcode that halted the CPU, shown in the window title
+    halted_at: Option<Address>,
+    /// Ctrl+R was pressed since the emulator last asked
+    reset_requested: bool,
 }
 
 impl SdlFrontend {
@@ -76,8 +80,18 @@ impl SdlFrontend {
             audio_queue,
             fps_counter: 0,
             fps_timer: Instant::now(),
+            halted_at: None,
+            reset_requested: false,
         })
     }
+
+    fn set_title(&mut self, fps: f64) {
+        let title = match self.halted_at {
+            Some(address) => format!("Sabi NES — {fps:.1} fps — CPU halted at ${address:04X}"),
+            None => format!("Sabi NES — {fps:.1} fps"),
+        };
+        let _ = self.canvas.window_mut().set_title(&title);
+    }
 }
 
 impl Frontend for SdlFrontend {
@@ -103,6 +117,14 @@ impl Frontend for SdlFrontend {
                     keycode: Some(Keycode::Escape),
                     ..
                 } => return Ok(false),
+                Event::KeyDown {
+                    keycode: Some(Keycode::R),
+                    keymod,
+                    repeat: false,
+                    ..
+                } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                    self.reset_requested = true;
+                }
                 Event::KeyDown {
                     keycode: Some(keycode),
                     ..
@@ -146,10 +168,7 @@ impl Frontend for SdlFrontend {
         let fps_elapsed = self.fps_timer.elapsed();
         if fps_elapsed >= Duration::from_secs(1) {
             let fps = self.fps_counter as f64 / fps_elapsed.as_secs_f64();
-            let _ = self
-                .canvas
-                .window_mut()
-                .set_title(&format!("Sabi NES — {fps:.1} fps"));
+            self.set_title(fps);
             self.fps_counter = 0;
             self.fps_timer = Instant::now();
         }
@@ -162,4 +181,19 @@ impl Frontend for SdlFrontend {
             let _ = self.audio_queue.queue_audio(samples);
         }
     }
+
+    fn cpu_halted(&mut self, address: Address) {
+        // Shown with the next FPS update
+        self.halted_at = Some(address);
+    }
+
+    fn take_reset_request(&mut self) -> bool {
+        let requested = std::mem::take(&mut self.reset_requested);
+        if requested {
+            // The reset brings a halted CPU back
+            self.halted_at = None;
+        }
+
+        requested
+    }
 }
@@ -3,8 +3,12 @@ mod frontend;
 
 use crate::config::Config;
 use crate::frontend::SdlFrontend;
+use anyhow::Context;
 use clap::Parser;
 use log::info;
+use sabi_nes_core::cartridge::mappers::SUPPORTED_MAPPERS;
+use sabi_nes_core::cartridge::patch::rom_patches;
+use sabi_nes_core::trace::TraceLogger;
 use sabi_nes_core::{Emulator, Result, Rom};
 
 fn main() -> Result<()> {
@@ -13,16 +17,41 @@ fn main() -> Result<()> {
     info!("Starting NES Emulator");
 
     let config = Config::parse();
-    let rom = Rom::from_file(&config.rom_path)?;
-    info!(
-        "Loaded ROM: `{}`",
-        config.rom_path.file_name().unwrap().display()
-    );
+    if config.list_mappers {
+        for mapper in SUPPORTED_MAPPERS {
+            println!("{mapper}");
+        }
+        return Ok(());
+    }
+
+    let rom_path = config.rom_path.as_ref().context("missing ROM path")?;
+    let patches = rom_patches(rom_path, &config.patches);
+    for patch in &patches {
+        info!("Applying patch `{}`", patch.display());
+    }
+    let rom = Rom::from_file_with_patches(rom_path, &patches)?;
+    info!("Loaded ROM: `{}`", rom_path.file_name().unwrap().display());
 
     let frontend = SdlFrontend::new(&config)?;
     info!("Initialised with SDL Frontend");
 
-    let mut emulator = Emulator::new(frontend, rom)?;
+    let mut emulator = Emulator::with_ram_init(frontend, rom, config.ram_init.clone())?;
+    emulator.attach_save_file(Rom::save_path(rom_path, config.save_dir.as_deref()))?;
+    if let Some(trace_path) = &config.trace_path {
+        emulator.set_tracer(TraceLogger::to_file(trace_path, config.trace_format)?);
+        info!("Tracing to `{}`", trace_path.display());
+    }
+    // Keep the save and the trace even if emulation stops on an error
+    let result = run(&mut emulator);
+    emulator.flush_save()?;
+    if let Some(mut tracer) = emulator.take_tracer() {
+        tracer.flush()?;
+    }
+
+    result
+}
+
+fn run(emulator: &mut Emulator<SdlFrontend>) -> Result<()> {
     while emulator.step_frame()? {}
 
     Ok(())